@@ -41,6 +41,8 @@ export interface AuctionInfo {
   endTime: number;
   startingPrice: bigint;
   currency: "Xlm" | "Usdc";
+  status: "Active" | "Finalized" | "Cancelled";
+  /** True once the auction is settled or cancelled. */
   finalized: boolean;
   escrowProvider: "Internal" | "TrustlessWork" | string | null;
   escrowSettled: boolean;
@@ -278,6 +280,10 @@ function mapAuctionInfoFromScVal(auctionData: Record<string, unknown>): AuctionI
   const currencyRaw = auctionData.currency;
   const currency: "Xlm" | "Usdc" =
     typeof currencyRaw === "string" && currencyRaw === "Usdc" ? "Usdc" : "Xlm";
+  // Unit enum variants decode as `["Variant"]`.
+  const statusRaw = Array.isArray(auctionData.status) ? auctionData.status[0] : auctionData.status;
+  const status: AuctionInfo["status"] =
+    statusRaw === "Finalized" || statusRaw === "Cancelled" ? statusRaw : "Active";
 
   return {
     tokenUri: String(auctionData.token_uri ?? ""),
@@ -288,7 +294,8 @@ function mapAuctionInfoFromScVal(auctionData: Record<string, unknown>): AuctionI
     endTime: Number(auctionData.end_time ?? 0),
     startingPrice: (auctionData.starting_price as bigint) ?? BigInt(0),
     currency,
-    finalized: Boolean(auctionData.finalized),
+    status,
+    finalized: status !== "Active",
     escrowProvider:
       typeof auctionData.escrow_provider === "string" ? (auctionData.escrow_provider as string) : null,
     escrowSettled: Boolean(auctionData.escrow_settled),
@@ -476,6 +483,12 @@ export async function buildCreateItemAuctionTx(
         // Currency enum variant: scvVec([scvSymbol("Xlm")]) or scvVec([scvSymbol("Usdc")])
         xdr.ScVal.scvVec([xdr.ScVal.scvSymbol(currency)]),
         nativeToScVal(durationSeconds, { type: "u64" }),
+        // AuctionOptions struct (map keys sorted): no reserve, no buy-now price
+        xdr.ScVal.scvMap([
+          new xdr.ScMapEntry({ key: xdr.ScVal.scvSymbol("buy_now_price"), val: xdr.ScVal.scvVoid() }),
+          new xdr.ScMapEntry({ key: xdr.ScVal.scvSymbol("reserve_hidden"), val: xdr.ScVal.scvBool(false) }),
+          new xdr.ScMapEntry({ key: xdr.ScVal.scvSymbol("reserve_price"), val: xdr.ScVal.scvVoid() }),
+        ]),
       ),
     )
     .setTimeout(300)
@@ -36,6 +36,8 @@ export interface ListingInfo {
   commissionEtaDays: number;
   isCommissionEgg: boolean;
   active: boolean;
+  /** Unix seconds after which the listing can't be bought; 0 never expires. */
+  expiresAt: number;
 }
 
 export interface SwapListing {
@@ -153,6 +155,7 @@ export async function fetchListings(): Promise<ListingInfo[]> {
           commissionEtaDays: (data.commission_eta_days as number) ?? 0,
           isCommissionEgg: Boolean(data.is_commission_egg),
           active: data.active as boolean,
+          expiresAt: Number(data.expires_at ?? 0),
         });
       } catch {
         // skip bad entries
@@ -344,7 +347,8 @@ export async function buildListForSaleTx(
   sellerPublicKey: string,
   tokenId: number,
   price: bigint,
-  currency: "Xlm" | "Usdc"
+  currency: "Xlm" | "Usdc",
+  expiresAt = 0
 ): Promise<string> {
   return buildMarketplaceTx(sellerPublicKey, "list_for_sale", [
     new Address(sellerPublicKey).toScVal(),
@@ -352,6 +356,21 @@ export async function buildListForSaleTx(
     nativeToScVal(price, { type: "i128" }),
     // Currency enum variant: scvVec([scvSymbol("Xlm")]) or scvVec([scvSymbol("Usdc")])
     xdr.ScVal.scvVec([xdr.ScVal.scvSymbol(currency)]),
+    nativeToScVal(expiresAt, { type: "u64" }),
+  ]);
+}
+
+export async function buildUpdateListingPriceTx(
+  sellerPublicKey: string,
+  listingId: number,
+  newPrice: bigint,
+  newCurrency: "Xlm" | "Usdc"
+): Promise<string> {
+  return buildMarketplaceTx(sellerPublicKey, "update_listing_price", [
+    new Address(sellerPublicKey).toScVal(),
+    nativeToScVal(listingId, { type: "u64" }),
+    nativeToScVal(newPrice, { type: "i128" }),
+    xdr.ScVal.scvVec([xdr.ScVal.scvSymbol(newCurrency)]),
   ]);
 }
 
@@ -77,21 +77,25 @@ export async function buildFreezeCreatorMetadataUpdatesTx(
 /// Permissionless: any creator can mint their own commission egg (self-service).
 export async function buildCreateCommissionEggTx(
   creatorPublicKey: string,
-  uri: string
+  uri: string,
+  royaltyBps = 0
 ): Promise<string> {
   return buildNftTx(creatorPublicKey, "create_commission_egg", [
     new Address(creatorPublicKey).toScVal(),
     nativeToScVal(uri, { type: "string" }),
+    nativeToScVal(royaltyBps, { type: "u32" }),
   ]);
 }
 
 /// Permissionless: any creator can mint their own finished NFT.
 export async function buildCreateFinishedNftTx(
   creatorPublicKey: string,
-  uri: string
+  uri: string,
+  royaltyBps = 0
 ): Promise<string> {
   return buildNftTx(creatorPublicKey, "create_finished_nft", [
     new Address(creatorPublicKey).toScVal(),
     nativeToScVal(uri, { type: "string" }),
+    nativeToScVal(royaltyBps, { type: "u32" }),
   ]);
 }
@@ -1,13 +1,92 @@
 #![no_std]
 
-use soroban_sdk::{contract, contractimpl, contracttype, token, Address, Env, IntoVal, String, Symbol};
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, panic_with_error, symbol_short, token,
+    xdr::ToXdr, Address, Bytes, BytesN, Env, IntoVal, Map, String, Symbol, TryFromVal, Val, Vec,
+};
+pub use shimeji_common::{PauseFlag, Role};
 
 #[cfg(test)]
 const AUCTION_DURATION: u64 = 604_800; // 7 days in seconds
 const MIN_AUCTION_DURATION: u64 = 3_600; // 1 hour
 const MAX_AUCTION_DURATION: u64 = 2_592_000; // 30 days
 const MIN_INCREMENT_BPS: i128 = 500; // 5% minimum bid increment
+const MAX_FEE_BPS: u32 = 1_000; // platform fee is capped at 10%
+const DEFAULT_SOFT_CLOSE_WINDOW: u64 = 600; // 10 minutes
+const DEFAULT_SOFT_CLOSE_EXTENSION: u64 = 600; // 10 minutes
+const DEFAULT_MAX_SOFT_CLOSE_EXTENSION: u64 = 86_400; // 1 day
+const DEFAULT_BID_HISTORY_LIMIT: u32 = 100;
+const MAX_BID_HISTORY_LIMIT: u32 = 500;
+const MAX_PAGE_LIMIT: u32 = 100;
+const MAX_DROP_EDITIONS: u32 = 20; // each edition is minted in the `finalize` call
+const MAX_ROYALTY_BPS: u32 = 1_000; // same cap as shimeji-nft
+const DEFAULT_ORACLE_MAX_AGE: u64 = 900; // Reflector publishes every 5 minutes
 const TOKEN_SCALE: i128 = 10_000_000; // 7 decimals
+/// Storage layout version written by this code. Bump it together with a new
+/// step in `migrate` whenever a release changes how existing records are stored.
+const SCHEMA_VERSION: u32 = 4;
+/// Ledgers close roughly every 5 seconds.
+const DAY_IN_LEDGERS: u32 = 17_280;
+/// Entries touched by a call are topped back up to ~30 days of TTL once they
+/// drop under ~29 days, so anything in active use never nears archival.
+const TTL_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const TTL_THRESHOLD: u32 = TTL_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum AuctionError {
+    AlreadyInitialized = 1,
+    AuctionNotFound = 2,
+    AlreadyFinalized = 3,
+    DurationTooShort = 4,
+    DurationTooLong = 5,
+    AuctionEnded = 6,
+    AuctionNotEnded = 7,
+    BidBelowMinimum = 8,
+    BidIncrementTooLow = 9,
+    NoBids = 10,
+    OracleNotConfigured = 11,
+    NotTokenOwner = 12,
+    EscrowDestinationNotConfigured = 13,
+    UnsupportedSchemaVersion = 14,
+    NoPendingAdmin = 15,
+    AdminRoleNotGrantable = 16,
+    Paused = 17,
+    NotPauser = 18,
+    InvalidReservePrice = 19,
+    InvalidSoftClose = 20,
+    InvalidBuyNowPrice = 21,
+    BuyNowUnavailable = 22,
+    InvalidOracleRate = 23,
+    InvalidDutchSchedule = 24,
+    WrongAuctionFormat = 25,
+    InvalidSealedRules = 26,
+    AlreadyCommitted = 27,
+    NoCommitment = 28,
+    NotRevealPhase = 29,
+    RevealMismatch = 30,
+    DepositTooLow = 31,
+    DepositAlreadyReleased = 32,
+    NotFinalized = 33,
+    NotSeller = 34,
+    AuctionHasBids = 35,
+    AuctionCancelled = 36,
+    InvalidBidHistoryLimit = 37,
+    NothingToClaim = 38,
+    StaleOraclePrice = 39,
+    OraclePriceOutOfBounds = 40,
+    InvalidOracleGuard = 41,
+    NotDropCreator = 42,
+    InvalidEditionSize = 43,
+    CurrencyMismatch = 44,
+    InvalidFee = 45,
+    NotTreasurer = 46,
+    NoFeesAccrued = 47,
+    WithdrawalExceedsFreeBalance = 48,
+    InvalidRoyalty = 49,
+    HeldFundsUnderflow = 50,
+}
 
 /// Oracle asset descriptor (Reflector protocol).
 #[contracttype]
@@ -25,6 +104,19 @@ pub struct OraclePriceData {
     pub timestamp: u64,
 }
 
+/// Sanity checks applied to every Reflector price before it is used.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct OracleGuard {
+    /// Oldest price accepted, in seconds behind the current ledger time.
+    pub max_age_seconds: u64,
+    /// Accepted XLM price range, in USDC at TOKEN_SCALE (10^7).
+    pub min_rate: i128,
+    pub max_rate: i128,
+    /// Average this many records through `twap`; 0 or 1 uses `lastprice` alone.
+    pub twap_records: u32,
+}
+
 #[contracttype]
 #[derive(Clone, Debug, PartialEq)]
 pub enum Currency {
@@ -39,9 +131,172 @@ pub enum EscrowProvider {
     TrustlessWork,
 }
 
+/// Anti-sniping rule: a bid accepted less than `window_seconds` before the end
+/// pushes the end out by `extension_seconds`, until `max_extension_seconds` in
+/// total have been added. A zero window turns it off.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoftClose {
+    pub window_seconds: u64,
+    pub extension_seconds: u64,
+    pub max_extension_seconds: u64,
+}
+
+/// Dutch price schedule: the price falls from `starting_price` to `floor_price`
+/// over the auction. `step_seconds == 0` decays every second (linear); otherwise
+/// the price drops in equal steps every `step_seconds`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DutchSchedule {
+    pub floor_price: i128,
+    pub step_seconds: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SealedPricing {
+    /// The winner pays their own bid.
+    FirstPrice,
+    /// Vickrey: the winner pays the second-highest revealed bid (or the starting price).
+    SecondPrice,
+}
+
+/// Sealed-bid rules. Bids are committed until `end_time`, then revealed for
+/// `reveal_seconds`. A bidder who never reveals forfeits `slash_bps` of their
+/// deposit to the seller and gets the rest back.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SealedBidRules {
+    pub reveal_seconds: u64,
+    pub pricing: SealedPricing,
+    pub slash_bps: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub enum AuctionFormat {
+    /// Ascending bids; the highest bid wins at `finalize`.
+    English,
+    /// Descending price; the first buyer wins immediately.
+    Dutch(DutchSchedule),
+    /// Commit-reveal bids; the highest revealed bid wins at `finalize`.
+    Sealed(SealedBidRules),
+    /// Primary sale of this many editions of `token_uri`; the top bids each win one.
+    Drop(u32),
+}
+
+/// What a drop sells: `edition_size` copies, each paying its creator
+/// `royalty_bps` (max 10%) on resales.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DropTerms {
+    pub edition_size: u32,
+    pub royalty_bps: u32,
+}
+
+/// A committed sealed bid. The deposit is in the auction currency and must
+/// cover the revealed amount, so over-depositing hides the real bid.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct SealedBid {
+    pub commitment: BytesN<32>,
+    pub deposit: i128,
+    pub revealed_amount: Option<i128>,
+    pub released: bool,
+}
+
+/// Platform take rate on auction sales. Fees stay in the contract, accrued per
+/// currency, until `collect_fees` sends them to `treasury`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeeConfig {
+    pub treasury: Address,
+    pub auction_bps: u32,
+}
+
+/// Optional pricing rules for `create_item_auction`; all prices are in the auction currency.
+#[contracttype]
+#[derive(Clone, Debug, Default)]
+pub struct AuctionOptions {
+    /// Lowest winning bid; must be at least the starting price.
+    pub reserve_price: Option<i128>,
+    /// Keep `reserve_price` out of `get_auction` until a bid reaches it.
+    pub reserve_hidden: bool,
+    /// Price at which anyone can end the auction early; must clear the start and the reserve.
+    pub buy_now_price: Option<i128>,
+}
+
+/// Where an auction is in its life. Only `Active` auctions take bids or settle.
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AuctionStatus {
+    Active,
+    /// Settled by `finalize` or a buy-now; a winner, if any, has been paid out.
+    Finalized,
+    /// Closed early by the seller or the admin: nothing settles and there is no winner.
+    Cancelled,
+}
+
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct AuctionInfo {
+    pub token_uri: String,
+    pub is_item_auction: bool,
+    pub seller: Address,
+    pub token_id: u64,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub starting_price: i128,
+    pub currency: Currency,
+    pub status: AuctionStatus,
+    pub escrow_provider: EscrowProvider,
+    pub escrow_settled: bool,
+    /// Lowest winning bid, in `currency`. `None` means any valid bid wins.
+    pub reserve_price: Option<i128>,
+    /// Keeps `reserve_price` out of `get_auction` until a bid reaches it. The
+    /// ledger entry itself is public, so this hides it from UIs, not from chain readers.
+    pub reserve_hidden: bool,
+    /// Soft-close rule copied from the contract config when the auction was created.
+    pub soft_close: SoftClose,
+    /// Seconds soft close has added to `end_time` so far.
+    pub extended_seconds: u64,
+    /// Most recent bids kept in the log; older ones are overwritten.
+    pub bid_history_limit: u32,
+    /// Price, in `currency`, at which anyone can end the auction early and take the item.
+    pub buy_now_price: Option<i128>,
+    pub format: AuctionFormat,
+}
+
+/// `AuctionInfo` as stored by schema v2, with `finalized` and `cancelled` flags
+/// instead of a status. Only read by `load_auction`.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct AuctionInfoV2 {
+    pub token_uri: String,
+    pub is_item_auction: bool,
+    pub seller: Address,
+    pub token_id: u64,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub starting_price: i128,
+    pub currency: Currency,
+    pub finalized: bool,
+    pub escrow_provider: EscrowProvider,
+    pub escrow_settled: bool,
+    pub cancelled: bool,
+    pub reserve_price: Option<i128>,
+    pub reserve_hidden: bool,
+    pub soft_close: SoftClose,
+    pub extended_seconds: u64,
+    pub bid_history_limit: u32,
+    pub buy_now_price: Option<i128>,
+    pub format: AuctionFormat,
+}
+
+/// `AuctionInfo` as stored by schema v1, before reserve prices. Only read by `load_auction`.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct AuctionInfoV1 {
     pub token_uri: String,
     pub is_item_auction: bool,
     pub seller: Address,
@@ -63,6 +318,19 @@ pub struct BidInfo {
     pub currency: Currency,
 }
 
+/// One entry of an auction's bid log.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct BidRecord {
+    pub bidder: Address,
+    pub amount: i128,
+    pub currency: Currency,
+    /// `amount` in USDC at the oracle rate when the bid was placed; `None` for
+    /// XLM bids while no oracle is configured.
+    pub usdc_value: Option<i128>,
+    pub timestamp: u64,
+}
+
 #[contracttype]
 pub enum DataKey {
     Admin,
@@ -76,38 +344,112 @@ pub enum DataKey {
     NextAuctionId,
     Auction(u64),
     HighestBid(u64),
+    SchemaVersion,
+    PendingAdmin,
+    RoleMember(Role, Address),
+    Paused(PauseFlag),
+    SoftClose,
+    SealedBid(u64, Address),
+    SecondBid(u64),
+    CommitCount(u64),
+    MaxBidHistory,
+    BidCount(u64),
+    BidLog(u64, u32),
+    PushRefunds,
+    PendingRefund(Address, Currency),
+    OracleGuard,
+    DropBids(u64),
+    DropRoyalty(u64),
+    FeeConfig,
+    AccruedFees(Currency),
+    HeldFunds(Currency),
 }
 
 #[contract]
 pub struct ShimejiAuction;
 
+/// The XLM/USDC rate for one contract call. The oracle is only asked the first
+/// time a conversion needs it, so every amount in the call is compared at the same
+/// rate and at most one `xlm_rate` event is published.
+#[derive(Default)]
+struct XlmRate(Option<Result<i128, AuctionError>>);
+
+impl XlmRate {
+    fn get(&mut self, env: &Env) -> Result<i128, AuctionError> {
+        *self.0.get_or_insert_with(|| ShimejiAuction::get_xlm_usdc_rate(env))
+    }
+}
+
 impl ShimejiAuction {
     /// Normalize `amount` in `currency` to USDC equivalent using the Reflector oracle.
     /// Only calls the oracle when `currency` is XLM.
-    fn normalize_to_usdc(env: &Env, amount: i128, currency: &Currency) -> i128 {
+    fn normalize_to_usdc(
+        env: &Env,
+        rate: &mut XlmRate,
+        amount: i128,
+        currency: &Currency,
+    ) -> Result<i128, AuctionError> {
         match currency {
-            Currency::Usdc => amount,
+            Currency::Usdc => Ok(amount),
             Currency::Xlm => {
-                let rate = Self::get_xlm_usdc_rate(env)
-                    .unwrap_or_else(|| panic!("oracle not configured for cross-currency comparison"));
-                amount * rate / TOKEN_SCALE
+                let rate = rate.get(env)?;
+                Ok(amount * rate / TOKEN_SCALE)
             }
         }
     }
 
     /// Fetch XLM/USDC rate from Reflector oracle.
-    /// Returns XLM price expressed in USDC at TOKEN_SCALE (10^7), or None if no oracle configured.
-    fn get_xlm_usdc_rate(env: &Env) -> Option<i128> {
-        let oracle: Option<Address> = env.storage().instance().get(&DataKey::OracleContract);
-        let oracle = oracle?;
+    /// Returns XLM price expressed in USDC at TOKEN_SCALE (10^7). Prices older than
+    /// the oracle guard allows or outside its bounds are rejected, and the rate
+    /// used is published as an `xlm_rate` event.
+    fn get_xlm_usdc_rate(env: &Env) -> Result<i128, AuctionError> {
+        let oracle: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::OracleContract)
+            .ok_or(AuctionError::OracleNotConfigured)?;
+        let guard = Self::current_oracle_guard(env);
         let asset = OracleAsset::Other(Symbol::new(env, "XLM"));
         let data: Option<OraclePriceData> = env.invoke_contract(
             &oracle,
             &Symbol::new(env, "lastprice"),
-            (asset,).into_val(env),
+            (asset.clone(),).into_val(env),
         );
+        let data = data.ok_or(AuctionError::InvalidOracleRate)?;
+        if env.ledger().timestamp().saturating_sub(data.timestamp) > guard.max_age_seconds {
+            return Err(AuctionError::StaleOraclePrice);
+        }
+
+        let price = if guard.twap_records > 1 {
+            let twap: Option<i128> = env.invoke_contract(
+                &oracle,
+                &Symbol::new(env, "twap"),
+                (asset, guard.twap_records).into_val(env),
+            );
+            twap.ok_or(AuctionError::StaleOraclePrice)?
+        } else {
+            data.price
+        };
         // Reflector returns price at 10^14 scale; convert to 10^7 by dividing by 10^7
-        data.map(|pd| pd.price / TOKEN_SCALE)
+        let rate = price / TOKEN_SCALE;
+        if rate < guard.min_rate || rate > guard.max_rate {
+            return Err(AuctionError::OraclePriceOutOfBounds);
+        }
+        env.events()
+            .publish((symbol_short!("xlm_rate"),), (rate, data.timestamp));
+        Ok(rate)
+    }
+
+    fn current_oracle_guard(env: &Env) -> OracleGuard {
+        env.storage()
+            .instance()
+            .get(&DataKey::OracleGuard)
+            .unwrap_or(OracleGuard {
+                max_age_seconds: DEFAULT_ORACLE_MAX_AGE,
+                min_rate: 1,
+                max_rate: i128::MAX,
+                twap_records: 0,
+            })
     }
 
     fn require_admin(env: &Env) -> Address {
@@ -116,6 +458,61 @@ impl ShimejiAuction {
         admin
     }
 
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(TTL_THRESHOLD, TTL_BUMP_AMOUNT);
+    }
+
+    /// Reads a persistent entry and, if it exists, tops its TTL back up.
+    fn read_entry<V: TryFromVal<Env, Val>>(env: &Env, key: &DataKey) -> Option<V> {
+        let value = env.storage().persistent().get(key);
+        if value.is_some() {
+            env.storage()
+                .persistent()
+                .extend_ttl(key, TTL_THRESHOLD, TTL_BUMP_AMOUNT);
+            Self::extend_instance(env);
+        }
+        value
+    }
+
+    /// Writes a persistent entry with a full TTL.
+    fn write_entry<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+        env.storage().persistent().set(key, value);
+        env.storage()
+            .persistent()
+            .extend_ttl(key, TTL_THRESHOLD, TTL_BUMP_AMOUNT);
+        Self::extend_instance(env);
+    }
+
+    /// Tops up `key`'s TTL if it exists; returns whether it did.
+    fn extend_entry(env: &Env, key: &DataKey) -> bool {
+        if !env.storage().persistent().has(key) {
+            return false;
+        }
+        env.storage()
+            .persistent()
+            .extend_ttl(key, TTL_THRESHOLD, TTL_BUMP_AMOUNT);
+        true
+    }
+
+    fn require_pauser(env: &Env, caller: &Address) -> Result<(), AuctionError> {
+        caller.require_auth();
+        if !Self::has_role(env.clone(), Role::Admin, caller.clone())
+            && !Self::has_role(env.clone(), Role::Pauser, caller.clone())
+        {
+            return Err(AuctionError::NotPauser);
+        }
+        Ok(())
+    }
+
+    fn require_not_paused(env: &Env, flag: PauseFlag) -> Result<(), AuctionError> {
+        if env.storage().instance().has(&DataKey::Paused(flag)) {
+            return Err(AuctionError::Paused);
+        }
+        Ok(())
+    }
+
     fn current_escrow_provider(env: &Env) -> EscrowProvider {
         env.storage()
             .instance()
@@ -123,7 +520,10 @@ impl ShimejiAuction {
             .unwrap_or(EscrowProvider::Internal)
     }
 
-    fn trustless_escrow_destination(env: &Env, currency: &Currency) -> Address {
+    fn trustless_escrow_destination(
+        env: &Env,
+        currency: &Currency,
+    ) -> Result<Address, AuctionError> {
         let key = match currency {
             Currency::Xlm => DataKey::TrustlessEscrowXlm,
             Currency::Usdc => DataKey::TrustlessEscrowUsdc,
@@ -131,869 +531,4134 @@ impl ShimejiAuction {
         env.storage()
             .instance()
             .get(&key)
-            .unwrap_or_else(|| panic!("trustless escrow destination not configured"))
+            .ok_or(AuctionError::EscrowDestinationNotConfigured)
     }
 
-    fn nft_contract(env: &Env) -> Address {
-        env.storage().instance().get(&DataKey::NftContract).unwrap()
+    /// Returns a losing bid to its bidder in the currency it was placed in. By
+    /// default the amount is credited for `claim_refund`; with push refunds on it
+    /// is sent straight back, falling back to a credit if that transfer fails so
+    /// one unreachable bidder cannot block the auction.
+    fn refund_bid(env: &Env, auction_id: u64, bid: &BidInfo) {
+        let push: bool = env.storage().instance().get(&DataKey::PushRefunds).unwrap_or(false);
+        if push {
+            let token_addr = match bid.currency {
+                Currency::Xlm => env.storage().instance().get::<DataKey, Address>(&DataKey::XlmToken).unwrap(),
+                Currency::Usdc => env.storage().instance().get::<DataKey, Address>(&DataKey::UsdcToken).unwrap(),
+            };
+            let token_client = token::Client::new(env, &token_addr);
+            let sent =
+                token_client.try_transfer(&env.current_contract_address(), &bid.bidder, &bid.amount);
+            if matches!(sent, Ok(Ok(()))) {
+                Self::adjust_held(env, &bid.currency, -bid.amount);
+                env.events().publish(
+                    (symbol_short!("refund"), auction_id, bid.bidder.clone()),
+                    (bid.amount, bid.currency.clone()),
+                );
+                return;
+            }
+        }
+
+        let key = DataKey::PendingRefund(bid.bidder.clone(), bid.currency.clone());
+        let pending: i128 = Self::read_entry(env, &key).unwrap_or(0);
+        Self::write_entry(env, &key, &(pending + bid.amount));
+        env.events().publish(
+            (symbol_short!("credited"), auction_id, bid.bidder.clone()),
+            (bid.amount, bid.currency.clone()),
+        );
     }
 
-    fn validate_auction_duration(duration_seconds: u64) {
-        if duration_seconds < MIN_AUCTION_DURATION {
-            panic!("auction duration too short");
+    /// Whether `bid` clears the auction's reserve; cross-currency bids are compared in USDC.
+    fn reserve_reached(
+        env: &Env,
+        rate: &mut XlmRate,
+        auction: &AuctionInfo,
+        bid: Option<&BidInfo>,
+    ) -> Result<bool, AuctionError> {
+        let Some(reserve) = auction.reserve_price else {
+            return Ok(true);
+        };
+        let Some(bid) = bid else {
+            return Ok(false);
+        };
+        if bid.currency == auction.currency {
+            return Ok(bid.amount >= reserve);
         }
-        if duration_seconds > MAX_AUCTION_DURATION {
-            panic!("auction duration too long");
+        let bid_usdc = Self::normalize_to_usdc(env, rate, bid.amount, &bid.currency)?;
+        let reserve_usdc = Self::normalize_to_usdc(env, rate, reserve, &auction.currency)?;
+        Ok(bid_usdc >= reserve_usdc)
+    }
+
+    /// Hands a custodied item to the winner and pays out the winning amount:
+    /// creator royalty directly, the seller's share along the auction's escrow route.
+    fn settle_item_sale(
+        env: &Env,
+        auction: &AuctionInfo,
+        winning_bid: &BidInfo,
+    ) -> Result<(), AuctionError> {
+        Self::nft_transfer(
+            env,
+            &env.current_contract_address(),
+            &winning_bid.bidder,
+            auction.token_id,
+        );
+
+        let token_addr = match winning_bid.currency {
+            Currency::Xlm => env.storage().instance().get::<DataKey, Address>(&DataKey::XlmToken).unwrap(),
+            Currency::Usdc => env.storage().instance().get::<DataKey, Address>(&DataKey::UsdcToken).unwrap(),
+        };
+        let token_client = token::Client::new(env, &token_addr);
+
+        // Creator royalty is paid out directly; the seller's share follows the escrow route.
+        let (royalty_receiver, royalty) =
+            Self::nft_royalty_info(env, auction.token_id, winning_bid.amount);
+        let fee = Self::take_fee(env, &winning_bid.currency, winning_bid.amount);
+        let mut seller_amount = winning_bid.amount - fee;
+        if royalty > 0 && royalty_receiver != auction.seller {
+            token_client.transfer(&env.current_contract_address(), &royalty_receiver, &royalty);
+            Self::adjust_held(env, &winning_bid.currency, -royalty);
+            seller_amount -= royalty;
         }
+        Self::pay_seller(env, auction, &winning_bid.currency, seller_amount)
     }
 
-    fn nft_owner_of(env: &Env, token_id: u64) -> Address {
-        let nft_contract = Self::nft_contract(env);
-        env.invoke_contract::<Address>(
-            &nft_contract,
-            &Symbol::new(env, "owner_of"),
-            (token_id,).into_val(env),
-        )
+    /// Books the platform fee on a sale of `amount` as accrued and returns it.
+    /// The fee itself never leaves the contract until `collect_fees`.
+    fn take_fee(env: &Env, currency: &Currency, amount: i128) -> i128 {
+        let config: Option<FeeConfig> = env.storage().instance().get(&DataKey::FeeConfig);
+        let fee = match config {
+            Some(config) => amount * config.auction_bps as i128 / 10_000,
+            None => 0,
+        };
+        if fee > 0 {
+            let key = DataKey::AccruedFees(currency.clone());
+            let accrued: i128 = env.storage().instance().get(&key).unwrap_or(0);
+            env.storage().instance().set(&key, &(accrued + fee));
+        }
+        fee
     }
 
-    fn nft_token_uri(env: &Env, token_id: u64) -> String {
-        let nft_contract = Self::nft_contract(env);
-        env.invoke_contract::<String>(
-            &nft_contract,
-            &Symbol::new(env, "token_uri"),
-            (token_id,).into_val(env),
-        )
+    /// Sends the seller's proceeds along the auction's escrow route.
+    fn pay_seller(
+        env: &Env,
+        auction: &AuctionInfo,
+        currency: &Currency,
+        amount: i128,
+    ) -> Result<(), AuctionError> {
+        let token_addr = match currency {
+            Currency::Xlm => env.storage().instance().get::<DataKey, Address>(&DataKey::XlmToken).unwrap(),
+            Currency::Usdc => env.storage().instance().get::<DataKey, Address>(&DataKey::UsdcToken).unwrap(),
+        };
+        let token_client = token::Client::new(env, &token_addr);
+        if auction.escrow_provider == EscrowProvider::TrustlessWork {
+            let destination = Self::trustless_escrow_destination(env, currency)?;
+            token_client.transfer(&env.current_contract_address(), &destination, &amount);
+        } else {
+            token_client.transfer(&env.current_contract_address(), &auction.seller, &amount);
+        }
+        Self::adjust_held(env, currency, -amount);
+        Ok(())
     }
 
-    fn nft_transfer(env: &Env, from: &Address, to: &Address, token_id: u64) {
-        let nft_contract = Self::nft_contract(env);
-        env.invoke_contract::<()>(
-            &nft_contract,
-            &Symbol::new(env, "transfer"),
-            (from.clone(), to.clone(), token_id).into_val(env),
-        );
+    /// Moves the running total of what the contract holds for others in `currency`:
+    /// standing bids, sealed deposits, credited refunds and accrued fees. Admin
+    /// withdrawals can only reach the balance above it. Every payout was counted in
+    /// when it came in (or by `migrate`), so going below zero means the books are
+    /// wrong and the call traps with `HeldFundsUnderflow`.
+    fn adjust_held(env: &Env, currency: &Currency, delta: i128) {
+        let key = DataKey::HeldFunds(currency.clone());
+        let held: i128 = env.storage().instance().get(&key).unwrap_or(0);
+        if held + delta < 0 {
+            panic_with_error!(env, AuctionError::HeldFundsUnderflow);
+        }
+        env.storage().instance().set(&key, &(held + delta));
     }
-}
 
-#[contractimpl]
-impl ShimejiAuction {
-    pub fn initialize(env: Env, admin: Address, nft_contract: Address, usdc_token: Address, xlm_token: Address) {
-        if env.storage().instance().has(&DataKey::Admin) {
-            panic!("already initialized");
+    /// Rebuilds `HeldFunds` from every record that holds money for someone else:
+    /// accrued fees, the standing bids of open auctions, open drop bid books,
+    /// unreleased sealed deposits and credited refunds. The last two are keyed by
+    /// bidder and cannot be listed on chain, so only `bidders` are looked up.
+    fn seed_held_funds(env: &Env, bidders: &Vec<Address>) {
+        let mut xlm = Self::accrued_fees(env.clone(), Currency::Xlm);
+        let mut usdc = Self::accrued_fees(env.clone(), Currency::Usdc);
+        let mut add = |currency: &Currency, amount: i128| match currency {
+            Currency::Xlm => xlm += amount,
+            Currency::Usdc => usdc += amount,
+        };
+
+        let mut distinct: Vec<Address> = Vec::new(env);
+        for bidder in bidders.iter() {
+            if distinct.contains(&bidder) {
+                continue;
+            }
+            for currency in [Currency::Xlm, Currency::Usdc] {
+                let key = DataKey::PendingRefund(bidder.clone(), currency.clone());
+                add(&currency, Self::read_entry(env, &key).unwrap_or(0));
+            }
+            distinct.push_back(bidder);
         }
-        env.storage().instance().set(&DataKey::Admin, &admin);
-        env.storage().instance().set(&DataKey::NftContract, &nft_contract);
-        env.storage().instance().set(&DataKey::UsdcToken, &usdc_token);
-        env.storage().instance().set(&DataKey::XlmToken, &xlm_token);
-        env.storage().instance().set(&DataKey::EscrowProvider, &EscrowProvider::Internal);
-        env.storage().instance().set(&DataKey::TrustlessEscrowXlm, &admin);
-        env.storage().instance().set(&DataKey::TrustlessEscrowUsdc, &admin);
-        env.storage().instance().set(&DataKey::NextAuctionId, &0u64);
+
+        let next_id: u64 = env.storage().instance().get(&DataKey::NextAuctionId).unwrap_or(0);
+        for auction_id in 0..next_id {
+            let Some(auction) = Self::load_auction(env, auction_id) else {
+                continue;
+            };
+            let highest: Option<BidInfo> = Self::read_entry(env, &DataKey::HighestBid(auction_id));
+            match auction.format {
+                // Deposits stay until released, whatever the auction's status; a
+                // finalized winner has already paid the price out of theirs.
+                AuctionFormat::Sealed(_) => {
+                    for bidder in distinct.iter() {
+                        let key = DataKey::SealedBid(auction_id, bidder.clone());
+                        let Some(sealed) = Self::read_entry::<SealedBid>(env, &key) else {
+                            continue;
+                        };
+                        if sealed.released {
+                            continue;
+                        }
+                        let owed = match &highest {
+                            Some(winner)
+                                if auction.status == AuctionStatus::Finalized
+                                    && sealed.revealed_amount.is_some()
+                                    && winner.bidder == bidder =>
+                            {
+                                sealed.deposit - winner.amount
+                            }
+                            _ => sealed.deposit,
+                        };
+                        add(&auction.currency, owed);
+                    }
+                }
+                _ if auction.status != AuctionStatus::Active => {}
+                AuctionFormat::Drop(_) => {
+                    let bids: Vec<BidInfo> = Self::read_entry(env, &DataKey::DropBids(auction_id))
+                        .unwrap_or(Vec::new(env));
+                    for bid in bids.iter() {
+                        add(&bid.currency, bid.amount);
+                    }
+                }
+                AuctionFormat::English | AuctionFormat::Dutch(_) => {
+                    if let Some(bid) = highest {
+                        add(&bid.currency, bid.amount);
+                    }
+                }
+            }
+        }
+
+        env.storage().instance().set(&DataKey::HeldFunds(Currency::Xlm), &xlm);
+        env.storage().instance().set(&DataKey::HeldFunds(Currency::Usdc), &usdc);
     }
 
-    pub fn configure_oracle(env: Env, oracle: Address) {
-        Self::require_admin(&env);
-        env.storage().instance().set(&DataKey::OracleContract, &oracle);
+    /// Sends `amount` of `currency` to the admin, as long as it is not held for anyone else.
+    fn withdraw_free_balance(
+        env: &Env,
+        currency: Currency,
+        amount: i128,
+    ) -> Result<(), AuctionError> {
+        Self::require_not_paused(env, PauseFlag::Withdrawals)?;
+        let admin = Self::require_admin(env);
+        let token_addr = match currency {
+            Currency::Xlm => env.storage().instance().get::<DataKey, Address>(&DataKey::XlmToken).unwrap(),
+            Currency::Usdc => env.storage().instance().get::<DataKey, Address>(&DataKey::UsdcToken).unwrap(),
+        };
+        let token_client = token::Client::new(env, &token_addr);
+        let held: i128 = env.storage().instance().get(&DataKey::HeldFunds(currency)).unwrap_or(0);
+        if amount > token_client.balance(&env.current_contract_address()) - held {
+            return Err(AuctionError::WithdrawalExceedsFreeBalance);
+        }
+        token_client.transfer(&env.current_contract_address(), &admin, &amount);
+        Ok(())
     }
 
-    pub fn configure_internal_escrow(env: Env) {
-        Self::require_admin(&env);
-        env.storage()
-            .instance()
-            .set(&DataKey::EscrowProvider, &EscrowProvider::Internal);
+    /// Mints an edition to every bid left in a drop's book, crediting the drop's
+    /// creator and royalty, and pays each bid out along the escrow route.
+    fn settle_drop(
+        env: &Env,
+        auction_id: u64,
+        mut auction: AuctionInfo,
+    ) -> Result<(), AuctionError> {
+        auction.status = AuctionStatus::Finalized;
+        let winners: Vec<BidInfo> =
+            Self::read_entry(env, &DataKey::DropBids(auction_id)).unwrap_or(Vec::new(env));
+        let royalty_bps: u32 =
+            Self::read_entry(env, &DataKey::DropRoyalty(auction_id)).unwrap_or(0);
+        for bid in winners.iter() {
+            let mint_args = (
+                env.current_contract_address(),
+                bid.bidder.clone(),
+                auction.token_uri.clone(),
+                auction.seller.clone(),
+                royalty_bps,
+            );
+            let token_id = env.invoke_contract::<u64>(
+                &Self::nft_contract(env),
+                &Symbol::new(env, "mint_edition"),
+                mint_args.into_val(env),
+            );
+            let fee = Self::take_fee(env, &bid.currency, bid.amount);
+            Self::pay_seller(env, &auction, &bid.currency, bid.amount - fee)?;
+            env.events().publish(
+                (symbol_short!("edition"), auction_id, bid.bidder.clone()),
+                (token_id, bid.amount, bid.currency.clone()),
+            );
+        }
+        auction.escrow_settled = !winners.is_empty();
+        Self::write_entry(env, &DataKey::Auction(auction_id), &auction);
+
+        env.events()
+            .publish((symbol_short!("finalized"), auction_id), winners.first());
+        Ok(())
     }
 
-    pub fn configure_trustless_escrow(env: Env, xlm_destination: Address, usdc_destination: Address) {
-        Self::require_admin(&env);
-        env.storage().instance().set(&DataKey::TrustlessEscrowXlm, &xlm_destination);
-        env.storage().instance().set(&DataKey::TrustlessEscrowUsdc, &usdc_destination);
-        env.storage()
-            .instance()
-            .set(&DataKey::EscrowProvider, &EscrowProvider::TrustlessWork);
+    /// Converts `amount` of `from` into `to` at the oracle rate.
+    fn convert_amount(
+        env: &Env,
+        rate: &mut XlmRate,
+        amount: i128,
+        from: &Currency,
+        to: &Currency,
+    ) -> Result<i128, AuctionError> {
+        match (from, to) {
+            (Currency::Xlm, Currency::Xlm) | (Currency::Usdc, Currency::Usdc) => Ok(amount),
+            (Currency::Xlm, Currency::Usdc) => Self::normalize_to_usdc(env, rate, amount, from),
+            (Currency::Usdc, Currency::Xlm) => {
+                let rate = rate.get(env)?;
+                Ok(amount * TOKEN_SCALE / rate)
+            }
+        }
     }
 
-    pub fn get_escrow_provider(env: Env) -> EscrowProvider {
-        Self::current_escrow_provider(&env)
+    /// Reads an auction in the current layout. Records written under schema v1 or v2 are
+    /// converted here and take the new layout the next time they are written, so an
+    /// upgrade never has to rewrite every auction in one transaction.
+    fn load_auction(env: &Env, auction_id: u64) -> Option<AuctionInfo> {
+        let fields: Map<Symbol, Val> = Self::read_entry(env, &DataKey::Auction(auction_id))?;
+        // Decoding a struct from a map with other fields traps, so check the shape first.
+        if fields.contains_key(symbol_short!("status")) {
+            return AuctionInfo::try_from_val(env, &fields.to_val()).ok();
+        }
+        if fields.contains_key(symbol_short!("format")) {
+            let legacy = AuctionInfoV2::try_from_val(env, &fields.to_val()).ok()?;
+            return Some(AuctionInfo {
+                token_uri: legacy.token_uri,
+                is_item_auction: legacy.is_item_auction,
+                seller: legacy.seller,
+                token_id: legacy.token_id,
+                start_time: legacy.start_time,
+                end_time: legacy.end_time,
+                starting_price: legacy.starting_price,
+                currency: legacy.currency,
+                status: Self::legacy_status(legacy.finalized, legacy.cancelled),
+                escrow_provider: legacy.escrow_provider,
+                escrow_settled: legacy.escrow_settled,
+                reserve_price: legacy.reserve_price,
+                reserve_hidden: legacy.reserve_hidden,
+                soft_close: legacy.soft_close,
+                extended_seconds: legacy.extended_seconds,
+                bid_history_limit: legacy.bid_history_limit,
+                buy_now_price: legacy.buy_now_price,
+                format: legacy.format,
+            });
+        }
+        let legacy = AuctionInfoV1::try_from_val(env, &fields.to_val()).ok()?;
+        Some(AuctionInfo {
+            token_uri: legacy.token_uri,
+            is_item_auction: legacy.is_item_auction,
+            seller: legacy.seller,
+            token_id: legacy.token_id,
+            start_time: legacy.start_time,
+            end_time: legacy.end_time,
+            starting_price: legacy.starting_price,
+            currency: legacy.currency,
+            status: Self::legacy_status(legacy.finalized, false),
+            escrow_provider: legacy.escrow_provider,
+            escrow_settled: legacy.escrow_settled,
+            reserve_price: None,
+            reserve_hidden: false,
+            soft_close: Self::disabled_soft_close(),
+            extended_seconds: 0,
+            bid_history_limit: DEFAULT_BID_HISTORY_LIMIT,
+            buy_now_price: None,
+            format: AuctionFormat::English,
+        })
     }
 
-    pub fn get_trustless_escrow_dests(env: Env) -> (Address, Address) {
-        let xlm_destination: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::TrustlessEscrowXlm)
-            .unwrap();
-        let usdc_destination: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::TrustlessEscrowUsdc)
-            .unwrap();
-        (xlm_destination, usdc_destination)
+    /// Schema v2 marked a cancelled auction as both `finalized` and `cancelled`.
+    fn legacy_status(finalized: bool, cancelled: bool) -> AuctionStatus {
+        if cancelled {
+            AuctionStatus::Cancelled
+        } else if finalized {
+            AuctionStatus::Finalized
+        } else {
+            AuctionStatus::Active
+        }
     }
 
-    pub fn create_item_auction(
-        env: Env,
+    fn disabled_soft_close() -> SoftClose {
+        SoftClose {
+            window_seconds: 0,
+            extension_seconds: 0,
+            max_extension_seconds: 0,
+        }
+    }
+
+    /// Dutch price at `now`, clamped to the schedule's floor once the auction has run out.
+    fn dutch_price(auction: &AuctionInfo, schedule: &DutchSchedule, now: u64) -> i128 {
+        let duration = auction.end_time - auction.start_time;
+        let elapsed = now.saturating_sub(auction.start_time).min(duration);
+        // No step size means the price moves every second.
+        let (done, total) = match duration.checked_div(schedule.step_seconds) {
+            Some(steps) => (elapsed / schedule.step_seconds, steps),
+            None => (elapsed, duration),
+        };
+        let drop = auction.starting_price - schedule.floor_price;
+        auction.starting_price - drop * done as i128 / total as i128
+    }
+
+    /// Takes custody of `token_id` and records a new auction for it. Callers
+    /// check auth, pause state and format-specific pricing first.
+    #[allow(clippy::too_many_arguments)]
+    fn open_item_auction(
+        env: &Env,
         seller: Address,
         token_id: u64,
         starting_price: i128,
         currency: Currency,
         duration_seconds: u64,
-    ) -> u64 {
-        seller.require_auth();
-        Self::validate_auction_duration(duration_seconds);
-
-        let owner = Self::nft_owner_of(&env, token_id);
+        format: AuctionFormat,
+        options: AuctionOptions,
+    ) -> Result<u64, AuctionError> {
+        Self::validate_auction_duration(duration_seconds)?;
+        let owner = Self::nft_owner_of(env, token_id);
         if owner != seller {
-            panic!("seller does not own token");
+            return Err(AuctionError::NotTokenOwner);
         }
 
-        let token_uri = Self::nft_token_uri(&env, token_id);
+        let token_uri = Self::nft_token_uri(env, token_id);
         let now = env.ledger().timestamp();
         let auction_id: u64 = env.storage().instance().get(&DataKey::NextAuctionId).unwrap();
 
-        Self::nft_transfer(&env, &seller, &env.current_contract_address(), token_id);
+        Self::nft_transfer(env, &seller, &env.current_contract_address(), token_id);
 
+        // Soft close only matters where there are open bids to answer.
+        let soft_close = match format {
+            AuctionFormat::English => Self::current_soft_close(env),
+            AuctionFormat::Dutch(_) | AuctionFormat::Sealed(_) | AuctionFormat::Drop(_) => {
+                Self::disabled_soft_close()
+            }
+        };
+        let end_time = now + duration_seconds;
         let auction = AuctionInfo {
             token_uri,
             is_item_auction: true,
             seller: seller.clone(),
             token_id,
             start_time: now,
-            end_time: now + duration_seconds,
+            end_time,
             starting_price,
-            currency,
-            finalized: false,
+            currency: currency.clone(),
+            status: AuctionStatus::Active,
             // Item auctions use the currently configured escrow provider
             // (TrustlessWork when available; Internal otherwise).
-            escrow_provider: Self::current_escrow_provider(&env),
+            escrow_provider: Self::current_escrow_provider(env),
             escrow_settled: false,
+            reserve_price: options.reserve_price,
+            reserve_hidden: options.reserve_hidden && options.reserve_price.is_some(),
+            soft_close,
+            extended_seconds: 0,
+            bid_history_limit: Self::current_bid_history_limit(env),
+            buy_now_price: options.buy_now_price,
+            format,
         };
 
-        env.storage().persistent().set(&DataKey::Auction(auction_id), &auction);
+        Self::write_entry(env, &DataKey::Auction(auction_id), &auction);
         env.storage().instance().set(&DataKey::NextAuctionId, &(auction_id + 1));
-        auction_id
-    }
-
-    pub fn bid_xlm(env: Env, auction_id: u64, bidder: Address, amount: i128) {
-        bidder.require_auth();
-        Self::place_bid(&env, auction_id, bidder, amount, Currency::Xlm);
+        env.events().publish(
+            (symbol_short!("created"), auction_id, seller),
+            (token_id, starting_price, currency, end_time),
+        );
+        Ok(auction_id)
     }
 
-    pub fn bid_usdc(env: Env, auction_id: u64, bidder: Address, amount: i128) {
-        bidder.require_auth();
-        Self::place_bid(&env, auction_id, bidder, amount, Currency::Usdc);
+    /// Rejects auctions that were already settled or cancelled.
+    fn require_open(auction: &AuctionInfo) -> Result<(), AuctionError> {
+        match auction.status {
+            AuctionStatus::Active => Ok(()),
+            AuctionStatus::Finalized => Err(AuctionError::AlreadyFinalized),
+            AuctionStatus::Cancelled => Err(AuctionError::AuctionCancelled),
+        }
     }
 
-    fn place_bid(env: &Env, auction_id: u64, bidder: Address, amount: i128, currency: Currency) {
-        let auction: AuctionInfo = env
-            .storage()
-            .persistent()
-            .get(&DataKey::Auction(auction_id))
-            .unwrap_or_else(|| panic!("auction does not exist"));
-
-        let now = env.ledger().timestamp();
-        if now > auction.end_time {
-            panic!("auction has ended");
+    /// Closes an auction without a winner: standing bids (English and drop) are
+    /// refunded and an item goes back to the seller. Sealed-bid deposits are
+    /// returned in full through `release_sealed_deposit`.
+    fn cancel(
+        env: &Env,
+        auction_id: u64,
+        mut auction: AuctionInfo,
+        caller: Address,
+    ) -> Result<(), AuctionError> {
+        Self::require_open(&auction)?;
+        auction.status = AuctionStatus::Cancelled;
+        Self::write_entry(env, &DataKey::Auction(auction_id), &auction);
+
+        match auction.format {
+            AuctionFormat::Sealed(_) => {}
+            AuctionFormat::Drop(_) => {
+                let bids: Vec<BidInfo> =
+                    Self::read_entry(env, &DataKey::DropBids(auction_id)).unwrap_or(Vec::new(env));
+                for bid in bids.iter() {
+                    Self::refund_bid(env, auction_id, &bid);
+                }
+                env.storage().persistent().remove(&DataKey::DropBids(auction_id));
+            }
+            AuctionFormat::English | AuctionFormat::Dutch(_) => {
+                let highest_bid: Option<BidInfo> =
+                    Self::read_entry(env, &DataKey::HighestBid(auction_id));
+                if let Some(bid) = highest_bid {
+                    Self::refund_bid(env, auction_id, &bid);
+                    env.storage().persistent().remove(&DataKey::HighestBid(auction_id));
+                }
+            }
         }
-        if auction.finalized {
-            panic!("auction already finalized");
+        if auction.is_item_auction {
+            Self::nft_transfer(
+                env,
+                &env.current_contract_address(),
+                &auction.seller,
+                auction.token_id,
+            );
         }
+        env.events()
+            .publish((symbol_short!("cancelled"), auction_id), caller);
+        Ok(())
+    }
 
-        // Check bid meets the minimum starting price.
-        // Same currency → direct comparison; cross-currency → normalize both to USDC via oracle.
-        if currency == auction.currency {
-            if amount < auction.starting_price {
-                panic!("bid below minimum starting price");
-            }
-        } else {
-            let bid_usdc = Self::normalize_to_usdc(env, amount, &currency);
-            let starting_usdc = Self::normalize_to_usdc(env, auction.starting_price, &auction.currency);
-            if bid_usdc < starting_usdc {
-                panic!("bid below minimum starting price");
-            }
+    /// When `finalize` may run: the end of bidding, or of the reveal phase for sealed bids.
+    fn closes_at(auction: &AuctionInfo) -> u64 {
+        match &auction.format {
+            AuctionFormat::Sealed(rules) => auction.end_time + rules.reveal_seconds,
+            _ => auction.end_time,
         }
+    }
 
-        // Check against current highest bid.
-        // Same currency as highest bid → direct comparison; different → normalize to USDC via oracle.
-        let has_existing_bid = env.storage().persistent().has(&DataKey::HighestBid(auction_id));
-        if has_existing_bid {
-            let current_bid: BidInfo = env
-                .storage()
-                .persistent()
-                .get(&DataKey::HighestBid(auction_id))
-                .unwrap();
+    /// `sha256(auction_id || bidder || amount || salt)`. Binding the auction and the
+    /// bidder means a commitment copied from someone else, or replayed from another
+    /// auction, can never be revealed.
+    fn sealed_commitment(
+        env: &Env,
+        auction_id: u64,
+        bidder: &Address,
+        amount: i128,
+        salt: &BytesN<32>,
+    ) -> BytesN<32> {
+        let mut preimage = Bytes::from_array(env, &auction_id.to_be_bytes());
+        preimage.append(&bidder.clone().to_xdr(env));
+        preimage.extend_from_array(&amount.to_be_bytes());
+        preimage.extend_from_array(&salt.to_array());
+        env.crypto().sha256(&preimage).to_bytes()
+    }
 
-            if currency == current_bid.currency {
-                // Same currency: compare directly
-                let min_required = current_bid.amount + (current_bid.amount * MIN_INCREMENT_BPS / 10_000);
-                if amount < min_required {
-                    panic!("bid must be at least 5% higher than current highest");
-                }
-            } else {
-                // Cross-currency: normalize both to USDC
-                let bid_usdc = Self::normalize_to_usdc(env, amount, &currency);
-                let current_usdc = Self::normalize_to_usdc(env, current_bid.amount, &current_bid.currency);
-                let required_usdc = current_usdc + (current_usdc * MIN_INCREMENT_BPS / 10_000);
-                if bid_usdc < required_usdc {
-                    panic!("bid must be at least 5% higher than current highest");
-                }
-            }
+    fn current_bid_history_limit(env: &Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::MaxBidHistory)
+            .unwrap_or(DEFAULT_BID_HISTORY_LIMIT)
+    }
 
-            // Refund previous bidder in their original currency
-            let token_addr = match current_bid.currency {
-                Currency::Xlm => env.storage().instance().get::<DataKey, Address>(&DataKey::XlmToken).unwrap(),
-                Currency::Usdc => env.storage().instance().get::<DataKey, Address>(&DataKey::UsdcToken).unwrap(),
+    /// Appends `bid` to the auction's log. The log is a ring of
+    /// `bid_history_limit` slots, so only the most recent bids are kept.
+    fn record_bid(
+        env: &Env,
+        rate: &mut XlmRate,
+        auction_id: u64,
+        auction: &AuctionInfo,
+        bid: &BidInfo,
+    ) {
+        let count: u32 = Self::read_entry(env, &DataKey::BidCount(auction_id)).unwrap_or(0);
+        if auction.bid_history_limit > 0 {
+            let record = BidRecord {
+                bidder: bid.bidder.clone(),
+                amount: bid.amount,
+                currency: bid.currency.clone(),
+                usdc_value: Self::normalize_to_usdc(env, rate, bid.amount, &bid.currency).ok(),
+                timestamp: env.ledger().timestamp(),
             };
-            let token_client = token::Client::new(env, &token_addr);
-            token_client.transfer(&env.current_contract_address(), &current_bid.bidder, &current_bid.amount);
+            let slot = count % auction.bid_history_limit;
+            Self::write_entry(env, &DataKey::BidLog(auction_id, slot), &record);
         }
+        Self::write_entry(env, &DataKey::BidCount(auction_id), &(count + 1));
+    }
 
-        // Transfer bid amount to contract
-        let token_addr = match currency {
-            Currency::Xlm => env.storage().instance().get::<DataKey, Address>(&DataKey::XlmToken).unwrap(),
-            Currency::Usdc => env.storage().instance().get::<DataKey, Address>(&DataKey::UsdcToken).unwrap(),
-        };
-        let token_client = token::Client::new(env, &token_addr);
-        token_client.transfer(&bidder, &env.current_contract_address(), &amount);
-
-        let bid = BidInfo {
-            bidder,
-            amount,
-            currency,
-        };
-        env.storage().persistent().set(&DataKey::HighestBid(auction_id), &bid);
+    fn current_soft_close(env: &Env) -> SoftClose {
+        env.storage()
+            .instance()
+            .get(&DataKey::SoftClose)
+            .unwrap_or(SoftClose {
+                window_seconds: DEFAULT_SOFT_CLOSE_WINDOW,
+                extension_seconds: DEFAULT_SOFT_CLOSE_EXTENSION,
+                max_extension_seconds: DEFAULT_MAX_SOFT_CLOSE_EXTENSION,
+            })
     }
 
-    pub fn finalize(env: Env, auction_id: u64) {
-        let mut auction: AuctionInfo = env
-            .storage()
-            .persistent()
-            .get(&DataKey::Auction(auction_id))
-            .unwrap_or_else(|| panic!("auction does not exist"));
+    fn nft_contract(env: &Env) -> Address {
+        env.storage().instance().get(&DataKey::NftContract).unwrap()
+    }
 
-        let now = env.ledger().timestamp();
-        if now <= auction.end_time {
-            panic!("auction has not ended yet");
+    fn validate_auction_duration(duration_seconds: u64) -> Result<(), AuctionError> {
+        if duration_seconds < MIN_AUCTION_DURATION {
+            return Err(AuctionError::DurationTooShort);
         }
-        if auction.finalized {
-            panic!("auction already finalized");
+        if duration_seconds > MAX_AUCTION_DURATION {
+            return Err(AuctionError::DurationTooLong);
         }
+        Ok(())
+    }
 
-        auction.finalized = true;
-        env.storage().persistent().set(&DataKey::Auction(auction_id), &auction);
-
-        // If there's a winning bid, mint NFT to winner
-        if env.storage().persistent().has(&DataKey::HighestBid(auction_id)) {
-            let winning_bid: BidInfo = env
-                .storage()
-                .persistent()
-                .get(&DataKey::HighestBid(auction_id))
-                .unwrap();
-
-            if auction.is_item_auction {
-                Self::nft_transfer(
-                    &env,
-                    &env.current_contract_address(),
-                    &winning_bid.bidder,
-                    auction.token_id,
-                );
+    fn nft_owner_of(env: &Env, token_id: u64) -> Address {
+        let nft_contract = Self::nft_contract(env);
+        env.invoke_contract::<Address>(
+            &nft_contract,
+            &Symbol::new(env, "owner_of"),
+            (token_id,).into_val(env),
+        )
+    }
 
-                let token_addr = match winning_bid.currency {
-                    Currency::Xlm => env.storage().instance().get::<DataKey, Address>(&DataKey::XlmToken).unwrap(),
-                    Currency::Usdc => env.storage().instance().get::<DataKey, Address>(&DataKey::UsdcToken).unwrap(),
-                };
-                let token_client = token::Client::new(&env, &token_addr);
-                if auction.escrow_provider == EscrowProvider::TrustlessWork {
-                    let destination = Self::trustless_escrow_destination(&env, &winning_bid.currency);
-                    token_client.transfer(
-                        &env.current_contract_address(),
-                        &destination,
-                        &winning_bid.amount,
-                    );
-                } else {
-                    token_client.transfer(
-                        &env.current_contract_address(),
-                        &auction.seller,
-                        &winning_bid.amount,
-                    );
-                }
+    fn nft_token_uri(env: &Env, token_id: u64) -> String {
+        let nft_contract = Self::nft_contract(env);
+        env.invoke_contract::<String>(
+            &nft_contract,
+            &Symbol::new(env, "token_uri"),
+            (token_id,).into_val(env),
+        )
+    }
 
-                auction.escrow_settled = true;
-                env.storage().persistent().set(&DataKey::Auction(auction_id), &auction);
-            } else {
-                let nft_contract: Address = env.storage().instance().get(&DataKey::NftContract).unwrap();
+    fn nft_royalty_info(env: &Env, token_id: u64, sale_price: i128) -> (Address, i128) {
+        let nft_contract = Self::nft_contract(env);
+        env.invoke_contract::<(Address, i128)>(
+            &nft_contract,
+            &Symbol::new(env, "royalty_info"),
+            (token_id, sale_price).into_val(env),
+        )
+    }
 
-                // Cross-contract call to mint NFT
-                let mint_args = (winning_bid.bidder.clone(), auction.token_uri.clone());
-                env.invoke_contract::<u64>(
-                    &nft_contract,
-                    &Symbol::new(&env, "mint"),
-                    mint_args.into_val(&env),
-                );
+    fn nft_transfer(env: &Env, from: &Address, to: &Address, token_id: u64) {
+        let nft_contract = Self::nft_contract(env);
+        env.invoke_contract::<()>(
+            &nft_contract,
+            &Symbol::new(env, "transfer"),
+            (from.clone(), to.clone(), token_id).into_val(env),
+        );
+    }
+}
 
-                if auction.escrow_provider == EscrowProvider::TrustlessWork {
-                    let destination = Self::trustless_escrow_destination(&env, &winning_bid.currency);
-                    let token_addr = match winning_bid.currency {
-                        Currency::Xlm => env.storage().instance().get::<DataKey, Address>(&DataKey::XlmToken).unwrap(),
-                        Currency::Usdc => env.storage().instance().get::<DataKey, Address>(&DataKey::UsdcToken).unwrap(),
-                    };
-                    let token_client = token::Client::new(&env, &token_addr);
-                    token_client.transfer(
-                        &env.current_contract_address(),
-                        &destination,
-                        &winning_bid.amount,
-                    );
-                    auction.escrow_settled = true;
-                    env.storage().persistent().set(&DataKey::Auction(auction_id), &auction);
-                }
-            }
-        } else if auction.is_item_auction {
-            Self::nft_transfer(
-                &env,
-                &env.current_contract_address(),
-                &auction.seller,
-                auction.token_id,
-            );
+#[contractimpl]
+impl ShimejiAuction {
+    pub fn initialize(
+        env: Env,
+        admin: Address,
+        nft_contract: Address,
+        usdc_token: Address,
+        xlm_token: Address,
+    ) -> Result<(), AuctionError> {
+        if env.storage().instance().has(&DataKey::Admin) {
+            return Err(AuctionError::AlreadyInitialized);
         }
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(&DataKey::NftContract, &nft_contract);
+        env.storage().instance().set(&DataKey::UsdcToken, &usdc_token);
+        env.storage().instance().set(&DataKey::XlmToken, &xlm_token);
+        env.storage().instance().set(&DataKey::EscrowProvider, &EscrowProvider::Internal);
+        env.storage().instance().set(&DataKey::TrustlessEscrowXlm, &admin);
+        env.storage().instance().set(&DataKey::TrustlessEscrowUsdc, &admin);
+        env.storage().instance().set(&DataKey::NextAuctionId, &0u64);
+        env.storage().instance().set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+        Self::extend_instance(&env);
+        Ok(())
     }
 
-    pub fn withdraw_xlm(env: Env, amount: i128) {
-        let admin = Self::require_admin(&env);
-        let xlm_token: Address = env.storage().instance().get(&DataKey::XlmToken).unwrap();
-        let token_client = token::Client::new(&env, &xlm_token);
-        token_client.transfer(&env.current_contract_address(), &admin, &amount);
+    /// Swaps this contract's code for an already-uploaded wasm. Storage is left
+    /// untouched; call `migrate` afterwards if the new code bumps the schema.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) {
+        Self::require_admin(&env);
+        env.deployer()
+            .update_current_contract_wasm(new_wasm_hash.clone());
+        env.events()
+            .publish((symbol_short!("upgraded"),), new_wasm_hash);
     }
 
-    pub fn withdraw_usdc(env: Env, amount: i128) {
-        let admin = Self::require_admin(&env);
-        let usdc_token: Address = env.storage().instance().get(&DataKey::UsdcToken).unwrap();
-        let token_client = token::Client::new(&env, &usdc_token);
-        token_client.transfer(&env.current_contract_address(), &admin, &amount);
+    /// Storage layout version. Deployments from before versioning report 0.
+    pub fn schema_version(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::SchemaVersion)
+            .unwrap_or(0)
     }
 
-    pub fn get_auction(env: Env, auction_id: u64) -> AuctionInfo {
+    /// Brings stored data from the recorded schema version up to the one this
+    /// code expects. Safe to call repeatedly; returns the resulting version.
+    pub fn migrate(env: Env, bidders: Vec<Address>) -> Result<u32, AuctionError> {
+        Self::require_admin(&env);
+        let from = Self::schema_version(env.clone());
+        if from > SCHEMA_VERSION {
+            return Err(AuctionError::UnsupportedSchemaVersion);
+        }
+        if from == SCHEMA_VERSION {
+            return Ok(from);
+        }
+        // v0 -> v1: layout unchanged, only the version stamp is new.
+        // v1 -> v2: auctions gain format, cancellation, reserve, soft-close, bid-history
+        // and buy-now fields. `load_auction` converts v1 records as they are read, so
+        // existing English auctions keep the rules they were created under (no reserve,
+        // no soft close, no buy-now) without a pass over every auction here.
+        // v2 -> v3: the `finalized` and `cancelled` flags become a single `status`;
+        // `load_auction` converts v2 records the same way.
+        // v3 -> v4: admin withdrawals stop at `HeldFunds`, which older code did not
+        // keep, so it is rebuilt once from the records. `bidders` must name everyone
+        // who may hold a sealed deposit or a credited refund (`committed` and
+        // `credited` events); v0 and v1 storage has neither, so it can be empty.
+        if from < 4 {
+            Self::seed_held_funds(&env, &bidders);
+        }
         env.storage()
-            .persistent()
-            .get(&DataKey::Auction(auction_id))
-            .unwrap_or_else(|| panic!("auction does not exist"))
+            .instance()
+            .set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+        env.events()
+            .publish((symbol_short!("migrated"),), (from, SCHEMA_VERSION));
+        Ok(SCHEMA_VERSION)
     }
 
-    pub fn get_highest_bid(env: Env, auction_id: u64) -> BidInfo {
-        env.storage()
-            .persistent()
-            .get(&DataKey::HighestBid(auction_id))
-            .unwrap_or_else(|| panic!("no bids for this auction"))
+    /// First half of an admin handover: nothing changes until `new_admin`
+    /// calls `accept_admin`. A later proposal replaces an earlier one.
+    pub fn propose_admin(env: Env, new_admin: Address) {
+        let admin = Self::require_admin(&env);
+        env.storage().instance().set(&DataKey::PendingAdmin, &new_admin);
+        env.events()
+            .publish((symbol_short!("adm_prop"), admin), new_admin);
     }
 
-    pub fn total_auctions(env: Env) -> u64 {
-        env.storage()
+    pub fn accept_admin(env: Env) -> Result<(), AuctionError> {
+        let new_admin: Address = env
+            .storage()
             .instance()
-            .get(&DataKey::NextAuctionId)
-            .unwrap_or(0u64)
+            .get(&DataKey::PendingAdmin)
+            .ok_or(AuctionError::NoPendingAdmin)?;
+        new_admin.require_auth();
+        let previous: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        env.storage().instance().set(&DataKey::Admin, &new_admin);
+        env.storage().instance().remove(&DataKey::PendingAdmin);
+        env.events()
+            .publish((symbol_short!("adm_xfer"), previous), new_admin);
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use soroban_sdk::{
-        contract,
-        contractimpl,
-        contracttype,
-        testutils::Address as _,
-        testutils::Ledger,
-        token::{StellarAssetClient, TokenClient},
-        Env, String,
-    };
+    pub fn pending_admin(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::PendingAdmin)
+    }
 
-    // MockNft — unchanged
-    #[contract]
-    struct MockNft;
+    pub fn admin(env: Env) -> Address {
+        env.storage().instance().get(&DataKey::Admin).unwrap()
+    }
 
-    #[contracttype]
-    #[derive(Clone)]
-    enum MockNftDataKey {
-        NextTokenId,
-        Owner(u64),
-        TokenUri(u64),
+    pub fn grant_role(env: Env, role: Role, account: Address) -> Result<(), AuctionError> {
+        Self::require_admin(&env);
+        if role == Role::Admin {
+            return Err(AuctionError::AdminRoleNotGrantable);
+        }
+        let key = DataKey::RoleMember(role, account.clone());
+        if !env.storage().instance().has(&key) {
+            env.storage().instance().set(&key, &true);
+            env.events()
+                .publish((symbol_short!("role_grnt"), account), role);
+        }
+        Ok(())
     }
 
-    #[contractimpl]
-    impl MockNft {
-        pub fn mint(env: Env, to: Address, token_uri: String) -> u64 {
-            let next_id: u64 = env
-                .storage()
-                .instance()
-                .get(&MockNftDataKey::NextTokenId)
-                .unwrap_or(0u64);
-            env.storage()
-                .instance()
-                .set(&MockNftDataKey::Owner(next_id), &to);
-            env.storage()
-                .instance()
-                .set(&MockNftDataKey::TokenUri(next_id), &token_uri);
-            env.storage()
-                .instance()
-                .set(&MockNftDataKey::NextTokenId, &(next_id + 1));
-            next_id
+    pub fn revoke_role(env: Env, role: Role, account: Address) -> Result<(), AuctionError> {
+        Self::require_admin(&env);
+        if role == Role::Admin {
+            return Err(AuctionError::AdminRoleNotGrantable);
+        }
+        let key = DataKey::RoleMember(role, account.clone());
+        if env.storage().instance().has(&key) {
+            env.storage().instance().remove(&key);
+            env.events()
+                .publish((symbol_short!("role_rvk"), account), role);
         }
+        Ok(())
+    }
 
-        pub fn owner_of(env: Env, token_id: u64) -> Address {
-            env.storage()
+    pub fn has_role(env: Env, role: Role, account: Address) -> bool {
+        match role {
+            Role::Admin => {
+                let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+                admin == account
+            }
+            _ => env
+                .storage()
                 .instance()
-                .get(&MockNftDataKey::Owner(token_id))
-                .unwrap_or_else(|| panic!("token does not exist"))
+                .has(&DataKey::RoleMember(role, account)),
         }
+    }
 
-        pub fn token_uri(env: Env, token_id: u64) -> String {
-            env.storage()
-                .instance()
-                .get(&MockNftDataKey::TokenUri(token_id))
-                .unwrap_or_else(|| panic!("token does not exist"))
+    /// Halts one class of operations until `unpause`. Admin or pausers only.
+    pub fn pause(env: Env, caller: Address, flag: PauseFlag) -> Result<(), AuctionError> {
+        Self::require_pauser(&env, &caller)?;
+        env.storage().instance().set(&DataKey::Paused(flag), &true);
+        env.events().publish((symbol_short!("paused"), caller), flag);
+        Ok(())
+    }
+
+    pub fn unpause(env: Env, caller: Address, flag: PauseFlag) -> Result<(), AuctionError> {
+        Self::require_pauser(&env, &caller)?;
+        env.storage().instance().remove(&DataKey::Paused(flag));
+        env.events().publish((symbol_short!("unpaused"), caller), flag);
+        Ok(())
+    }
+
+    pub fn is_paused(env: Env, flag: PauseFlag) -> bool {
+        env.storage().instance().has(&DataKey::Paused(flag))
+    }
+
+    pub fn configure_oracle(env: Env, oracle: Address) {
+        Self::require_admin(&env);
+        env.storage().instance().set(&DataKey::OracleContract, &oracle);
+    }
+
+    /// Sets the staleness and bounds checks for oracle prices (default: 15 minutes,
+    /// any positive rate, `lastprice` only).
+    pub fn configure_oracle_guard(env: Env, guard: OracleGuard) -> Result<(), AuctionError> {
+        Self::require_admin(&env);
+        if guard.max_age_seconds == 0 || guard.min_rate <= 0 || guard.max_rate < guard.min_rate {
+            return Err(AuctionError::InvalidOracleGuard);
         }
+        env.storage().instance().set(&DataKey::OracleGuard, &guard);
+        Ok(())
+    }
 
-        pub fn transfer(env: Env, from: Address, to: Address, token_id: u64) {
-            let owner: Address = env
-                .storage()
-                .instance()
-                .get(&MockNftDataKey::Owner(token_id))
-                .unwrap_or_else(|| panic!("token does not exist"));
-            if owner != from {
-                panic!("not token owner");
+    pub fn get_oracle_guard(env: Env) -> OracleGuard {
+        Self::current_oracle_guard(&env)
+    }
+
+    pub fn configure_internal_escrow(env: Env) {
+        Self::require_admin(&env);
+        env.storage()
+            .instance()
+            .set(&DataKey::EscrowProvider, &EscrowProvider::Internal);
+    }
+
+    pub fn configure_trustless_escrow(env: Env, xlm_destination: Address, usdc_destination: Address) {
+        Self::require_admin(&env);
+        env.storage().instance().set(&DataKey::TrustlessEscrowXlm, &xlm_destination);
+        env.storage().instance().set(&DataKey::TrustlessEscrowUsdc, &usdc_destination);
+        env.storage()
+            .instance()
+            .set(&DataKey::EscrowProvider, &EscrowProvider::TrustlessWork);
+    }
+
+    /// Sets the soft-close rule for auctions created from now on; running auctions
+    /// keep the rule they started with.
+    pub fn configure_soft_close(
+        env: Env,
+        window_seconds: u64,
+        extension_seconds: u64,
+        max_extension_seconds: u64,
+    ) -> Result<(), AuctionError> {
+        Self::require_admin(&env);
+        if window_seconds > 0 && extension_seconds == 0 {
+            return Err(AuctionError::InvalidSoftClose);
+        }
+        if window_seconds > MIN_AUCTION_DURATION || max_extension_seconds > MAX_AUCTION_DURATION {
+            return Err(AuctionError::InvalidSoftClose);
+        }
+        let soft_close = SoftClose {
+            window_seconds,
+            extension_seconds,
+            max_extension_seconds,
+        };
+        env.storage().instance().set(&DataKey::SoftClose, &soft_close);
+        Ok(())
+    }
+
+    /// Sets how many recent bids new auctions keep in their log (0 turns the log off).
+    pub fn configure_bid_history(env: Env, limit: u32) -> Result<(), AuctionError> {
+        Self::require_admin(&env);
+        if limit > MAX_BID_HISTORY_LIMIT {
+            return Err(AuctionError::InvalidBidHistoryLimit);
+        }
+        env.storage().instance().set(&DataKey::MaxBidHistory, &limit);
+        Ok(())
+    }
+
+    /// Chooses how losing bids are returned: credited for `claim_refund` (the
+    /// default) or pushed to the bidder straight away.
+    pub fn configure_push_refunds(env: Env, enabled: bool) {
+        Self::require_admin(&env);
+        env.storage().instance().set(&DataKey::PushRefunds, &enabled);
+    }
+
+    pub fn get_soft_close(env: Env) -> SoftClose {
+        Self::current_soft_close(&env)
+    }
+
+    pub fn get_escrow_provider(env: Env) -> EscrowProvider {
+        Self::current_escrow_provider(&env)
+    }
+
+    pub fn get_trustless_escrow_dests(env: Env) -> (Address, Address) {
+        let xlm_destination: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::TrustlessEscrowXlm)
+            .unwrap();
+        let usdc_destination: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::TrustlessEscrowUsdc)
+            .unwrap();
+        (xlm_destination, usdc_destination)
+    }
+
+    pub fn create_item_auction(
+        env: Env,
+        seller: Address,
+        token_id: u64,
+        starting_price: i128,
+        currency: Currency,
+        duration_seconds: u64,
+        options: AuctionOptions,
+    ) -> Result<u64, AuctionError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
+        seller.require_auth();
+        if let Some(reserve) = options.reserve_price {
+            if reserve < starting_price {
+                return Err(AuctionError::InvalidReservePrice);
+            }
+        }
+        if let Some(buy_now) = options.buy_now_price {
+            if buy_now < starting_price || buy_now < options.reserve_price.unwrap_or(0) {
+                return Err(AuctionError::InvalidBuyNowPrice);
+            }
+        }
+        Self::open_item_auction(
+            &env,
+            seller,
+            token_id,
+            starting_price,
+            currency,
+            duration_seconds,
+            AuctionFormat::English,
+            options,
+        )
+    }
+
+    /// Puts `token_id` up at a price falling from `starting_price` to `floor_price`
+    /// over the auction (see `DutchSchedule`). The first `buy_now_xlm`/`buy_now_usdc`
+    /// call pays `current_price` and takes the item; unsold items go back to the
+    /// seller at `finalize`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_dutch_auction(
+        env: Env,
+        seller: Address,
+        token_id: u64,
+        starting_price: i128,
+        floor_price: i128,
+        currency: Currency,
+        duration_seconds: u64,
+        step_seconds: u64,
+    ) -> Result<u64, AuctionError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
+        seller.require_auth();
+        if floor_price <= 0 || floor_price >= starting_price || step_seconds > duration_seconds {
+            return Err(AuctionError::InvalidDutchSchedule);
+        }
+        let schedule = DutchSchedule {
+            floor_price,
+            step_seconds,
+        };
+        Self::open_item_auction(
+            &env,
+            seller,
+            token_id,
+            starting_price,
+            currency,
+            duration_seconds,
+            AuctionFormat::Dutch(schedule),
+            AuctionOptions::default(),
+        )
+    }
+
+    /// Primary sale of `terms.edition_size` copies of `token_uri`, open to the admin
+    /// and `Minter` role holders. Bids go through `bid_xlm`/`bid_usdc` in the auction
+    /// currency; the top `edition_size` bids stand, and at `finalize` each of those
+    /// bidders is minted a copy and pays their own bid to `creator`. Every copy
+    /// names `creator` as its creator and pays them `terms.royalty_bps` on resales.
+    pub fn create_drop_auction(
+        env: Env,
+        creator: Address,
+        token_uri: String,
+        terms: DropTerms,
+        starting_price: i128,
+        currency: Currency,
+        duration_seconds: u64,
+    ) -> Result<u64, AuctionError> {
+        let DropTerms { edition_size, royalty_bps } = terms;
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
+        creator.require_auth();
+        if !Self::has_role(env.clone(), Role::Admin, creator.clone())
+            && !Self::has_role(env.clone(), Role::Minter, creator.clone())
+        {
+            return Err(AuctionError::NotDropCreator);
+        }
+        if edition_size == 0 || edition_size > MAX_DROP_EDITIONS {
+            return Err(AuctionError::InvalidEditionSize);
+        }
+        if royalty_bps > MAX_ROYALTY_BPS {
+            return Err(AuctionError::InvalidRoyalty);
+        }
+        Self::validate_auction_duration(duration_seconds)?;
+
+        let now = env.ledger().timestamp();
+        let auction_id: u64 = env.storage().instance().get(&DataKey::NextAuctionId).unwrap();
+        let end_time = now + duration_seconds;
+        let auction = AuctionInfo {
+            token_uri,
+            is_item_auction: false,
+            seller: creator.clone(),
+            token_id: 0,
+            start_time: now,
+            end_time,
+            starting_price,
+            currency: currency.clone(),
+            status: AuctionStatus::Active,
+            escrow_provider: Self::current_escrow_provider(&env),
+            escrow_settled: false,
+            reserve_price: None,
+            reserve_hidden: false,
+            soft_close: Self::current_soft_close(&env),
+            extended_seconds: 0,
+            bid_history_limit: Self::current_bid_history_limit(&env),
+            buy_now_price: None,
+            format: AuctionFormat::Drop(edition_size),
+        };
+
+        Self::write_entry(&env, &DataKey::Auction(auction_id), &auction);
+        if royalty_bps > 0 {
+            Self::write_entry(&env, &DataKey::DropRoyalty(auction_id), &royalty_bps);
+        }
+        env.storage().instance().set(&DataKey::NextAuctionId, &(auction_id + 1));
+        env.events().publish(
+            (symbol_short!("drop"), auction_id, creator),
+            (edition_size, starting_price, currency, end_time),
+        );
+        Ok(auction_id)
+    }
+
+    /// Price a Dutch auction sells at right now, in its own currency.
+    pub fn current_price(env: Env, auction_id: u64) -> Result<i128, AuctionError> {
+        let auction: AuctionInfo = Self::load_auction(&env, auction_id)
+            .ok_or(AuctionError::AuctionNotFound)?;
+        match &auction.format {
+            AuctionFormat::Dutch(schedule) => {
+                Ok(Self::dutch_price(&auction, schedule, env.ledger().timestamp()))
+            }
+            AuctionFormat::English | AuctionFormat::Sealed(_) | AuctionFormat::Drop(_) => {
+                Err(AuctionError::WrongAuctionFormat)
+            }
+        }
+    }
+
+    /// Auctions `token_id` with sealed bids: `commit_bid` until the end time, then
+    /// `reveal_bid` for `rules.reveal_seconds`, then `finalize`. Every deposit is
+    /// settled afterwards through `release_sealed_deposit`.
+    pub fn create_sealed_auction(
+        env: Env,
+        seller: Address,
+        token_id: u64,
+        starting_price: i128,
+        currency: Currency,
+        duration_seconds: u64,
+        rules: SealedBidRules,
+    ) -> Result<u64, AuctionError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
+        seller.require_auth();
+        if rules.reveal_seconds < MIN_AUCTION_DURATION
+            || rules.reveal_seconds > MAX_AUCTION_DURATION
+            || rules.slash_bps > 10_000
+        {
+            return Err(AuctionError::InvalidSealedRules);
+        }
+        Self::open_item_auction(
+            &env,
+            seller,
+            token_id,
+            starting_price,
+            currency,
+            duration_seconds,
+            AuctionFormat::Sealed(rules),
+            AuctionOptions::default(),
+        )
+    }
+
+    /// Commits to a sealed bid and locks `deposit` in the auction currency. The
+    /// commitment is `sha256(auction_id as 8 big-endian bytes || bidder.to_xdr() ||
+    /// amount as 16 big-endian bytes || salt)`. One commitment per bidder.
+    pub fn commit_bid(
+        env: Env,
+        auction_id: u64,
+        bidder: Address,
+        commitment: BytesN<32>,
+        deposit: i128,
+    ) -> Result<(), AuctionError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
+        bidder.require_auth();
+        let auction: AuctionInfo = Self::load_auction(&env, auction_id)
+            .ok_or(AuctionError::AuctionNotFound)?;
+        if !matches!(auction.format, AuctionFormat::Sealed(_)) {
+            return Err(AuctionError::WrongAuctionFormat);
+        }
+        Self::require_open(&auction)?;
+        if env.ledger().timestamp() > auction.end_time {
+            return Err(AuctionError::AuctionEnded);
+        }
+        let key = DataKey::SealedBid(auction_id, bidder.clone());
+        if env.storage().persistent().has(&key) {
+            return Err(AuctionError::AlreadyCommitted);
+        }
+        if deposit < auction.starting_price {
+            return Err(AuctionError::DepositTooLow);
+        }
+
+        let token_addr = match auction.currency {
+            Currency::Xlm => env.storage().instance().get::<DataKey, Address>(&DataKey::XlmToken).unwrap(),
+            Currency::Usdc => env.storage().instance().get::<DataKey, Address>(&DataKey::UsdcToken).unwrap(),
+        };
+        let token_client = token::Client::new(&env, &token_addr);
+        token_client.transfer(&bidder, &env.current_contract_address(), &deposit);
+        Self::adjust_held(&env, &auction.currency, deposit);
+
+        let sealed = SealedBid {
+            commitment,
+            deposit,
+            revealed_amount: None,
+            released: false,
+        };
+        Self::write_entry(&env, &key, &sealed);
+        let commits: u32 =
+            Self::read_entry(&env, &DataKey::CommitCount(auction_id)).unwrap_or(0);
+        Self::write_entry(&env, &DataKey::CommitCount(auction_id), &(commits + 1));
+        env.events()
+            .publish((symbol_short!("committed"), auction_id, bidder), deposit);
+        Ok(())
+    }
+
+    /// Opens a commitment during the reveal phase. Reveals below the starting
+    /// price or above the deposit are rejected and count as not revealed.
+    pub fn reveal_bid(
+        env: Env,
+        auction_id: u64,
+        bidder: Address,
+        amount: i128,
+        salt: BytesN<32>,
+    ) -> Result<(), AuctionError> {
+        bidder.require_auth();
+        let auction: AuctionInfo = Self::load_auction(&env, auction_id)
+            .ok_or(AuctionError::AuctionNotFound)?;
+        if !matches!(auction.format, AuctionFormat::Sealed(_)) {
+            return Err(AuctionError::WrongAuctionFormat);
+        }
+        Self::require_open(&auction)?;
+        let now = env.ledger().timestamp();
+        if now <= auction.end_time || now > Self::closes_at(&auction) {
+            return Err(AuctionError::NotRevealPhase);
+        }
+        let key = DataKey::SealedBid(auction_id, bidder.clone());
+        let mut sealed: SealedBid =
+            Self::read_entry(&env, &key).ok_or(AuctionError::NoCommitment)?;
+        if sealed.revealed_amount.is_some()
+            || Self::sealed_commitment(&env, auction_id, &bidder, amount, &salt)
+                != sealed.commitment
+        {
+            return Err(AuctionError::RevealMismatch);
+        }
+        if amount < auction.starting_price {
+            return Err(AuctionError::BidBelowMinimum);
+        }
+        if amount > sealed.deposit {
+            return Err(AuctionError::DepositTooLow);
+        }
+        sealed.revealed_amount = Some(amount);
+        Self::write_entry(&env, &key, &sealed);
+        let revealed = BidInfo {
+            bidder: bidder.clone(),
+            amount,
+            currency: auction.currency.clone(),
+        };
+        Self::record_bid(&env, &mut XlmRate::default(), auction_id, &auction, &revealed);
+
+        // Keep the top two reveals; on a tie the earlier reveal keeps the lead.
+        let highest: Option<BidInfo> = Self::read_entry(&env, &DataKey::HighestBid(auction_id));
+        let second: i128 = Self::read_entry(&env, &DataKey::SecondBid(auction_id)).unwrap_or(0);
+        match highest {
+            Some(top) if amount <= top.amount => {
+                if amount > second {
+                    Self::write_entry(&env, &DataKey::SecondBid(auction_id), &amount);
+                }
+            }
+            _ => {
+                if let Some(top) = highest {
+                    Self::write_entry(&env, &DataKey::SecondBid(auction_id), &top.amount);
+                }
+                let bid = BidInfo {
+                    bidder: bidder.clone(),
+                    amount,
+                    currency: auction.currency.clone(),
+                };
+                Self::write_entry(&env, &DataKey::HighestBid(auction_id), &bid);
+            }
+        }
+        env.events()
+            .publish((symbol_short!("revealed"), auction_id, bidder), amount);
+        Ok(())
+    }
+
+    /// Returns what is left of a sealed-bid deposit once the auction is finalized:
+    /// all of it for losing reveals, the excess over the price paid for the winner,
+    /// and, for a bidder who never revealed, all but the slashed share, which goes
+    /// to the seller. A cancelled auction returns every deposit in full. Anyone may call it.
+    pub fn release_sealed_deposit(
+        env: Env,
+        auction_id: u64,
+        bidder: Address,
+    ) -> Result<(), AuctionError> {
+        let auction: AuctionInfo = Self::load_auction(&env, auction_id)
+            .ok_or(AuctionError::AuctionNotFound)?;
+        let AuctionFormat::Sealed(rules) = &auction.format else {
+            return Err(AuctionError::WrongAuctionFormat);
+        };
+        if auction.status == AuctionStatus::Active {
+            return Err(AuctionError::NotFinalized);
+        }
+        let key = DataKey::SealedBid(auction_id, bidder.clone());
+        let mut sealed: SealedBid =
+            Self::read_entry(&env, &key).ok_or(AuctionError::NoCommitment)?;
+        if sealed.released {
+            return Err(AuctionError::DepositAlreadyReleased);
+        }
+
+        let winning_bid: Option<BidInfo> = Self::read_entry(&env, &DataKey::HighestBid(auction_id));
+        let (refund, slashed) = match (sealed.revealed_amount, winning_bid) {
+            _ if auction.status == AuctionStatus::Cancelled => (sealed.deposit, 0),
+            (None, _) => {
+                let slashed = sealed.deposit * rules.slash_bps as i128 / 10_000;
+                (sealed.deposit - slashed, slashed)
+            }
+            (Some(_), Some(winner)) if winner.bidder == bidder => {
+                (sealed.deposit - winner.amount, 0)
+            }
+            (Some(_), _) => (sealed.deposit, 0),
+        };
+        sealed.released = true;
+        Self::write_entry(&env, &key, &sealed);
+
+        let token_addr = match auction.currency {
+            Currency::Xlm => env.storage().instance().get::<DataKey, Address>(&DataKey::XlmToken).unwrap(),
+            Currency::Usdc => env.storage().instance().get::<DataKey, Address>(&DataKey::UsdcToken).unwrap(),
+        };
+        let token_client = token::Client::new(&env, &token_addr);
+        if refund > 0 {
+            token_client.transfer(&env.current_contract_address(), &bidder, &refund);
+        }
+        if slashed > 0 {
+            token_client.transfer(&env.current_contract_address(), &auction.seller, &slashed);
+        }
+        Self::adjust_held(&env, &auction.currency, -(refund + slashed));
+        env.events().publish(
+            (symbol_short!("dep_rel"), auction_id, bidder),
+            (refund, slashed),
+        );
+        Ok(())
+    }
+
+    pub fn get_sealed_bid(
+        env: Env,
+        auction_id: u64,
+        bidder: Address,
+    ) -> Result<SealedBid, AuctionError> {
+        Self::read_entry(&env, &DataKey::SealedBid(auction_id, bidder))
+            .ok_or(AuctionError::NoCommitment)
+    }
+
+    pub fn bid_xlm(
+        env: Env,
+        auction_id: u64,
+        bidder: Address,
+        amount: i128,
+    ) -> Result<(), AuctionError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
+        bidder.require_auth();
+        Self::place_bid(&env, auction_id, bidder, amount, Currency::Xlm)
+    }
+
+    pub fn bid_usdc(
+        env: Env,
+        auction_id: u64,
+        bidder: Address,
+        amount: i128,
+    ) -> Result<(), AuctionError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
+        bidder.require_auth();
+        Self::place_bid(&env, auction_id, bidder, amount, Currency::Usdc)
+    }
+
+    pub fn buy_now_xlm(env: Env, auction_id: u64, buyer: Address) -> Result<(), AuctionError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
+        Self::require_not_paused(&env, PauseFlag::Settlements)?;
+        buyer.require_auth();
+        Self::buy_now(&env, auction_id, buyer, Currency::Xlm)
+    }
+
+    pub fn buy_now_usdc(env: Env, auction_id: u64, buyer: Address) -> Result<(), AuctionError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
+        Self::require_not_paused(&env, PauseFlag::Settlements)?;
+        buyer.require_auth();
+        Self::buy_now(&env, auction_id, buyer, Currency::Usdc)
+    }
+
+    /// Ends a live auction at its buy-now price (a Dutch auction's current price):
+    /// the standing top bid is refunded and the item settles to `buyer` exactly as
+    /// `finalize` would settle it.
+    fn buy_now(
+        env: &Env,
+        auction_id: u64,
+        buyer: Address,
+        currency: Currency,
+    ) -> Result<(), AuctionError> {
+        let mut auction: AuctionInfo = Self::load_auction(env, auction_id)
+            .ok_or(AuctionError::AuctionNotFound)?;
+
+        Self::require_open(&auction)?;
+        let now = env.ledger().timestamp();
+        if now > auction.end_time {
+            return Err(AuctionError::AuctionEnded);
+        }
+        let buy_now_price = match &auction.format {
+            AuctionFormat::Dutch(schedule) => Self::dutch_price(&auction, schedule, now),
+            AuctionFormat::English => {
+                auction.buy_now_price.ok_or(AuctionError::BuyNowUnavailable)?
+            }
+            AuctionFormat::Sealed(_) | AuctionFormat::Drop(_) => {
+                return Err(AuctionError::WrongAuctionFormat)
+            }
+        };
+        let mut rate = XlmRate::default();
+        let amount =
+            Self::convert_amount(env, &mut rate, buy_now_price, &auction.currency, &currency)?;
+
+        // Once bidding has reached the buy-now price the auction has to run its course.
+        let highest_bid: Option<BidInfo> = Self::read_entry(env, &DataKey::HighestBid(auction_id));
+        if let Some(current_bid) = &highest_bid {
+            let current_value = Self::convert_amount(
+                env,
+                &mut rate,
+                current_bid.amount,
+                &current_bid.currency,
+                &auction.currency,
+            )?;
+            if current_value >= buy_now_price {
+                return Err(AuctionError::BuyNowUnavailable);
+            }
+        }
+
+        let token_addr = match currency {
+            Currency::Xlm => env.storage().instance().get::<DataKey, Address>(&DataKey::XlmToken).unwrap(),
+            Currency::Usdc => env.storage().instance().get::<DataKey, Address>(&DataKey::UsdcToken).unwrap(),
+        };
+        let token_client = token::Client::new(env, &token_addr);
+        token_client.transfer(&buyer, &env.current_contract_address(), &amount);
+        Self::adjust_held(env, &currency, amount);
+
+        if let Some(current_bid) = &highest_bid {
+            Self::refund_bid(env, auction_id, current_bid);
+        }
+
+        let winning_bid = BidInfo {
+            bidder: buyer.clone(),
+            amount,
+            currency: currency.clone(),
+        };
+        Self::write_entry(env, &DataKey::HighestBid(auction_id), &winning_bid);
+
+        auction.end_time = now;
+        auction.status = AuctionStatus::Finalized;
+        Self::settle_item_sale(env, &auction, &winning_bid)?;
+        auction.escrow_settled = true;
+        Self::write_entry(env, &DataKey::Auction(auction_id), &auction);
+
+        env.events().publish(
+            (symbol_short!("buy_now"), auction_id, buyer),
+            (amount, currency),
+        );
+        env.events()
+            .publish((symbol_short!("finalized"), auction_id), Some(winning_bid));
+        Ok(())
+    }
+
+    fn place_bid(
+        env: &Env,
+        auction_id: u64,
+        bidder: Address,
+        amount: i128,
+        currency: Currency,
+    ) -> Result<(), AuctionError> {
+        let mut auction: AuctionInfo = Self::load_auction(env, auction_id)
+            .ok_or(AuctionError::AuctionNotFound)?;
+
+        Self::require_open(&auction)?;
+        let now = env.ledger().timestamp();
+        if now > auction.end_time {
+            return Err(AuctionError::AuctionEnded);
+        }
+        match auction.format {
+            AuctionFormat::English => {}
+            AuctionFormat::Drop(editions) => {
+                return Self::place_drop_bid(
+                    env, auction_id, auction, editions, bidder, amount, currency,
+                );
+            }
+            AuctionFormat::Dutch(_) | AuctionFormat::Sealed(_) => {
+                return Err(AuctionError::WrongAuctionFormat);
             }
-            env.storage()
-                .instance()
-                .set(&MockNftDataKey::Owner(token_id), &to);
         }
+
+        // Check bid meets the minimum starting price.
+        // Same currency → direct comparison; cross-currency → normalize both to USDC via oracle.
+        let mut rate = XlmRate::default();
+        if currency == auction.currency {
+            if amount < auction.starting_price {
+                return Err(AuctionError::BidBelowMinimum);
+            }
+        } else {
+            let bid_usdc = Self::normalize_to_usdc(env, &mut rate, amount, &currency)?;
+            let starting_usdc =
+                Self::normalize_to_usdc(env, &mut rate, auction.starting_price, &auction.currency)?;
+            if bid_usdc < starting_usdc {
+                return Err(AuctionError::BidBelowMinimum);
+            }
+        }
+
+        // Check against current highest bid.
+        // Same currency as highest bid → direct comparison; different → normalize to USDC via oracle.
+        let has_existing_bid = env.storage().persistent().has(&DataKey::HighestBid(auction_id));
+        if has_existing_bid {
+            let current_bid: BidInfo = Self::read_entry(env, &DataKey::HighestBid(auction_id))
+                .unwrap();
+
+            if currency == current_bid.currency {
+                // Same currency: compare directly
+                let min_required = current_bid.amount + (current_bid.amount * MIN_INCREMENT_BPS / 10_000);
+                if amount < min_required {
+                    return Err(AuctionError::BidIncrementTooLow);
+                }
+            } else {
+                // Cross-currency: normalize both to USDC
+                let bid_usdc = Self::normalize_to_usdc(env, &mut rate, amount, &currency)?;
+                let current_usdc = Self::normalize_to_usdc(
+                    env,
+                    &mut rate,
+                    current_bid.amount,
+                    &current_bid.currency,
+                )?;
+                let required_usdc = current_usdc + (current_usdc * MIN_INCREMENT_BPS / 10_000);
+                if bid_usdc < required_usdc {
+                    return Err(AuctionError::BidIncrementTooLow);
+                }
+            }
+
+            // Refund previous bidder in their original currency
+            Self::refund_bid(env, auction_id, &current_bid);
+        }
+
+        // Transfer bid amount to contract
+        let token_addr = match currency {
+            Currency::Xlm => env.storage().instance().get::<DataKey, Address>(&DataKey::XlmToken).unwrap(),
+            Currency::Usdc => env.storage().instance().get::<DataKey, Address>(&DataKey::UsdcToken).unwrap(),
+        };
+        let token_client = token::Client::new(env, &token_addr);
+        token_client.transfer(&bidder, &env.current_contract_address(), &amount);
+        Self::adjust_held(env, &currency, amount);
+
+        env.events().publish(
+            (symbol_short!("bid"), auction_id, bidder.clone()),
+            (amount, currency.clone()),
+        );
+        let bid = BidInfo {
+            bidder,
+            amount,
+            currency,
+        };
+        Self::write_entry(env, &DataKey::HighestBid(auction_id), &bid);
+        Self::record_bid(env, &mut rate, auction_id, &auction, &bid);
+        Self::apply_soft_close(env, auction_id, &mut auction, now);
+        Ok(())
+    }
+
+    /// Drop bids compete for `editions` places, in the auction currency only. Once
+    /// every place is taken a new bid must beat the lowest one by the minimum
+    /// increment, and that lowest bid is refunded.
+    fn place_drop_bid(
+        env: &Env,
+        auction_id: u64,
+        mut auction: AuctionInfo,
+        editions: u32,
+        bidder: Address,
+        amount: i128,
+        currency: Currency,
+    ) -> Result<(), AuctionError> {
+        if currency != auction.currency {
+            return Err(AuctionError::CurrencyMismatch);
+        }
+        if amount < auction.starting_price {
+            return Err(AuctionError::BidBelowMinimum);
+        }
+
+        let mut bids: Vec<BidInfo> =
+            Self::read_entry(env, &DataKey::DropBids(auction_id)).unwrap_or(Vec::new(env));
+        if bids.len() >= editions {
+            let lowest = bids.last().unwrap();
+            let min_required = lowest.amount + (lowest.amount * MIN_INCREMENT_BPS / 10_000);
+            if amount < min_required {
+                return Err(AuctionError::BidIncrementTooLow);
+            }
+            bids.pop_back();
+            Self::refund_bid(env, auction_id, &lowest);
+        }
+
+        let token_addr = match currency {
+            Currency::Xlm => env.storage().instance().get::<DataKey, Address>(&DataKey::XlmToken).unwrap(),
+            Currency::Usdc => env.storage().instance().get::<DataKey, Address>(&DataKey::UsdcToken).unwrap(),
+        };
+        let token_client = token::Client::new(env, &token_addr);
+        token_client.transfer(&bidder, &env.current_contract_address(), &amount);
+        Self::adjust_held(env, &currency, amount);
+
+        env.events().publish(
+            (symbol_short!("bid"), auction_id, bidder.clone()),
+            (amount, currency.clone()),
+        );
+        let bid = BidInfo {
+            bidder,
+            amount,
+            currency,
+        };
+        // Highest first; equal bids keep their arrival order.
+        let position = bids.iter().position(|b| b.amount < amount).unwrap_or(bids.len() as usize);
+        bids.insert(position as u32, bid.clone());
+        Self::write_entry(env, &DataKey::DropBids(auction_id), &bids);
+        Self::record_bid(env, &mut XlmRate::default(), auction_id, &auction, &bid);
+        Self::apply_soft_close(env, auction_id, &mut auction, env.ledger().timestamp());
+        Ok(())
+    }
+
+    /// Soft close: a late bid gives everyone else time to answer it.
+    fn apply_soft_close(env: &Env, auction_id: u64, auction: &mut AuctionInfo, now: u64) {
+        if auction.end_time - now < auction.soft_close.window_seconds {
+            let headroom = auction.soft_close.max_extension_seconds - auction.extended_seconds;
+            let extension = auction.soft_close.extension_seconds.min(headroom);
+            if extension > 0 {
+                auction.end_time += extension;
+                auction.extended_seconds += extension;
+                Self::write_entry(env, &DataKey::Auction(auction_id), &*auction);
+                env.events().publish(
+                    (symbol_short!("extended"), auction_id),
+                    auction.end_time,
+                );
+            }
+        }
+    }
+
+    pub fn finalize(env: Env, auction_id: u64) -> Result<(), AuctionError> {
+        Self::require_not_paused(&env, PauseFlag::Settlements)?;
+        let mut auction: AuctionInfo = Self::load_auction(&env, auction_id)
+            .ok_or(AuctionError::AuctionNotFound)?;
+
+        Self::require_open(&auction)?;
+        let now = env.ledger().timestamp();
+        if now <= Self::closes_at(&auction) {
+            return Err(AuctionError::AuctionNotEnded);
+        }
+        if let AuctionFormat::Drop(_) = auction.format {
+            return Self::settle_drop(&env, auction_id, auction);
+        }
+
+        auction.status = AuctionStatus::Finalized;
+        Self::write_entry(&env, &DataKey::Auction(auction_id), &auction);
+
+        let mut highest_bid: Option<BidInfo> =
+            Self::read_entry(&env, &DataKey::HighestBid(auction_id));
+
+        // A Vickrey winner pays the runner-up's bid. The price is recorded as the
+        // winning amount; the rest of the deposit comes back via `release_sealed_deposit`.
+        if let AuctionFormat::Sealed(rules) = &auction.format {
+            if let Some(bid) = highest_bid.as_mut() {
+                if rules.pricing == SealedPricing::SecondPrice {
+                    let second: Option<i128> =
+                        Self::read_entry(&env, &DataKey::SecondBid(auction_id));
+                    bid.amount = second.unwrap_or(auction.starting_price);
+                    Self::write_entry(&env, &DataKey::HighestBid(auction_id), &*bid);
+                }
+            }
+        }
+
+        // A top bid under the reserve loses: it is refunded and the item stays with the seller.
+        if let Some(bid) = &highest_bid {
+            if !Self::reserve_reached(&env, &mut XlmRate::default(), &auction, Some(bid))? {
+                Self::refund_bid(&env, auction_id, bid);
+                highest_bid = None;
+            }
+        }
+
+        // If there's a winning bid, mint NFT to winner
+        if let Some(winning_bid) = &highest_bid {
+            if auction.is_item_auction {
+                Self::settle_item_sale(&env, &auction, winning_bid)?;
+                auction.escrow_settled = true;
+                Self::write_entry(&env, &DataKey::Auction(auction_id), &auction);
+            } else {
+                let nft_contract: Address = env.storage().instance().get(&DataKey::NftContract).unwrap();
+
+                // Cross-contract call to mint NFT
+                let mint_args = (
+                    env.current_contract_address(),
+                    winning_bid.bidder.clone(),
+                    auction.token_uri.clone(),
+                );
+                env.invoke_contract::<u64>(
+                    &nft_contract,
+                    &Symbol::new(&env, "mint"),
+                    mint_args.into_val(&env),
+                );
+
+                if auction.escrow_provider == EscrowProvider::TrustlessWork {
+                    let destination = Self::trustless_escrow_destination(&env, &winning_bid.currency)?;
+                    let token_addr = match winning_bid.currency {
+                        Currency::Xlm => env.storage().instance().get::<DataKey, Address>(&DataKey::XlmToken).unwrap(),
+                        Currency::Usdc => env.storage().instance().get::<DataKey, Address>(&DataKey::UsdcToken).unwrap(),
+                    };
+                    let token_client = token::Client::new(&env, &token_addr);
+                    token_client.transfer(
+                        &env.current_contract_address(),
+                        &destination,
+                        &winning_bid.amount,
+                    );
+                    auction.escrow_settled = true;
+                    Self::write_entry(&env, &DataKey::Auction(auction_id), &auction);
+                }
+                // Internally escrowed proceeds of an admin auction belong to the platform.
+                Self::adjust_held(&env, &winning_bid.currency, -winning_bid.amount);
+            }
+        } else if auction.is_item_auction {
+            Self::nft_transfer(
+                &env,
+                &env.current_contract_address(),
+                &auction.seller,
+                auction.token_id,
+            );
+        }
+
+        env.events()
+            .publish((symbol_short!("finalized"), auction_id), highest_bid);
+        Ok(())
+    }
+
+    /// Pays out everything credited to `bidder` in `currency` from outbid,
+    /// below-reserve or cancelled bids. Never paused.
+    pub fn claim_refund(env: Env, bidder: Address, currency: Currency) -> Result<i128, AuctionError> {
+        bidder.require_auth();
+        let key = DataKey::PendingRefund(bidder.clone(), currency.clone());
+        let amount: i128 = Self::read_entry(&env, &key).unwrap_or(0);
+        if amount <= 0 {
+            return Err(AuctionError::NothingToClaim);
+        }
+        env.storage().persistent().remove(&key);
+
+        let token_addr = match currency {
+            Currency::Xlm => env.storage().instance().get::<DataKey, Address>(&DataKey::XlmToken).unwrap(),
+            Currency::Usdc => env.storage().instance().get::<DataKey, Address>(&DataKey::UsdcToken).unwrap(),
+        };
+        let token_client = token::Client::new(&env, &token_addr);
+        token_client.transfer(&env.current_contract_address(), &bidder, &amount);
+        Self::adjust_held(&env, &currency, -amount);
+        env.events()
+            .publish((symbol_short!("claimed"), bidder), (amount, currency));
+        Ok(amount)
+    }
+
+    /// Claimable refund balances for `bidder`, as `(xlm, usdc)`.
+    pub fn pending_refunds(env: Env, bidder: Address) -> (i128, i128) {
+        let xlm: i128 = Self::read_entry(&env, &DataKey::PendingRefund(bidder.clone(), Currency::Xlm))
+            .unwrap_or(0);
+        let usdc: i128 = Self::read_entry(&env, &DataKey::PendingRefund(bidder, Currency::Usdc))
+            .unwrap_or(0);
+        (xlm, usdc)
+    }
+
+    /// Lets the seller take their item back before anyone has bid on it
+    /// (or committed a sealed bid).
+    pub fn cancel_auction(env: Env, seller: Address, auction_id: u64) -> Result<(), AuctionError> {
+        seller.require_auth();
+        let auction: AuctionInfo = Self::load_auction(&env, auction_id)
+            .ok_or(AuctionError::AuctionNotFound)?;
+        if auction.seller != seller {
+            return Err(AuctionError::NotSeller);
+        }
+        let commits: u32 = Self::read_entry(&env, &DataKey::CommitCount(auction_id)).unwrap_or(0);
+        if env.storage().persistent().has(&DataKey::HighestBid(auction_id))
+            || env.storage().persistent().has(&DataKey::DropBids(auction_id))
+            || commits > 0
+        {
+            return Err(AuctionError::AuctionHasBids);
+        }
+        Self::cancel(&env, auction_id, auction, seller)
+    }
+
+    /// Admin escape hatch for any unsettled auction: refunds the standing bid
+    /// and returns the item to the seller.
+    pub fn force_cancel_auction(env: Env, auction_id: u64) -> Result<(), AuctionError> {
+        let admin = Self::require_admin(&env);
+        let auction: AuctionInfo = Self::load_auction(&env, auction_id)
+            .ok_or(AuctionError::AuctionNotFound)?;
+        Self::cancel(&env, auction_id, auction, admin)
+    }
+
+    /// Sets the treasury and the fee taken from every auction sale.
+    pub fn configure_fees(env: Env, config: FeeConfig) -> Result<(), AuctionError> {
+        Self::require_admin(&env);
+        if config.auction_bps > MAX_FEE_BPS {
+            return Err(AuctionError::InvalidFee);
+        }
+        env.storage().instance().set(&DataKey::FeeConfig, &config);
+        Ok(())
+    }
+
+    pub fn get_fee_config(env: Env) -> Option<FeeConfig> {
+        env.storage().instance().get(&DataKey::FeeConfig)
+    }
+
+    pub fn accrued_fees(env: Env, currency: Currency) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::AccruedFees(currency))
+            .unwrap_or(0)
+    }
+
+    /// Sends every accrued fee in `currency` to the treasury. Admin or treasurers only.
+    pub fn collect_fees(
+        env: Env,
+        caller: Address,
+        currency: Currency,
+    ) -> Result<i128, AuctionError> {
+        Self::require_not_paused(&env, PauseFlag::Withdrawals)?;
+        caller.require_auth();
+        if !Self::has_role(env.clone(), Role::Admin, caller.clone())
+            && !Self::has_role(env.clone(), Role::Treasurer, caller.clone())
+        {
+            return Err(AuctionError::NotTreasurer);
+        }
+        let amount = Self::accrued_fees(env.clone(), currency.clone());
+        if amount == 0 {
+            return Err(AuctionError::NoFeesAccrued);
+        }
+        let config: FeeConfig = env.storage().instance().get(&DataKey::FeeConfig).unwrap();
+        env.storage().instance().remove(&DataKey::AccruedFees(currency.clone()));
+
+        let token_addr = match currency {
+            Currency::Xlm => env.storage().instance().get::<DataKey, Address>(&DataKey::XlmToken).unwrap(),
+            Currency::Usdc => env.storage().instance().get::<DataKey, Address>(&DataKey::UsdcToken).unwrap(),
+        };
+        let token_client = token::Client::new(&env, &token_addr);
+        token_client.transfer(&env.current_contract_address(), &config.treasury, &amount);
+        Self::adjust_held(&env, &currency, -amount);
+        env.events()
+            .publish((symbol_short!("fees"), config.treasury), (amount, currency));
+        Ok(amount)
+    }
+
+    /// What the contract holds for others in `currency`: standing bids, sealed
+    /// deposits, credited refunds and accrued fees. Withdrawals cannot touch it.
+    pub fn held_funds(env: Env, currency: Currency) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::HeldFunds(currency))
+            .unwrap_or(0)
+    }
+
+    pub fn withdraw_xlm(env: Env, amount: i128) -> Result<(), AuctionError> {
+        Self::withdraw_free_balance(&env, Currency::Xlm, amount)
+    }
+
+    pub fn withdraw_usdc(env: Env, amount: i128) -> Result<(), AuctionError> {
+        Self::withdraw_free_balance(&env, Currency::Usdc, amount)
+    }
+
+    pub fn get_auction(env: Env, auction_id: u64) -> Result<AuctionInfo, AuctionError> {
+        let mut auction: AuctionInfo = Self::load_auction(&env, auction_id)
+            .ok_or(AuctionError::AuctionNotFound)?;
+        if auction.reserve_hidden && !Self::reserve_met(env, auction_id).unwrap_or(false) {
+            auction.reserve_price = None;
+        }
+        Ok(auction)
+    }
+
+    /// Whether the current highest bid would win at `finalize`. Always true
+    /// without a reserve; always false for a reserve auction with no bids.
+    pub fn reserve_met(env: Env, auction_id: u64) -> Result<bool, AuctionError> {
+        let auction: AuctionInfo = Self::load_auction(&env, auction_id)
+            .ok_or(AuctionError::AuctionNotFound)?;
+        let highest_bid: Option<BidInfo> = Self::read_entry(&env, &DataKey::HighestBid(auction_id));
+        Self::reserve_reached(&env, &mut XlmRate::default(), &auction, highest_bid.as_ref())
+    }
+
+    pub fn get_highest_bid(env: Env, auction_id: u64) -> Result<BidInfo, AuctionError> {
+        Self::read_entry(&env, &DataKey::HighestBid(auction_id)).ok_or(AuctionError::NoBids)
+    }
+
+    /// Standing bids of a drop auction, highest first; each wins an edition at `finalize`.
+    pub fn get_drop_bids(env: Env, auction_id: u64) -> Vec<BidInfo> {
+        Self::read_entry(&env, &DataKey::DropBids(auction_id)).unwrap_or(Vec::new(&env))
+    }
+
+    /// Number of bids ever placed (or sealed bids revealed) on the auction,
+    /// including ones that have since rotated out of the log.
+    pub fn bid_count(env: Env, auction_id: u64) -> u32 {
+        Self::read_entry(&env, &DataKey::BidCount(auction_id)).unwrap_or(0)
+    }
+
+    /// Returns up to `limit` logged bids in the order they were placed, starting
+    /// at bid number `start`. Bids that rotated out of the log are skipped, so
+    /// the first entry may be later than `start`.
+    pub fn get_bids(
+        env: Env,
+        auction_id: u64,
+        start: u32,
+        limit: u32,
+    ) -> Result<Vec<BidRecord>, AuctionError> {
+        let auction: AuctionInfo = Self::load_auction(&env, auction_id)
+            .ok_or(AuctionError::AuctionNotFound)?;
+        let count = Self::bid_count(env.clone(), auction_id);
+        let oldest_kept = count.saturating_sub(auction.bid_history_limit);
+        let end = count.min(start.saturating_add(limit.min(MAX_PAGE_LIMIT)));
+        let mut bids = Vec::new(&env);
+        for index in start.max(oldest_kept)..end {
+            let slot = index % auction.bid_history_limit;
+            let record: BidRecord =
+                Self::read_entry(&env, &DataKey::BidLog(auction_id, slot)).unwrap();
+            bids.push_back(record);
+        }
+        Ok(bids)
+    }
+
+    /// Permissionless keep-alive for an auction record, its auction-wide bid records
+    /// and log, and the contract instance. Anyone may call it; it only extends TTLs.
+    /// Sealed commitments and credited refunds are kept per bidder; see
+    /// `bump_sealed_bid` and `bump_refund`.
+    pub fn bump_auction(env: Env, auction_id: u64) -> Result<(), AuctionError> {
+        if !Self::extend_entry(&env, &DataKey::Auction(auction_id)) {
+            return Err(AuctionError::AuctionNotFound);
+        }
+        Self::extend_entry(&env, &DataKey::HighestBid(auction_id));
+        Self::extend_entry(&env, &DataKey::DropBids(auction_id));
+        Self::extend_entry(&env, &DataKey::DropRoyalty(auction_id));
+        Self::extend_entry(&env, &DataKey::SecondBid(auction_id));
+        Self::extend_entry(&env, &DataKey::CommitCount(auction_id));
+        Self::extend_entry(&env, &DataKey::BidCount(auction_id));
+        let mut slot = 0;
+        while Self::extend_entry(&env, &DataKey::BidLog(auction_id, slot)) {
+            slot += 1;
+        }
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Permissionless keep-alive for one bidder's sealed commitment and deposit.
+    pub fn bump_sealed_bid(env: Env, auction_id: u64, bidder: Address) -> Result<(), AuctionError> {
+        if !Self::extend_entry(&env, &DataKey::SealedBid(auction_id, bidder)) {
+            return Err(AuctionError::NoCommitment);
+        }
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Permissionless keep-alive for a refund credited to `bidder` in `currency`.
+    pub fn bump_refund(env: Env, bidder: Address, currency: Currency) -> Result<(), AuctionError> {
+        if !Self::extend_entry(&env, &DataKey::PendingRefund(bidder, currency)) {
+            return Err(AuctionError::NothingToClaim);
+        }
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    pub fn total_auctions(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::NextAuctionId)
+            .unwrap_or(0u64)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        contract,
+        contractimpl,
+        contracttype,
+        testutils::Address as _,
+        testutils::Events,
+        testutils::IssuerFlags,
+        testutils::Ledger,
+        testutils::storage::Persistent as _,
+        token::{StellarAssetClient, TokenClient},
+        vec, Env, String, Val, Vec,
+    };
+
+    // MockNft — unchanged
+    #[contract]
+    struct MockNft;
+
+    #[contracttype]
+    #[derive(Clone)]
+    enum MockNftDataKey {
+        NextTokenId,
+        Owner(u64),
+        TokenUri(u64),
+        Royalty(u64),
+        Creator(u64),
+    }
+
+    #[contractimpl]
+    impl MockNft {
+        pub fn mint(env: Env, _minter: Address, to: Address, token_uri: String) -> u64 {
+            let next_id: u64 = env
+                .storage()
+                .instance()
+                .get(&MockNftDataKey::NextTokenId)
+                .unwrap_or(0u64);
+            env.storage()
+                .instance()
+                .set(&MockNftDataKey::Owner(next_id), &to);
+            env.storage()
+                .instance()
+                .set(&MockNftDataKey::TokenUri(next_id), &token_uri);
+            env.storage()
+                .instance()
+                .set(&MockNftDataKey::NextTokenId, &(next_id + 1));
+            env.storage()
+                .instance()
+                .set(&MockNftDataKey::Creator(next_id), &to);
+            next_id
+        }
+
+        pub fn mint_edition(
+            env: Env,
+            minter: Address,
+            to: Address,
+            token_uri: String,
+            creator: Address,
+            royalty_bps: u32,
+        ) -> u64 {
+            let token_id = Self::mint(env.clone(), minter, to, token_uri);
+            env.storage()
+                .instance()
+                .set(&MockNftDataKey::Creator(token_id), &creator);
+            Self::set_royalty(env, token_id, creator, royalty_bps);
+            token_id
+        }
+
+        pub fn creator_of(env: Env, token_id: u64) -> Address {
+            env.storage()
+                .instance()
+                .get(&MockNftDataKey::Creator(token_id))
+                .unwrap_or_else(|| panic!("token does not exist"))
+        }
+
+        pub fn owner_of(env: Env, token_id: u64) -> Address {
+            env.storage()
+                .instance()
+                .get(&MockNftDataKey::Owner(token_id))
+                .unwrap_or_else(|| panic!("token does not exist"))
+        }
+
+        pub fn token_uri(env: Env, token_id: u64) -> String {
+            env.storage()
+                .instance()
+                .get(&MockNftDataKey::TokenUri(token_id))
+                .unwrap_or_else(|| panic!("token does not exist"))
+        }
+
+        pub fn set_royalty(env: Env, token_id: u64, receiver: Address, royalty_bps: u32) {
+            env.storage()
+                .instance()
+                .set(&MockNftDataKey::Royalty(token_id), &(receiver, royalty_bps));
+        }
+
+        pub fn royalty_info(env: Env, token_id: u64, sale_price: i128) -> (Address, i128) {
+            let royalty: Option<(Address, u32)> =
+                env.storage().instance().get(&MockNftDataKey::Royalty(token_id));
+            match royalty {
+                Some((receiver, bps)) => (receiver, sale_price * bps as i128 / 10_000),
+                None => (Self::owner_of(env, token_id), 0),
+            }
+        }
+
+        pub fn transfer(env: Env, from: Address, to: Address, token_id: u64) {
+            let owner: Address = env
+                .storage()
+                .instance()
+                .get(&MockNftDataKey::Owner(token_id))
+                .unwrap_or_else(|| panic!("token does not exist"));
+            if owner != from {
+                panic!("not token owner");
+            }
+            env.storage()
+                .instance()
+                .set(&MockNftDataKey::Owner(token_id), &to);
+        }
+    }
+
+    // MockOracle — returns an XLM/USDC rate of $0.10 (rate = 1_000_000 at 10^7 scale),
+    // stamped with the current ledger time, unless a test overrides it.
+    // Reflector price at 10^14 scale: 0.10 * 10^14 = 10_000_000_000_000
+    #[contract]
+    struct MockOracle;
+
+    #[contracttype]
+    #[derive(Clone)]
+    enum MockOracleDataKey {
+        Price,
+        Timestamp,
+        Twap,
+    }
+
+    #[contractimpl]
+    impl MockOracle {
+        pub fn lastprice(env: Env, _asset: OracleAsset) -> Option<OraclePriceData> {
+            let storage = env.storage().instance();
+            Some(OraclePriceData {
+                price: storage
+                    .get(&MockOracleDataKey::Price)
+                    .unwrap_or(10_000_000_000_000), // $0.10 per XLM at 10^14 scale
+                timestamp: storage
+                    .get(&MockOracleDataKey::Timestamp)
+                    .unwrap_or(env.ledger().timestamp()),
+            })
+        }
+
+        pub fn twap(env: Env, _asset: OracleAsset, _records: u32) -> Option<i128> {
+            env.storage().instance().get(&MockOracleDataKey::Twap)
+        }
+
+        pub fn set_price(env: Env, price: i128, timestamp: u64) {
+            env.storage().instance().set(&MockOracleDataKey::Price, &price);
+            env.storage().instance().set(&MockOracleDataKey::Timestamp, &timestamp);
+        }
+
+        pub fn set_twap(env: Env, price: i128) {
+            env.storage().instance().set(&MockOracleDataKey::Twap, &price);
+        }
+    }
+
+    struct TestEnv {
+        env: Env,
+        admin: Address,
+        contract_addr: Address,
+        client: ShimejiAuctionClient<'static>,
+        nft: MockNftClient<'static>,
+        xlm: TokenClient<'static>,
+        usdc: TokenClient<'static>,
+        xlm_sac: StellarAssetClient<'static>,
+        usdc_sac: StellarAssetClient<'static>,
+    }
+
+    // Rate after oracle conversion: $0.10/XLM → 1_000_000 at 10^7 scale
+    // So 500 XLM ≈ 50 USDC
+    const START_XLM: i128 = 500_0000000; // 500 XLM (7 decimals)
+    const START_USDC: i128 = 50_0000000; // 50 USDC (7 decimals)
+
+    fn setup() -> TestEnv {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+
+        // Register SAC tokens
+        let xlm_issuer = Address::generate(&env);
+        let usdc_issuer = Address::generate(&env);
+        let xlm_addr = env.register_stellar_asset_contract_v2(xlm_issuer).address();
+        // USDC's issuer can freeze trustlines, as Circle's can.
+        let usdc_asset = env.register_stellar_asset_contract_v2(usdc_issuer);
+        usdc_asset.issuer().set_flag(IssuerFlags::RevocableFlag);
+        let usdc_addr = usdc_asset.address();
+
+        let xlm = TokenClient::new(&env, &xlm_addr);
+        let usdc = TokenClient::new(&env, &usdc_addr);
+        let xlm_sac = StellarAssetClient::new(&env, &xlm_addr);
+        let usdc_sac = StellarAssetClient::new(&env, &usdc_addr);
+
+        let nft_contract = env.register(MockNft, ());
+        let contract_addr = env.register(ShimejiAuction, ());
+        let client = ShimejiAuctionClient::new(&env, &contract_addr);
+        let nft = MockNftClient::new(&env, &nft_contract);
+        client.initialize(&admin, &nft_contract, &usdc_addr, &xlm_addr);
+
+        TestEnv { env, admin, contract_addr, client, nft, xlm, usdc, xlm_sac, usdc_sac }
+    }
+
+    /// Configure the mock oracle so cross-currency bids work in tests.
+    fn configure_oracle(t: &TestEnv) -> MockOracleClient<'static> {
+        let oracle_addr = t.env.register(MockOracle, ());
+        t.client.configure_oracle(&oracle_addr);
+        MockOracleClient::new(&t.env, &oracle_addr)
+    }
+
+    fn fund_xlm(t: &TestEnv, to: &Address, amount: i128) {
+        t.xlm_sac.mint(to, &amount);
+    }
+
+    fn fund_usdc(t: &TestEnv, to: &Address, amount: i128) {
+        t.usdc_sac.mint(to, &amount);
+    }
+
+    fn mint_nft(t: &TestEnv, to: &Address) -> u64 {
+        let uri = String::from_str(&t.env, "ipfs://shimeji-metadata");
+        t.nft.mint(&t.admin, to, &uri)
+    }
+
+    /// Create a default XLM auction (starting at START_XLM).
+    fn create_xlm_auction(t: &TestEnv) -> u64 {
+        let token_id = mint_nft(t, &t.admin);
+        t.client.create_item_auction(
+            &t.admin,
+            &token_id,
+            &START_XLM,
+            &Currency::Xlm,
+            &AUCTION_DURATION,
+            &AuctionOptions::default(),
+        )
+    }
+
+    /// Create a USDC auction (starting at START_USDC).
+    fn create_usdc_auction(t: &TestEnv) -> u64 {
+        let token_id = mint_nft(t, &t.admin);
+        t.client.create_item_auction(
+            &t.admin,
+            &token_id,
+            &START_USDC,
+            &Currency::Usdc,
+            &AUCTION_DURATION,
+            &AuctionOptions::default(),
+        )
+    }
+
+    fn configure_trustless(t: &TestEnv, xlm_destination: &Address, usdc_destination: &Address) {
+        t.client
+            .configure_trustless_escrow(xlm_destination, usdc_destination);
+    }
+
+    /// Events the auction contract itself published during the last invocation.
+    fn auction_events(t: &TestEnv) -> Vec<(Vec<Val>, Val)> {
+        let mut events = Vec::new(&t.env);
+        for (emitter, topics, data) in t.env.events().all().iter() {
+            if emitter == t.contract_addr {
+                events.push_back((topics, data));
+            }
+        }
+        events
+    }
+
+    // ── Happy path ─────────────────────────────────────────────
+
+    #[test]
+    fn test_create_item_auction() {
+        let t = setup();
+        let id = create_xlm_auction(&t);
+        assert_eq!(id, 0);
+        assert_eq!(t.client.total_auctions(), 1);
+
+        let info = t.client.get_auction(&0);
+        assert_eq!(info.starting_price, START_XLM);
+        assert_eq!(info.currency, Currency::Xlm);
+        assert_eq!(info.status, AuctionStatus::Active);
+        assert_eq!(info.escrow_provider, EscrowProvider::Internal);
+        assert!(!info.escrow_settled);
+        assert_eq!(info.end_time - info.start_time, AUCTION_DURATION);
+    }
+
+    #[test]
+    fn test_create_usdc_auction() {
+        let t = setup();
+        let id = create_usdc_auction(&t);
+        assert_eq!(id, 0);
+        let info = t.client.get_auction(&0);
+        assert_eq!(info.starting_price, START_USDC);
+        assert_eq!(info.currency, Currency::Usdc);
+    }
+
+    #[test]
+    fn test_bid_xlm_happy_path() {
+        let t = setup();
+        create_xlm_auction(&t);
+
+        let bidder = Address::generate(&t.env);
+        fund_xlm(&t, &bidder, 600_0000000);
+
+        t.client.bid_xlm(&0, &bidder, &600_0000000);
+
+        let bid = t.client.get_highest_bid(&0);
+        assert_eq!(bid.bidder, bidder);
+        assert_eq!(bid.amount, 600_0000000);
+        assert_eq!(bid.currency, Currency::Xlm);
+        // Bidder's XLM was transferred to contract
+        assert_eq!(t.xlm.balance(&bidder), 0);
+    }
+
+    #[test]
+    fn test_bid_usdc_happy_path() {
+        let t = setup();
+        create_usdc_auction(&t);
+
+        let bidder = Address::generate(&t.env);
+        fund_usdc(&t, &bidder, 60_0000000);
+
+        t.client.bid_usdc(&0, &bidder, &60_0000000);
+
+        let bid = t.client.get_highest_bid(&0);
+        assert_eq!(bid.bidder, bidder);
+        assert_eq!(bid.amount, 60_0000000);
+        assert_eq!(bid.currency, Currency::Usdc);
+        assert_eq!(t.usdc.balance(&bidder), 0);
+    }
+
+    #[test]
+    fn test_outbid_refunds_previous_bidder() {
+        let t = setup();
+        create_xlm_auction(&t);
+
+        let bidder1 = Address::generate(&t.env);
+        let bidder2 = Address::generate(&t.env);
+        fund_xlm(&t, &bidder1, 600_0000000);
+        fund_xlm(&t, &bidder2, 700_0000000);
+
+        t.client.bid_xlm(&0, &bidder1, &600_0000000);
+        assert_eq!(t.xlm.balance(&bidder1), 0);
+
+        // Bidder2 outbids — bidder1's bid is credited for claiming
+        t.client.bid_xlm(&0, &bidder2, &700_0000000);
+        assert_eq!(
+            auction_events(&t).first().unwrap().0,
+            (symbol_short!("credited"), 0u64, bidder1.clone()).into_val(&t.env)
+        );
+        assert_eq!(t.xlm.balance(&bidder1), 0);
+        assert_eq!(t.xlm.balance(&bidder2), 0); // locked in contract
+        assert_eq!(t.client.pending_refunds(&bidder1), (600_0000000, 0));
+
+        assert_eq!(t.client.claim_refund(&bidder1, &Currency::Xlm), 600_0000000);
+        assert_eq!(t.xlm.balance(&bidder1), 600_0000000); // refunded
+        assert_eq!(t.client.pending_refunds(&bidder1), (0, 0));
+        assert_eq!(
+            t.client.try_claim_refund(&bidder1, &Currency::Xlm),
+            Err(Ok(AuctionError::NothingToClaim))
+        );
+
+        let bid = t.client.get_highest_bid(&0);
+        assert_eq!(bid.bidder, bidder2);
+    }
+
+    #[test]
+    fn test_cross_currency_outbid() {
+        let t = setup();
+        configure_oracle(&t);
+        create_xlm_auction(&t); // XLM auction at 500 XLM
+
+        // Bidder1 bids 600 XLM (same currency as auction, no oracle needed)
+        let bidder1 = Address::generate(&t.env);
+        fund_xlm(&t, &bidder1, 600_0000000);
+        t.client.bid_xlm(&0, &bidder1, &600_0000000);
+
+        // Bidder2 outbids with 70 USDC (cross-currency: oracle converts)
+        // 600 XLM * 0.10 = 60 USDC equivalent; 5% of 60 = 3; min = 63 USDC.
+        // 70 USDC > 63 USDC → valid.
+        let bidder2 = Address::generate(&t.env);
+        fund_usdc(&t, &bidder2, 70_0000000);
+        t.client.bid_usdc(&0, &bidder2, &70_0000000);
+        // The starting price and the standing bid were both priced from one oracle read.
+        let rate_topics: Vec<Val> = (symbol_short!("xlm_rate"),).into_val(&t.env);
+        let rate_events = auction_events(&t).iter().filter(|(topics, _)| *topics == rate_topics);
+        assert_eq!(rate_events.count(), 1);
+
+        // Bidder1 refunded in XLM
+        assert_eq!(t.client.pending_refunds(&bidder1), (600_0000000, 0));
+        let bid = t.client.get_highest_bid(&0);
+        assert_eq!(bid.bidder, bidder2);
+        assert_eq!(bid.currency, Currency::Usdc);
+    }
+
+    #[test]
+    fn test_cross_currency_bid_without_oracle_fails() {
+        let t = setup();
+        create_xlm_auction(&t);
+
+        let bidder = Address::generate(&t.env);
+        fund_usdc(&t, &bidder, 60_0000000);
+        assert_eq!(
+            t.client.try_bid_usdc(&0, &bidder, &60_0000000),
+            Err(Ok(AuctionError::OracleNotConfigured))
+        );
+    }
+
+    #[test]
+    fn test_cross_currency_first_bid_below_minimum_from_starting_price() {
+        let t = setup();
+        configure_oracle(&t);
+        create_xlm_auction(&t); // 500 XLM => 50 USDC equivalent with mock oracle
+
+        let bidder = Address::generate(&t.env);
+        fund_usdc(&t, &bidder, 49_0000000);
+        assert_eq!(
+            t.client.try_bid_usdc(&0, &bidder, &49_0000000),
+            Err(Ok(AuctionError::BidBelowMinimum))
+        );
+    }
+
+    #[test]
+    fn test_cross_currency_first_bid_exact_minimum_from_starting_price() {
+        let t = setup();
+        configure_oracle(&t);
+        create_xlm_auction(&t); // 500 XLM => 50 USDC equivalent with mock oracle
+
+        let bidder = Address::generate(&t.env);
+        fund_usdc(&t, &bidder, 50_0000000);
+        t.client.bid_usdc(&0, &bidder, &50_0000000);
+
+        let bid = t.client.get_highest_bid(&0);
+        assert_eq!(bid.bidder, bidder);
+        assert_eq!(bid.amount, 50_0000000);
+        assert_eq!(bid.currency, Currency::Usdc);
+    }
+
+    #[test]
+    fn test_finalize_no_bids() {
+        let t = setup();
+        create_xlm_auction(&t);
+
+        t.env.ledger().with_mut(|li| {
+            li.timestamp += AUCTION_DURATION + 1;
+        });
+
+        t.client.finalize(&0);
+        let info = t.client.get_auction(&0);
+        assert_eq!(info.status, AuctionStatus::Finalized);
+        assert!(!info.escrow_settled);
+    }
+
+    #[test]
+    fn test_finalize_item_auction_transfers_nft_and_pays_seller() {
+        let t = setup();
+        create_xlm_auction(&t);
+
+        let bidder = Address::generate(&t.env);
+        fund_xlm(&t, &bidder, 600_0000000);
+        t.client.bid_xlm(&0, &bidder, &600_0000000);
+
+        t.env.ledger().with_mut(|li| {
+            li.timestamp += AUCTION_DURATION + 1;
+        });
+
+        t.client.finalize(&0);
+
+        let info = t.client.get_auction(&0);
+        assert_eq!(info.escrow_provider, EscrowProvider::Internal);
+        assert_eq!(info.status, AuctionStatus::Finalized);
+        assert!(info.escrow_settled);
+        assert_eq!(t.xlm.balance(&t.contract_addr), 0);
+        assert_eq!(t.xlm.balance(&t.admin), 600_0000000);
+        assert_eq!(t.nft.owner_of(&0), bidder);
+    }
+
+    #[test]
+    fn test_finalize_splits_creator_royalty() {
+        let t = setup();
+        let creator = Address::generate(&t.env);
+        let token_id = mint_nft(&t, &t.admin);
+        t.nft.set_royalty(&token_id, &creator, &1_000);
+        t.client.create_item_auction(
+            &t.admin,
+            &token_id,
+            &START_XLM,
+            &Currency::Xlm,
+            &AUCTION_DURATION,
+            &AuctionOptions::default(),
+        );
+
+        let bidder = Address::generate(&t.env);
+        fund_xlm(&t, &bidder, 600_0000000);
+        t.client.bid_xlm(&0, &bidder, &600_0000000);
+
+        t.env.ledger().with_mut(|li| {
+            li.timestamp += AUCTION_DURATION + 1;
+        });
+        t.client.finalize(&0);
+
+        assert_eq!(t.xlm.balance(&creator), 60_0000000);
+        assert_eq!(t.xlm.balance(&t.admin), 540_0000000);
+        assert_eq!(t.xlm.balance(&t.contract_addr), 0);
+        assert_eq!(t.nft.owner_of(&token_id), bidder);
+    }
+
+    #[test]
+    fn test_outbid_emits_refund_and_bid_events() {
+        let t = setup();
+        t.client.configure_push_refunds(&true);
+        create_xlm_auction(&t);
+
+        let bidder1 = Address::generate(&t.env);
+        let bidder2 = Address::generate(&t.env);
+        fund_xlm(&t, &bidder1, 600_0000000);
+        fund_xlm(&t, &bidder2, 700_0000000);
+
+        t.client.bid_xlm(&0, &bidder1, &600_0000000);
+        t.client.bid_xlm(&0, &bidder2, &700_0000000);
+
+        assert_eq!(
+            auction_events(&t),
+            vec![
+                &t.env,
+                (
+                    (symbol_short!("refund"), 0u64, bidder1.clone()).into_val(&t.env),
+                    (600_0000000i128, Currency::Xlm).into_val(&t.env),
+                ),
+                (
+                    (symbol_short!("bid"), 0u64, bidder2.clone()).into_val(&t.env),
+                    (700_0000000i128, Currency::Xlm).into_val(&t.env),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_finalize_emits_winning_bid() {
+        let t = setup();
+        create_xlm_auction(&t);
+
+        let bidder = Address::generate(&t.env);
+        fund_xlm(&t, &bidder, 600_0000000);
+        t.client.bid_xlm(&0, &bidder, &600_0000000);
+
+        t.env.ledger().with_mut(|li| {
+            li.timestamp += AUCTION_DURATION + 1;
+        });
+        t.client.finalize(&0);
+
+        let events = auction_events(&t);
+        let (topics, data) = events.last().unwrap();
+        assert_eq!(topics, (symbol_short!("finalized"), 0u64).into_val(&t.env));
+        let winner: Option<BidInfo> = data.into_val(&t.env);
+        let winner = winner.unwrap();
+        assert_eq!(winner.bidder, bidder);
+        assert_eq!(winner.amount, 600_0000000);
+    }
+
+    #[test]
+    fn test_item_auction_uses_trustless_config_when_available() {
+        let t = setup();
+        let xlm_destination = Address::generate(&t.env);
+        let usdc_destination = Address::generate(&t.env);
+        configure_trustless(&t, &xlm_destination, &usdc_destination);
+        create_xlm_auction(&t);
+
+        let bidder = Address::generate(&t.env);
+        fund_xlm(&t, &bidder, 600_0000000);
+        t.client.bid_xlm(&0, &bidder, &600_0000000);
+        assert_eq!(t.xlm.balance(&xlm_destination), 0);
+
+        t.env.ledger().with_mut(|li| {
+            li.timestamp += AUCTION_DURATION + 1;
+        });
+
+        t.client.finalize(&0);
+
+        let info = t.client.get_auction(&0);
+        assert_eq!(info.escrow_provider, EscrowProvider::TrustlessWork);
+        assert!(info.escrow_settled);
+        assert_eq!(t.xlm.balance(&t.contract_addr), 0);
+        assert_eq!(t.xlm.balance(&xlm_destination), 600_0000000);
+        assert_eq!(t.xlm.balance(&t.admin), 0);
+        assert_eq!(t.nft.owner_of(&0), bidder);
+    }
+
+    #[test]
+    fn test_bid_exactly_at_minimum() {
+        let t = setup();
+        create_xlm_auction(&t);
+
+        let bidder = Address::generate(&t.env);
+        fund_xlm(&t, &bidder, START_XLM);
+
+        // Bid exactly at starting price — should succeed
+        t.client.bid_xlm(&0, &bidder, &START_XLM);
+        assert_eq!(t.client.get_highest_bid(&0).amount, START_XLM);
+    }
+
+    // ── Edge cases ─────────────────────────────────────────────
+
+    #[test]
+    fn test_bid_below_minimum_xlm() {
+        let t = setup();
+        create_xlm_auction(&t); // 500 XLM minimum
+
+        let bidder = Address::generate(&t.env);
+        fund_xlm(&t, &bidder, 400_0000000);
+        // below 500 XLM min
+        assert_eq!(
+            t.client.try_bid_xlm(&0, &bidder, &400_0000000),
+            Err(Ok(AuctionError::BidBelowMinimum))
+        );
+    }
+
+    #[test]
+    fn test_bid_below_minimum_usdc() {
+        let t = setup();
+        create_usdc_auction(&t); // 50 USDC minimum
+
+        let bidder = Address::generate(&t.env);
+        fund_usdc(&t, &bidder, 40_0000000);
+        // below 50 USDC min
+        assert_eq!(
+            t.client.try_bid_usdc(&0, &bidder, &40_0000000),
+            Err(Ok(AuctionError::BidBelowMinimum))
+        );
+    }
+
+    #[test]
+    fn test_bid_insufficient_increment() {
+        let t = setup();
+        create_xlm_auction(&t);
+
+        let bidder1 = Address::generate(&t.env);
+        let bidder2 = Address::generate(&t.env);
+        fund_xlm(&t, &bidder1, 600_0000000);
+        fund_xlm(&t, &bidder2, 610_0000000);
+
+        t.client.bid_xlm(&0, &bidder1, &600_0000000);
+        // 5% of 600 = 30, min required = 630. Bidding 610 should fail.
+        assert_eq!(
+            t.client.try_bid_xlm(&0, &bidder2, &610_0000000),
+            Err(Ok(AuctionError::BidIncrementTooLow))
+        );
+    }
+
+    #[test]
+    fn test_bid_after_auction_ends() {
+        let t = setup();
+        create_xlm_auction(&t);
+
+        t.env.ledger().with_mut(|li| {
+            li.timestamp += AUCTION_DURATION + 1;
+        });
+
+        let bidder = Address::generate(&t.env);
+        fund_xlm(&t, &bidder, 600_0000000);
+        assert_eq!(
+            t.client.try_bid_xlm(&0, &bidder, &600_0000000),
+            Err(Ok(AuctionError::AuctionEnded))
+        );
+    }
+
+    #[test]
+    fn test_finalize_before_end() {
+        let t = setup();
+        create_xlm_auction(&t);
+        assert_eq!(
+            t.client.try_finalize(&0),
+            Err(Ok(AuctionError::AuctionNotEnded))
+        );
+    }
+
+    #[test]
+    fn test_double_finalize() {
+        let t = setup();
+        create_xlm_auction(&t);
+
+        t.env.ledger().with_mut(|li| {
+            li.timestamp += AUCTION_DURATION + 1;
+        });
+
+        t.client.finalize(&0);
+        // second finalize is rejected
+        assert_eq!(
+            t.client.try_finalize(&0),
+            Err(Ok(AuctionError::AlreadyFinalized))
+        );
+        // and a settled auction can no longer be cancelled
+        assert_eq!(
+            t.client.try_cancel_auction(&t.admin, &0),
+            Err(Ok(AuctionError::AlreadyFinalized))
+        );
+        assert_eq!(t.client.try_force_cancel_auction(&0), Err(Ok(AuctionError::AlreadyFinalized)));
+        assert_eq!(t.client.get_auction(&0).status, AuctionStatus::Finalized);
+    }
+
+    #[test]
+    fn test_bid_nonexistent_auction() {
+        let t = setup();
+        let bidder = Address::generate(&t.env);
+        fund_xlm(&t, &bidder, 600_0000000);
+        assert_eq!(
+            t.client.try_bid_xlm(&99, &bidder, &600_0000000),
+            Err(Ok(AuctionError::AuctionNotFound))
+        );
+    }
+
+    #[test]
+    fn test_double_initialize() {
+        let t = setup();
+        let nft = Address::generate(&t.env);
+        let usdc = Address::generate(&t.env);
+        let xlm = Address::generate(&t.env);
+        assert_eq!(
+            t.client.try_initialize(&t.admin, &nft, &usdc, &xlm),
+            Err(Ok(AuctionError::AlreadyInitialized))
+        );
+    }
+
+    #[test]
+    fn test_pause_flags_gate_bids_and_settlement() {
+        let t = setup();
+        create_xlm_auction(&t);
+        let bidder = Address::generate(&t.env);
+        let pauser = Address::generate(&t.env);
+        fund_xlm(&t, &bidder, 600_0000000);
+
+        assert_eq!(
+            t.client.try_pause(&pauser, &PauseFlag::Trading),
+            Err(Ok(AuctionError::NotPauser))
+        );
+        t.client.grant_role(&Role::Pauser, &pauser);
+        t.client.pause(&pauser, &PauseFlag::Trading);
+        assert!(t.client.is_paused(&PauseFlag::Trading));
+        assert_eq!(
+            t.client.try_bid_xlm(&0, &bidder, &600_0000000),
+            Err(Ok(AuctionError::Paused))
+        );
+        t.client.unpause(&pauser, &PauseFlag::Trading);
+        t.client.bid_xlm(&0, &bidder, &600_0000000);
+
+        t.env.ledger().with_mut(|li| {
+            li.timestamp += AUCTION_DURATION + 1;
+        });
+        t.client.pause(&t.admin, &PauseFlag::Settlements);
+        assert_eq!(t.client.try_finalize(&0), Err(Ok(AuctionError::Paused)));
+        t.client.unpause(&t.admin, &PauseFlag::Settlements);
+        t.client.finalize(&0);
+        assert_eq!(t.client.get_auction(&0).status, AuctionStatus::Finalized);
+    }
+
+    // ── Reserve price ──────────────────────────────────────────
+
+    const RESERVE_XLM: i128 = 1000_0000000;
+
+    fn create_reserve_auction(t: &TestEnv, hidden: bool) -> u64 {
+        let token_id = mint_nft(t, &t.admin);
+        t.client.create_item_auction(
+            &t.admin,
+            &token_id,
+            &START_XLM,
+            &Currency::Xlm,
+            &AUCTION_DURATION,
+            &AuctionOptions {
+                reserve_price: Some(RESERVE_XLM),
+                reserve_hidden: hidden,
+                buy_now_price: None,
+            },
+        )
+    }
+
+    #[test]
+    fn test_reserve_below_starting_price_is_rejected() {
+        let t = setup();
+        let token_id = mint_nft(&t, &t.admin);
+        let result = t.client.try_create_item_auction(
+            &t.admin,
+            &token_id,
+            &START_XLM,
+            &Currency::Xlm,
+            &AUCTION_DURATION,
+            &AuctionOptions {
+                reserve_price: Some(START_XLM - 1),
+                ..Default::default()
+            },
+        );
+        assert_eq!(result, Err(Ok(AuctionError::InvalidReservePrice)));
+    }
+
+    #[test]
+    fn test_unmet_reserve_returns_nft_and_refunds_top_bid() {
+        let t = setup();
+        let id = create_reserve_auction(&t, false);
+        assert_eq!(t.client.get_auction(&id).reserve_price, Some(RESERVE_XLM));
+        assert!(!t.client.reserve_met(&id));
+
+        let bidder = Address::generate(&t.env);
+        fund_xlm(&t, &bidder, 600_0000000);
+        t.client.bid_xlm(&id, &bidder, &600_0000000);
+        assert!(!t.client.reserve_met(&id));
+
+        t.env.ledger().with_mut(|li| {
+            li.timestamp += AUCTION_DURATION + 1;
+        });
+        t.client.finalize(&id);
+
+        let events = auction_events(&t);
+        assert_eq!(
+            events.first().unwrap().0,
+            (symbol_short!("credited"), id, bidder.clone()).into_val(&t.env)
+        );
+        let winner: Option<BidInfo> = events.last().unwrap().1.into_val(&t.env);
+        assert!(winner.is_none());
+
+        let info = t.client.get_auction(&id);
+        assert_eq!(info.status, AuctionStatus::Finalized);
+        assert!(!info.escrow_settled);
+        assert_eq!(t.nft.owner_of(&0), t.admin);
+        assert_eq!(t.xlm.balance(&t.admin), 0);
+        t.client.claim_refund(&bidder, &Currency::Xlm);
+        assert_eq!(t.xlm.balance(&bidder), 600_0000000);
+        assert_eq!(t.xlm.balance(&t.contract_addr), 0);
     }
 
-    // MockOracle — returns a fixed XLM/USDC rate of $0.10 (rate = 1_000_000 at 10^7 scale).
-    // Reflector price at 10^14 scale: 0.10 * 10^14 = 10_000_000_000_000
-    #[contract]
-    struct MockOracle;
+    #[test]
+    fn test_hidden_reserve_is_revealed_once_met() {
+        let t = setup();
+        let id = create_reserve_auction(&t, true);
+        assert_eq!(t.client.get_auction(&id).reserve_price, None);
+        assert!(t.client.get_auction(&id).reserve_hidden);
 
-    #[contractimpl]
-    impl MockOracle {
-        pub fn lastprice(_env: Env, _asset: OracleAsset) -> Option<OraclePriceData> {
-            Some(OraclePriceData {
-                price: 10_000_000_000_000, // $0.10 per XLM at 10^14 scale
-                timestamp: 0,
-            })
-        }
+        let bidder1 = Address::generate(&t.env);
+        fund_xlm(&t, &bidder1, 600_0000000);
+        t.client.bid_xlm(&id, &bidder1, &600_0000000);
+        assert_eq!(t.client.get_auction(&id).reserve_price, None);
+
+        let bidder2 = Address::generate(&t.env);
+        fund_xlm(&t, &bidder2, RESERVE_XLM);
+        t.client.bid_xlm(&id, &bidder2, &RESERVE_XLM);
+        assert!(t.client.reserve_met(&id));
+        assert_eq!(t.client.get_auction(&id).reserve_price, Some(RESERVE_XLM));
+
+        t.env.ledger().with_mut(|li| {
+            li.timestamp += AUCTION_DURATION + 1;
+        });
+        t.client.finalize(&id);
+        assert_eq!(t.nft.owner_of(&0), bidder2);
+        assert_eq!(t.xlm.balance(&t.admin), RESERVE_XLM);
+        assert_eq!(t.client.pending_refunds(&bidder1), (600_0000000, 0));
     }
 
-    struct TestEnv {
-        env: Env,
-        admin: Address,
-        contract_addr: Address,
-        client: ShimejiAuctionClient<'static>,
-        nft: MockNftClient<'static>,
-        xlm: TokenClient<'static>,
-        usdc: TokenClient<'static>,
-        xlm_sac: StellarAssetClient<'static>,
-        usdc_sac: StellarAssetClient<'static>,
+    #[test]
+    fn test_cross_currency_bid_meets_reserve_via_oracle() {
+        let t = setup();
+        configure_oracle(&t);
+        let id = create_reserve_auction(&t, false);
+
+        // 1000 XLM at $0.10 is 100 USDC.
+        let bidder1 = Address::generate(&t.env);
+        fund_usdc(&t, &bidder1, 90_0000000);
+        t.client.bid_usdc(&id, &bidder1, &90_0000000);
+        assert!(!t.client.reserve_met(&id));
+
+        let bidder2 = Address::generate(&t.env);
+        fund_usdc(&t, &bidder2, 100_0000000);
+        t.client.bid_usdc(&id, &bidder2, &100_0000000);
+        assert!(t.client.reserve_met(&id));
     }
 
-    // Rate after oracle conversion: $0.10/XLM → 1_000_000 at 10^7 scale
-    // So 500 XLM ≈ 50 USDC
-    const START_XLM: i128 = 500_0000000; // 500 XLM (7 decimals)
-    const START_USDC: i128 = 50_0000000; // 50 USDC (7 decimals)
+    // ── Buy now ────────────────────────────────────────────────
 
-    fn setup() -> TestEnv {
-        let env = Env::default();
-        env.mock_all_auths();
+    const BUY_NOW_XLM: i128 = 2000_0000000;
 
-        let admin = Address::generate(&env);
+    fn create_buy_now_auction(t: &TestEnv) -> u64 {
+        let token_id = mint_nft(t, &t.admin);
+        t.client.create_item_auction(
+            &t.admin,
+            &token_id,
+            &START_XLM,
+            &Currency::Xlm,
+            &AUCTION_DURATION,
+            &AuctionOptions {
+                buy_now_price: Some(BUY_NOW_XLM),
+                ..Default::default()
+            },
+        )
+    }
 
-        // Register SAC tokens
-        let xlm_issuer = Address::generate(&env);
-        let usdc_issuer = Address::generate(&env);
-        let xlm_addr = env.register_stellar_asset_contract_v2(xlm_issuer).address();
-        let usdc_addr = env.register_stellar_asset_contract_v2(usdc_issuer).address();
+    #[test]
+    fn test_buy_now_refunds_top_bid_and_settles() {
+        let t = setup();
+        let id = create_buy_now_auction(&t);
 
-        let xlm = TokenClient::new(&env, &xlm_addr);
-        let usdc = TokenClient::new(&env, &usdc_addr);
-        let xlm_sac = StellarAssetClient::new(&env, &xlm_addr);
-        let usdc_sac = StellarAssetClient::new(&env, &usdc_addr);
+        let bidder = Address::generate(&t.env);
+        fund_xlm(&t, &bidder, 600_0000000);
+        t.client.bid_xlm(&id, &bidder, &600_0000000);
+
+        let buyer = Address::generate(&t.env);
+        fund_xlm(&t, &buyer, BUY_NOW_XLM);
+        t.client.buy_now_xlm(&id, &buyer);
+        assert_eq!(
+            auction_events(&t),
+            vec![
+                &t.env,
+                (
+                    (symbol_short!("credited"), id, bidder.clone()).into_val(&t.env),
+                    (600_0000000i128, Currency::Xlm).into_val(&t.env),
+                ),
+                (
+                    (symbol_short!("buy_now"), id, buyer.clone()).into_val(&t.env),
+                    (BUY_NOW_XLM, Currency::Xlm).into_val(&t.env),
+                ),
+                (
+                    (symbol_short!("finalized"), id).into_val(&t.env),
+                    Some(BidInfo {
+                        bidder: buyer.clone(),
+                        amount: BUY_NOW_XLM,
+                        currency: Currency::Xlm,
+                    })
+                    .into_val(&t.env),
+                ),
+            ]
+        );
 
-        let nft_contract = env.register(MockNft, ());
-        let contract_addr = env.register(ShimejiAuction, ());
-        let client = ShimejiAuctionClient::new(&env, &contract_addr);
-        let nft = MockNftClient::new(&env, &nft_contract);
-        client.initialize(&admin, &nft_contract, &usdc_addr, &xlm_addr);
+        let info = t.client.get_auction(&id);
+        assert_eq!(info.status, AuctionStatus::Finalized);
+        assert!(info.escrow_settled);
+        assert_eq!(t.client.get_highest_bid(&id).bidder, buyer);
+        assert_eq!(t.nft.owner_of(&0), buyer);
+        assert_eq!(t.xlm.balance(&t.admin), BUY_NOW_XLM);
+        t.client.claim_refund(&bidder, &Currency::Xlm);
+        assert_eq!(t.xlm.balance(&bidder), 600_0000000);
+        assert_eq!(t.xlm.balance(&t.contract_addr), 0);
 
-        TestEnv { env, admin, contract_addr, client, nft, xlm, usdc, xlm_sac, usdc_sac }
+        assert_eq!(
+            t.client.try_bid_xlm(&id, &bidder, &700_0000000),
+            Err(Ok(AuctionError::AlreadyFinalized))
+        );
+        assert_eq!(
+            t.client.try_buy_now_xlm(&id, &bidder),
+            Err(Ok(AuctionError::AlreadyFinalized))
+        );
     }
 
-    /// Configure the mock oracle so cross-currency bids work in tests.
-    fn configure_oracle(t: &TestEnv) {
-        let oracle_addr = t.env.register(MockOracle, ());
-        t.client.configure_oracle(&oracle_addr);
-    }
+    #[test]
+    fn test_buy_now_in_other_currency_follows_escrow_route() {
+        let t = setup();
+        configure_oracle(&t);
+        let xlm_destination = Address::generate(&t.env);
+        let usdc_destination = Address::generate(&t.env);
+        configure_trustless(&t, &xlm_destination, &usdc_destination);
+        let id = create_buy_now_auction(&t);
 
-    fn fund_xlm(t: &TestEnv, to: &Address, amount: i128) {
-        t.xlm_sac.mint(to, &amount);
-    }
+        // 2000 XLM at $0.10 is 200 USDC.
+        let buyer = Address::generate(&t.env);
+        fund_usdc(&t, &buyer, 200_0000000);
+        t.client.buy_now_usdc(&id, &buyer);
 
-    fn fund_usdc(t: &TestEnv, to: &Address, amount: i128) {
-        t.usdc_sac.mint(to, &amount);
+        assert_eq!(t.usdc.balance(&buyer), 0);
+        assert_eq!(t.usdc.balance(&usdc_destination), 200_0000000);
+        assert_eq!(t.nft.owner_of(&0), buyer);
+        assert_eq!(t.client.get_auction(&id).escrow_provider, EscrowProvider::TrustlessWork);
     }
 
-    fn mint_nft(t: &TestEnv, to: &Address) -> u64 {
-        let uri = String::from_str(&t.env, "ipfs://shimeji-metadata");
-        t.nft.mint(to, &uri)
-    }
+    #[test]
+    fn test_buy_now_unavailable_without_price_or_once_bids_reach_it() {
+        let t = setup();
+        let buyer = Address::generate(&t.env);
+        fund_xlm(&t, &buyer, 3000_0000000);
 
-    /// Create a default XLM auction (starting at START_XLM).
-    fn create_xlm_auction(t: &TestEnv) -> u64 {
-        let token_id = mint_nft(t, &t.admin);
-        t.client.create_item_auction(
+        let plain_id = create_xlm_auction(&t);
+        assert_eq!(
+            t.client.try_buy_now_xlm(&plain_id, &buyer),
+            Err(Ok(AuctionError::BuyNowUnavailable))
+        );
+
+        let id = create_buy_now_auction(&t);
+        let bidder = Address::generate(&t.env);
+        fund_xlm(&t, &bidder, BUY_NOW_XLM);
+        t.client.bid_xlm(&id, &bidder, &BUY_NOW_XLM);
+        assert_eq!(
+            t.client.try_buy_now_xlm(&id, &buyer),
+            Err(Ok(AuctionError::BuyNowUnavailable))
+        );
+
+        let token_id = mint_nft(&t, &t.admin);
+        let result = t.client.try_create_item_auction(
             &t.admin,
             &token_id,
             &START_XLM,
             &Currency::Xlm,
             &AUCTION_DURATION,
-        )
+            &AuctionOptions {
+                reserve_price: Some(RESERVE_XLM),
+                reserve_hidden: false,
+                buy_now_price: Some(RESERVE_XLM - 1),
+            },
+        );
+        assert_eq!(result, Err(Ok(AuctionError::InvalidBuyNowPrice)));
     }
 
-    /// Create a USDC auction (starting at START_USDC).
-    fn create_usdc_auction(t: &TestEnv) -> u64 {
+    // ── Dutch auctions ─────────────────────────────────────────
+
+    const DUTCH_START: i128 = 1000_0000000;
+    const DUTCH_FLOOR: i128 = 200_0000000;
+    const DUTCH_DURATION: u64 = 8_000;
+
+    fn create_dutch(t: &TestEnv, step_seconds: u64) -> u64 {
         let token_id = mint_nft(t, &t.admin);
-        t.client.create_item_auction(
+        t.client.create_dutch_auction(
             &t.admin,
             &token_id,
-            &START_USDC,
-            &Currency::Usdc,
-            &AUCTION_DURATION,
+            &DUTCH_START,
+            &DUTCH_FLOOR,
+            &Currency::Xlm,
+            &DUTCH_DURATION,
+            &step_seconds,
         )
     }
 
-    fn configure_trustless(t: &TestEnv, xlm_destination: &Address, usdc_destination: &Address) {
-        t.client
-            .configure_trustless_escrow(xlm_destination, usdc_destination);
+    #[test]
+    fn test_dutch_price_decays_linearly_or_in_steps() {
+        let t = setup();
+        let linear = create_dutch(&t, 0);
+        let stepped = create_dutch(&t, 2_000);
+        let start = t.client.get_auction(&linear).start_time;
+
+        assert_eq!(t.client.current_price(&linear), DUTCH_START);
+        assert_eq!(t.client.current_price(&stepped), DUTCH_START);
+
+        t.env.ledger().set_timestamp(start + 1_999);
+        assert_eq!(t.client.current_price(&stepped), DUTCH_START);
+        t.env.ledger().set_timestamp(start + 2_000);
+        assert_eq!(t.client.current_price(&linear), 800_0000000);
+        assert_eq!(t.client.current_price(&stepped), 800_0000000);
+
+        t.env.ledger().set_timestamp(start + 4_000);
+        assert_eq!(t.client.current_price(&linear), 600_0000000);
+        t.env.ledger().set_timestamp(start + 7_999);
+        assert_eq!(t.client.current_price(&stepped), 400_0000000);
+
+        // Floor from the end onwards.
+        t.env.ledger().set_timestamp(start + DUTCH_DURATION + 500);
+        assert_eq!(t.client.current_price(&linear), DUTCH_FLOOR);
+        assert_eq!(t.client.current_price(&stepped), DUTCH_FLOOR);
+
+        let english = create_xlm_auction(&t);
+        assert_eq!(
+            t.client.try_current_price(&english),
+            Err(Ok(AuctionError::WrongAuctionFormat))
+        );
     }
 
-    // ── Happy path ─────────────────────────────────────────────
-
     #[test]
-    fn test_create_item_auction() {
+    fn test_dutch_first_buyer_wins_at_current_price() {
         let t = setup();
-        let id = create_xlm_auction(&t);
-        assert_eq!(id, 0);
-        assert_eq!(t.client.total_auctions(), 1);
+        let id = create_dutch(&t, 0);
+        let start = t.client.get_auction(&id).start_time;
 
-        let info = t.client.get_auction(&0);
-        assert_eq!(info.starting_price, START_XLM);
-        assert_eq!(info.currency, Currency::Xlm);
-        assert!(!info.finalized);
-        assert_eq!(info.escrow_provider, EscrowProvider::Internal);
-        assert!(!info.escrow_settled);
-        assert_eq!(info.end_time - info.start_time, AUCTION_DURATION);
+        let bidder = Address::generate(&t.env);
+        fund_xlm(&t, &bidder, DUTCH_START);
+        assert_eq!(
+            t.client.try_bid_xlm(&id, &bidder, &DUTCH_START),
+            Err(Ok(AuctionError::WrongAuctionFormat))
+        );
+
+        t.env.ledger().set_timestamp(start + 4_000);
+        let buyer = Address::generate(&t.env);
+        fund_xlm(&t, &buyer, DUTCH_START);
+        t.client.buy_now_xlm(&id, &buyer);
+
+        assert_eq!(t.xlm.balance(&buyer), DUTCH_START - 600_0000000);
+        assert_eq!(t.xlm.balance(&t.admin), 600_0000000);
+        assert_eq!(t.nft.owner_of(&0), buyer);
+        let info = t.client.get_auction(&id);
+        assert_eq!(info.status, AuctionStatus::Finalized);
+        assert!(info.escrow_settled);
+        assert_eq!(info.end_time, start + 4_000);
+
+        assert_eq!(
+            t.client.try_buy_now_xlm(&id, &bidder),
+            Err(Ok(AuctionError::AlreadyFinalized))
+        );
     }
 
     #[test]
-    fn test_create_usdc_auction() {
+    fn test_unsold_dutch_auction_returns_item_at_finalize() {
         let t = setup();
-        let id = create_usdc_auction(&t);
-        assert_eq!(id, 0);
-        let info = t.client.get_auction(&0);
-        assert_eq!(info.starting_price, START_USDC);
-        assert_eq!(info.currency, Currency::Usdc);
+        let id = create_dutch(&t, 1_000);
+        let start = t.client.get_auction(&id).start_time;
+
+        t.env.ledger().set_timestamp(start + DUTCH_DURATION + 1);
+        let buyer = Address::generate(&t.env);
+        fund_xlm(&t, &buyer, DUTCH_FLOOR);
+        assert_eq!(
+            t.client.try_buy_now_xlm(&id, &buyer),
+            Err(Ok(AuctionError::AuctionEnded))
+        );
+        t.client.finalize(&id);
+        assert_eq!(t.nft.owner_of(&0), t.admin);
+        assert!(!t.client.get_auction(&id).escrow_settled);
+
+        let token_id = mint_nft(&t, &t.admin);
+        let result = t.client.try_create_dutch_auction(
+            &t.admin,
+            &token_id,
+            &DUTCH_START,
+            &DUTCH_START,
+            &Currency::Xlm,
+            &DUTCH_DURATION,
+            &0,
+        );
+        assert_eq!(result, Err(Ok(AuctionError::InvalidDutchSchedule)));
     }
 
-    #[test]
-    fn test_bid_xlm_happy_path() {
-        let t = setup();
-        create_xlm_auction(&t);
+    // ── Sealed bids ────────────────────────────────────────────
 
-        let bidder = Address::generate(&t.env);
-        fund_xlm(&t, &bidder, 600_0000000);
+    const REVEAL_SECONDS: u64 = 3_600;
 
-        t.client.bid_xlm(&0, &bidder, &600_0000000);
+    fn create_sealed(t: &TestEnv, pricing: SealedPricing) -> u64 {
+        let token_id = mint_nft(t, &t.admin);
+        t.client.create_sealed_auction(
+            &t.admin,
+            &token_id,
+            &START_XLM,
+            &Currency::Xlm,
+            &AUCTION_DURATION,
+            &SealedBidRules {
+                reveal_seconds: REVEAL_SECONDS,
+                pricing,
+                slash_bps: 1_000,
+            },
+        )
+    }
 
-        let bid = t.client.get_highest_bid(&0);
-        assert_eq!(bid.bidder, bidder);
-        assert_eq!(bid.amount, 600_0000000);
-        assert_eq!(bid.currency, Currency::Xlm);
-        // Bidder's XLM was transferred to contract
-        assert_eq!(t.xlm.balance(&bidder), 0);
+    fn salt(t: &TestEnv, seed: u8) -> BytesN<32> {
+        BytesN::from_array(&t.env, &[seed; 32])
+    }
+
+    /// Commits `amount` under a salt of repeated `seed` bytes, depositing `deposit`.
+    fn commit(t: &TestEnv, id: u64, bidder: &Address, amount: i128, deposit: i128, seed: u8) {
+        fund_xlm(t, bidder, deposit);
+        let commitment =
+            ShimejiAuction::sealed_commitment(&t.env, id, bidder, amount, &salt(t, seed));
+        t.client.commit_bid(&id, bidder, &commitment, &deposit);
     }
 
     #[test]
-    fn test_bid_usdc_happy_path() {
+    fn test_sealed_second_price_auction_settles_and_slashes_non_revealers() {
         let t = setup();
-        create_usdc_auction(&t);
-
-        let bidder = Address::generate(&t.env);
-        fund_usdc(&t, &bidder, 60_0000000);
+        let id = create_sealed(&t, SealedPricing::SecondPrice);
+        let alice = Address::generate(&t.env);
+        let bob = Address::generate(&t.env);
+        let carol = Address::generate(&t.env);
+
+        commit(&t, id, &alice, 900_0000000, 1000_0000000, 1);
+        commit(&t, id, &bob, 700_0000000, 800_0000000, 2);
+        commit(&t, id, &carol, 800_0000000, 1000_0000000, 3);
+        assert_eq!(
+            t.client.try_bid_xlm(&id, &alice, &600_0000000),
+            Err(Ok(AuctionError::WrongAuctionFormat))
+        );
+        assert_eq!(
+            t.client.try_reveal_bid(&id, &alice, &900_0000000, &salt(&t, 1)),
+            Err(Ok(AuctionError::NotRevealPhase))
+        );
+        // Nothing about the amounts is on chain yet.
+        assert!(t.client.try_get_highest_bid(&id).is_err());
+
+        let end_time = t.client.get_auction(&id).end_time;
+        t.env.ledger().set_timestamp(end_time + 1);
+        let late = Address::generate(&t.env);
+        fund_xlm(&t, &late, START_XLM);
+        assert_eq!(
+            t.client.try_commit_bid(&id, &late, &salt(&t, 9), &START_XLM),
+            Err(Ok(AuctionError::AuctionEnded))
+        );
+        assert_eq!(
+            t.client.try_reveal_bid(&id, &alice, &901_0000000, &salt(&t, 1)),
+            Err(Ok(AuctionError::RevealMismatch))
+        );
+        t.client.reveal_bid(&id, &alice, &900_0000000, &salt(&t, 1));
+        t.client.reveal_bid(&id, &bob, &700_0000000, &salt(&t, 2));
+        assert_eq!(t.client.get_highest_bid(&id).bidder, alice);
+        assert_eq!(t.client.try_finalize(&id), Err(Ok(AuctionError::AuctionNotEnded)));
+
+        t.env.ledger().set_timestamp(end_time + REVEAL_SECONDS + 1);
+        t.client.finalize(&id);
+        assert_eq!(t.nft.owner_of(&0), alice);
+        assert_eq!(t.client.get_highest_bid(&id).amount, 700_0000000);
+        assert_eq!(t.xlm.balance(&t.admin), 700_0000000);
+
+        t.client.release_sealed_deposit(&id, &alice);
+        t.client.release_sealed_deposit(&id, &bob);
+        t.client.release_sealed_deposit(&id, &carol);
+        assert_eq!(
+            auction_events(&t),
+            vec![
+                &t.env,
+                (
+                    (symbol_short!("dep_rel"), id, carol.clone()).into_val(&t.env),
+                    (900_0000000i128, 100_0000000i128).into_val(&t.env),
+                ),
+            ]
+        );
+        assert_eq!(t.xlm.balance(&alice), 300_0000000);
+        assert_eq!(t.xlm.balance(&bob), 800_0000000);
+        assert_eq!(t.xlm.balance(&carol), 900_0000000);
+        assert_eq!(t.xlm.balance(&t.admin), 800_0000000);
+        assert_eq!(t.xlm.balance(&t.contract_addr), 0);
+        assert_eq!(t.client.held_funds(&Currency::Xlm), 0);
+        assert_eq!(
+            t.client.try_release_sealed_deposit(&id, &bob),
+            Err(Ok(AuctionError::DepositAlreadyReleased))
+        );
+    }
 
-        t.client.bid_usdc(&0, &bidder, &60_0000000);
+    #[test]
+    fn test_sealed_first_price_winner_pays_own_bid() {
+        let t = setup();
+        let id = create_sealed(&t, SealedPricing::FirstPrice);
+        let alice = Address::generate(&t.env);
+        let bob = Address::generate(&t.env);
+        commit(&t, id, &alice, 900_0000000, 1000_0000000, 1);
+        commit(&t, id, &bob, 700_0000000, 700_0000000, 2);
+        assert_eq!(
+            t.client.try_commit_bid(&id, &bob, &salt(&t, 9), &START_XLM),
+            Err(Ok(AuctionError::AlreadyCommitted))
+        );
+        assert_eq!(
+            t.client.try_release_sealed_deposit(&id, &bob),
+            Err(Ok(AuctionError::NotFinalized))
+        );
 
-        let bid = t.client.get_highest_bid(&0);
-        assert_eq!(bid.bidder, bidder);
-        assert_eq!(bid.amount, 60_0000000);
-        assert_eq!(bid.currency, Currency::Usdc);
-        assert_eq!(t.usdc.balance(&bidder), 0);
+        let end_time = t.client.get_auction(&id).end_time;
+        t.env.ledger().set_timestamp(end_time + 1);
+        t.client.reveal_bid(&id, &bob, &700_0000000, &salt(&t, 2));
+        t.client.reveal_bid(&id, &alice, &900_0000000, &salt(&t, 1));
+        t.env.ledger().set_timestamp(end_time + REVEAL_SECONDS + 1);
+        t.client.finalize(&id);
+
+        assert_eq!(t.nft.owner_of(&0), alice);
+        assert_eq!(t.xlm.balance(&t.admin), 900_0000000);
+        t.client.release_sealed_deposit(&id, &alice);
+        t.client.release_sealed_deposit(&id, &bob);
+        assert_eq!(t.xlm.balance(&alice), 100_0000000);
+        assert_eq!(t.xlm.balance(&bob), 700_0000000);
+        assert!(t.client.get_sealed_bid(&id, &bob).released);
+
+        let token_id = mint_nft(&t, &t.admin);
+        let result = t.client.try_create_sealed_auction(
+            &t.admin,
+            &token_id,
+            &START_XLM,
+            &Currency::Xlm,
+            &AUCTION_DURATION,
+            &SealedBidRules {
+                reveal_seconds: REVEAL_SECONDS,
+                pricing: SealedPricing::FirstPrice,
+                slash_bps: 10_001,
+            },
+        );
+        assert_eq!(result, Err(Ok(AuctionError::InvalidSealedRules)));
     }
 
     #[test]
-    fn test_outbid_refunds_previous_bidder() {
+    fn test_copied_sealed_commitment_cannot_be_revealed() {
         let t = setup();
-        create_xlm_auction(&t);
+        let id = create_sealed(&t, SealedPricing::SecondPrice);
+        let victim = Address::generate(&t.env);
+        let shill = Address::generate(&t.env);
+        commit(&t, id, &victim, 900_0000000, 1000_0000000, 1);
+
+        // The shill copies the victim's commitment hash and deposit.
+        let copied = t.client.get_sealed_bid(&id, &victim).commitment;
+        fund_xlm(&t, &shill, 1000_0000000);
+        t.client.commit_bid(&id, &shill, &copied, &1000_0000000);
+
+        // A commitment replayed into another auction does not open either.
+        let other_id = create_sealed(&t, SealedPricing::SecondPrice);
+        fund_xlm(&t, &victim, 1000_0000000);
+        t.client.commit_bid(&other_id, &victim, &copied, &1000_0000000);
+
+        let end_time = t.client.get_auction(&id).end_time;
+        t.env.ledger().set_timestamp(end_time + 1);
+        t.client.reveal_bid(&id, &victim, &900_0000000, &salt(&t, 1));
+        assert_eq!(
+            t.client.try_reveal_bid(&id, &shill, &900_0000000, &salt(&t, 1)),
+            Err(Ok(AuctionError::RevealMismatch))
+        );
+        assert_eq!(
+            t.client.try_reveal_bid(&other_id, &victim, &900_0000000, &salt(&t, 1)),
+            Err(Ok(AuctionError::RevealMismatch))
+        );
 
-        let bidder1 = Address::generate(&t.env);
-        let bidder2 = Address::generate(&t.env);
-        fund_xlm(&t, &bidder1, 600_0000000);
-        fund_xlm(&t, &bidder2, 700_0000000);
+        // With the shill's reveal rejected, the victim pays the starting price.
+        t.env.ledger().set_timestamp(end_time + REVEAL_SECONDS + 1);
+        t.client.finalize(&id);
+        assert_eq!(t.nft.owner_of(&0), victim);
+        assert_eq!(t.xlm.balance(&t.admin), START_XLM);
+    }
 
-        t.client.bid_xlm(&0, &bidder1, &600_0000000);
-        assert_eq!(t.xlm.balance(&bidder1), 0);
+    // ── Drop auctions ──────────────────────────────────────────
 
-        // Bidder2 outbids — bidder1 should be refunded
-        t.client.bid_xlm(&0, &bidder2, &700_0000000);
-        assert_eq!(t.xlm.balance(&bidder1), 600_0000000); // refunded
-        assert_eq!(t.xlm.balance(&bidder2), 0); // locked in contract
+    const DROP_ROYALTY_BPS: u32 = 500;
 
-        let bid = t.client.get_highest_bid(&0);
-        assert_eq!(bid.bidder, bidder2);
+    fn create_drop(t: &TestEnv, creator: &Address, editions: u32) -> u64 {
+        let uri = String::from_str(&t.env, "ipfs://shimeji-drop");
+        t.client.create_drop_auction(
+            creator,
+            &uri,
+            &DropTerms { edition_size: editions, royalty_bps: DROP_ROYALTY_BPS },
+            &100_0000000,
+            &Currency::Xlm,
+            &AUCTION_DURATION,
+        )
     }
 
     #[test]
-    fn test_cross_currency_outbid() {
+    fn test_drop_mints_an_edition_to_each_top_bidder() {
         let t = setup();
-        configure_oracle(&t);
-        create_xlm_auction(&t); // XLM auction at 500 XLM
+        let id = create_drop(&t, &t.admin, 2);
+        let a = Address::generate(&t.env);
+        let b = Address::generate(&t.env);
+        let c = Address::generate(&t.env);
+        let d = Address::generate(&t.env);
+        for bidder in [&a, &b, &c, &d] {
+            fund_xlm(&t, bidder, 200_0000000);
+        }
 
-        // Bidder1 bids 600 XLM (same currency as auction, no oracle needed)
-        let bidder1 = Address::generate(&t.env);
-        fund_xlm(&t, &bidder1, 600_0000000);
-        t.client.bid_xlm(&0, &bidder1, &600_0000000);
+        t.client.bid_xlm(&id, &a, &100_0000000);
+        t.client.bid_xlm(&id, &b, &150_0000000);
+        // The book is full: 120 beats the lowest place (100) by more than 5%.
+        t.client.bid_xlm(&id, &c, &120_0000000);
+        assert_eq!(t.client.pending_refunds(&a), (100_0000000, 0));
+        assert_eq!(
+            t.client.try_bid_xlm(&id, &d, &125_0000000),
+            Err(Ok(AuctionError::BidIncrementTooLow))
+        );
+        let book = t.client.get_drop_bids(&id);
+        assert_eq!(book.len(), 2);
+        assert_eq!(book.get(0).unwrap().bidder, b);
+        assert_eq!(book.get(1).unwrap().bidder, c);
 
-        // Bidder2 outbids with 70 USDC (cross-currency: oracle converts)
-        // 600 XLM * 0.10 = 60 USDC equivalent; 5% of 60 = 3; min = 63 USDC.
-        // 70 USDC > 63 USDC → valid.
-        let bidder2 = Address::generate(&t.env);
-        fund_usdc(&t, &bidder2, 70_0000000);
-        t.client.bid_usdc(&0, &bidder2, &70_0000000);
+        t.env.ledger().with_mut(|li| {
+            li.timestamp += AUCTION_DURATION + 1;
+        });
+        t.client.finalize(&id);
+        let events = auction_events(&t);
+        assert_eq!(
+            events.get(0).unwrap().0,
+            (symbol_short!("edition"), id, b.clone()).into_val(&t.env)
+        );
+        let minted: (u64, i128, Currency) = events.get(1).unwrap().1.into_val(&t.env);
+        assert_eq!(minted, (1, 120_0000000, Currency::Xlm));
+
+        assert_eq!(t.nft.owner_of(&0), b);
+        assert_eq!(t.nft.owner_of(&1), c);
+        assert_eq!(t.nft.token_uri(&1), String::from_str(&t.env, "ipfs://shimeji-drop"));
+        // Every edition credits the drop's creator, not its winner.
+        for token_id in [0, 1] {
+            assert_eq!(t.nft.creator_of(&token_id), t.admin);
+            assert_eq!(
+                t.nft.royalty_info(&token_id, &1000_0000000),
+                (t.admin.clone(), 1000_0000000 * DROP_ROYALTY_BPS as i128 / 10_000)
+            );
+        }
+        assert_eq!(t.xlm.balance(&t.admin), 270_0000000);
+        assert_eq!(t.xlm.balance(&t.contract_addr), 100_0000000); // a's unclaimed refund
+        let info = t.client.get_auction(&id);
+        assert_eq!(info.status, AuctionStatus::Finalized);
+        assert!(info.escrow_settled);
+    }
+
+    #[test]
+    fn test_drop_creation_and_bidding_rules() {
+        let t = setup();
+        let creator = Address::generate(&t.env);
+        assert_eq!(
+            t.client.try_create_drop_auction(
+                &creator,
+                &String::from_str(&t.env, "ipfs://shimeji-drop"),
+                &DropTerms { edition_size: 3, royalty_bps: 0 },
+                &100_0000000,
+                &Currency::Xlm,
+                &AUCTION_DURATION,
+            ),
+            Err(Ok(AuctionError::NotDropCreator))
+        );
+        t.client.grant_role(&Role::Minter, &creator);
+        for editions in [0, MAX_DROP_EDITIONS + 1] {
+            assert_eq!(
+                t.client.try_create_drop_auction(
+                    &creator,
+                    &String::from_str(&t.env, "ipfs://shimeji-drop"),
+                    &DropTerms { edition_size: editions, royalty_bps: 0 },
+                    &100_0000000,
+                    &Currency::Xlm,
+                    &AUCTION_DURATION,
+                ),
+                Err(Ok(AuctionError::InvalidEditionSize))
+            );
+        }
+        assert_eq!(
+            t.client.try_create_drop_auction(
+                &creator,
+                &String::from_str(&t.env, "ipfs://shimeji-drop"),
+                &DropTerms { edition_size: 3, royalty_bps: MAX_ROYALTY_BPS + 1 },
+                &100_0000000,
+                &Currency::Xlm,
+                &AUCTION_DURATION,
+            ),
+            Err(Ok(AuctionError::InvalidRoyalty))
+        );
+        let id = create_drop(&t, &creator, 3);
 
-        // Bidder1 refunded in XLM
-        assert_eq!(t.xlm.balance(&bidder1), 600_0000000);
-        let bid = t.client.get_highest_bid(&0);
-        assert_eq!(bid.bidder, bidder2);
-        assert_eq!(bid.currency, Currency::Usdc);
+        let bidder = Address::generate(&t.env);
+        fund_xlm(&t, &bidder, 200_0000000);
+        fund_usdc(&t, &bidder, 20_0000000);
+        assert_eq!(
+            t.client.try_bid_usdc(&id, &bidder, &20_0000000),
+            Err(Ok(AuctionError::CurrencyMismatch))
+        );
+        assert_eq!(
+            t.client.try_bid_xlm(&id, &bidder, &99_0000000),
+            Err(Ok(AuctionError::BidBelowMinimum))
+        );
+        assert_eq!(
+            t.client.try_buy_now_xlm(&id, &bidder),
+            Err(Ok(AuctionError::WrongAuctionFormat))
+        );
+        t.client.bid_xlm(&id, &bidder, &100_0000000);
+        t.client.bid_xlm(&id, &bidder, &100_0000000);
+        assert_eq!(
+            t.client.try_cancel_auction(&creator, &id),
+            Err(Ok(AuctionError::AuctionHasBids))
+        );
+
+        t.client.force_cancel_auction(&id);
+        assert_eq!(t.client.pending_refunds(&bidder), (200_0000000, 0));
+        assert!(t.client.get_drop_bids(&id).is_empty());
+        assert_eq!(t.client.get_auction(&id).status, AuctionStatus::Cancelled);
     }
 
+    // ── Oracle guard ───────────────────────────────────────────
+
     #[test]
-    #[should_panic(expected = "oracle not configured for cross-currency comparison")]
-    fn test_cross_currency_bid_without_oracle_panics() {
+    fn test_stale_or_out_of_bounds_oracle_price_is_rejected() {
         let t = setup();
+        let oracle = configure_oracle(&t);
         create_xlm_auction(&t);
-
         let bidder = Address::generate(&t.env);
         fund_usdc(&t, &bidder, 60_0000000);
+
+        t.env.ledger().with_mut(|li| {
+            li.timestamp = 10_000;
+        });
+        oracle.set_price(&10_000_000_000_000, &(10_000 - 901));
+        assert_eq!(
+            t.client.try_bid_usdc(&0, &bidder, &60_0000000),
+            Err(Ok(AuctionError::StaleOraclePrice))
+        );
+
+        oracle.set_price(&0, &10_000);
+        assert_eq!(
+            t.client.try_bid_usdc(&0, &bidder, &60_0000000),
+            Err(Ok(AuctionError::OraclePriceOutOfBounds))
+        );
+
+        // $2/XLM is outside a $0.05–$1 band.
+        let guard = OracleGuard {
+            max_age_seconds: 300,
+            min_rate: 500_000,
+            max_rate: 10_000_000,
+            twap_records: 0,
+        };
+        t.client.configure_oracle_guard(&guard);
+        assert_eq!(t.client.get_oracle_guard(), guard);
+        oracle.set_price(&200_000_000_000_000, &10_000);
+        assert_eq!(
+            t.client.try_bid_usdc(&0, &bidder, &60_0000000),
+            Err(Ok(AuctionError::OraclePriceOutOfBounds))
+        );
+
+        oracle.set_price(&10_000_000_000_000, &10_000);
         t.client.bid_usdc(&0, &bidder, &60_0000000);
+        assert_eq!(t.client.get_highest_bid(&0).bidder, bidder);
+
+        assert_eq!(
+            t.client.try_configure_oracle_guard(&OracleGuard { min_rate: 0, ..guard.clone() }),
+            Err(Ok(AuctionError::InvalidOracleGuard))
+        );
+        assert_eq!(
+            t.client.try_configure_oracle_guard(&OracleGuard { max_rate: 1, ..guard }),
+            Err(Ok(AuctionError::InvalidOracleGuard))
+        );
     }
 
     #[test]
-    #[should_panic(expected = "bid below minimum starting price")]
-    fn test_cross_currency_first_bid_below_minimum_from_starting_price() {
+    fn test_twap_rate_is_used_and_published() {
         let t = setup();
-        configure_oracle(&t);
-        create_xlm_auction(&t); // 500 XLM => 50 USDC equivalent with mock oracle
+        let oracle = configure_oracle(&t);
+        create_xlm_auction(&t); // 500 XLM => 100 USDC at a $0.20 average
+        t.client.configure_oracle_guard(&OracleGuard {
+            max_age_seconds: 900,
+            min_rate: 1,
+            max_rate: i128::MAX,
+            twap_records: 5,
+        });
 
         let bidder = Address::generate(&t.env);
-        fund_usdc(&t, &bidder, 49_0000000);
-        t.client.bid_usdc(&0, &bidder, &49_0000000);
+        fund_usdc(&t, &bidder, 100_0000000);
+        assert_eq!(
+            t.client.try_bid_usdc(&0, &bidder, &60_0000000),
+            Err(Ok(AuctionError::StaleOraclePrice))
+        );
+
+        oracle.set_twap(&20_000_000_000_000);
+        assert_eq!(
+            t.client.try_bid_usdc(&0, &bidder, &99_0000000),
+            Err(Ok(AuctionError::BidBelowMinimum))
+        );
+        t.client.bid_usdc(&0, &bidder, &100_0000000);
+        let (topics, data) = auction_events(&t).first().unwrap();
+        assert_eq!(topics, (symbol_short!("xlm_rate"),).into_val(&t.env));
+        let used: (i128, u64) = data.into_val(&t.env);
+        assert_eq!(used, (2_000_000, t.env.ledger().timestamp()));
     }
 
+    // ── Platform fees ──────────────────────────────────────────
+
     #[test]
-    fn test_cross_currency_first_bid_exact_minimum_from_starting_price() {
+    fn test_auction_fee_accrues_and_seller_fee_royalty_add_up() {
         let t = setup();
-        configure_oracle(&t);
-        create_xlm_auction(&t); // 500 XLM => 50 USDC equivalent with mock oracle
+        let treasury = Address::generate(&t.env);
+        let treasurer = Address::generate(&t.env);
+        assert_eq!(
+            t.client.try_configure_fees(&FeeConfig {
+                treasury: treasury.clone(),
+                auction_bps: MAX_FEE_BPS + 1,
+            }),
+            Err(Ok(AuctionError::InvalidFee))
+        );
+        t.client.configure_fees(&FeeConfig {
+            treasury: treasury.clone(),
+            auction_bps: 250,
+        });
 
+        let seller = Address::generate(&t.env);
+        let creator = Address::generate(&t.env);
+        let token_id = mint_nft(&t, &seller);
+        t.nft.set_royalty(&token_id, &creator, &1_000);
+        let id = t.client.create_item_auction(
+            &seller,
+            &token_id,
+            &START_XLM,
+            &Currency::Xlm,
+            &AUCTION_DURATION,
+            &AuctionOptions::default(),
+        );
         let bidder = Address::generate(&t.env);
-        fund_usdc(&t, &bidder, 50_0000000);
-        t.client.bid_usdc(&0, &bidder, &50_0000000);
+        let payment = 613_3333333;
+        fund_xlm(&t, &bidder, payment);
+        t.client.bid_xlm(&id, &bidder, &payment);
+        t.env.ledger().with_mut(|li| {
+            li.timestamp += AUCTION_DURATION + 1;
+        });
+        t.client.finalize(&id);
+
+        let fee = t.client.accrued_fees(&Currency::Xlm);
+        assert_eq!(fee, payment * 250 / 10_000);
+        assert_eq!(t.xlm.balance(&creator), payment / 10);
+        assert_eq!(t.xlm.balance(&seller) + fee + t.xlm.balance(&creator), payment);
+        assert_eq!(t.xlm.balance(&t.contract_addr), fee);
+        assert_eq!(t.client.held_funds(&Currency::Xlm), fee);
+        assert_eq!(
+            t.client.try_withdraw_xlm(&1),
+            Err(Ok(AuctionError::WithdrawalExceedsFreeBalance))
+        );
 
-        let bid = t.client.get_highest_bid(&0);
-        assert_eq!(bid.bidder, bidder);
-        assert_eq!(bid.amount, 50_0000000);
-        assert_eq!(bid.currency, Currency::Usdc);
+        assert_eq!(
+            t.client.try_collect_fees(&treasurer, &Currency::Xlm),
+            Err(Ok(AuctionError::NotTreasurer))
+        );
+        t.client.grant_role(&Role::Treasurer, &treasurer);
+        t.client.pause(&t.admin, &PauseFlag::Withdrawals);
+        assert_eq!(
+            t.client.try_collect_fees(&treasurer, &Currency::Xlm),
+            Err(Ok(AuctionError::Paused))
+        );
+        t.client.unpause(&t.admin, &PauseFlag::Withdrawals);
+        assert_eq!(t.client.collect_fees(&treasurer, &Currency::Xlm), fee);
+        assert_eq!(t.xlm.balance(&treasury), fee);
+        assert_eq!(t.client.accrued_fees(&Currency::Xlm), 0);
+        assert_eq!(t.client.held_funds(&Currency::Xlm), 0);
+        assert_eq!(
+            t.client.try_collect_fees(&treasurer, &Currency::Xlm),
+            Err(Ok(AuctionError::NoFeesAccrued))
+        );
     }
 
     #[test]
-    fn test_finalize_no_bids() {
+    fn test_drop_editions_pay_the_auction_fee() {
         let t = setup();
-        create_xlm_auction(&t);
-
+        let treasury = Address::generate(&t.env);
+        t.client.configure_fees(&FeeConfig {
+            treasury,
+            auction_bps: 500,
+        });
+        let id = create_drop(&t, &t.admin, 2);
+        let bidder = Address::generate(&t.env);
+        fund_xlm(&t, &bidder, 300_0000000);
+        t.client.bid_xlm(&id, &bidder, &100_0000000);
+        t.client.bid_xlm(&id, &bidder, &200_0000000);
         t.env.ledger().with_mut(|li| {
             li.timestamp += AUCTION_DURATION + 1;
         });
+        t.client.finalize(&id);
 
-        t.client.finalize(&0);
-        let info = t.client.get_auction(&0);
-        assert!(info.finalized);
-        assert!(!info.escrow_settled);
+        assert_eq!(t.client.accrued_fees(&Currency::Xlm), 15_0000000);
+        assert_eq!(t.client.held_funds(&Currency::Xlm), 15_0000000);
+        assert_eq!(t.xlm.balance(&t.admin), 285_0000000);
     }
 
+    // ── Refunds ────────────────────────────────────────────────
+
     #[test]
-    fn test_finalize_item_auction_transfers_nft_and_pays_seller() {
+    fn test_push_refund_falls_back_to_credit_when_transfer_fails() {
         let t = setup();
-        create_xlm_auction(&t);
+        t.client.configure_push_refunds(&true);
+        let token_id = mint_nft(&t, &t.admin);
+        let id = t.client.create_item_auction(
+            &t.admin,
+            &token_id,
+            &50_0000000,
+            &Currency::Usdc,
+            &AUCTION_DURATION,
+            &AuctionOptions::default(),
+        );
+
+        let bidder1 = Address::generate(&t.env);
+        let bidder2 = Address::generate(&t.env);
+        fund_usdc(&t, &bidder1, 60_0000000);
+        fund_usdc(&t, &bidder2, 70_0000000);
+        t.client.bid_usdc(&id, &bidder1, &60_0000000);
+
+        // A frozen trustline can no longer receive, but must not block the outbid.
+        t.usdc_sac.set_authorized(&bidder1, &false);
+        t.client.bid_usdc(&id, &bidder2, &70_0000000);
+        assert_eq!(
+            auction_events(&t).first().unwrap().0,
+            (symbol_short!("credited"), id, bidder1.clone()).into_val(&t.env)
+        );
+        assert_eq!(t.client.get_highest_bid(&id).bidder, bidder2);
+        assert_eq!(t.client.pending_refunds(&bidder1), (0, 60_0000000));
+
+        t.usdc_sac.set_authorized(&bidder1, &true);
+        t.client.claim_refund(&bidder1, &Currency::Usdc);
+        assert_eq!(t.usdc.balance(&bidder1), 60_0000000);
+    }
 
+    #[test]
+    fn test_refunds_accrue_per_currency() {
+        let t = setup();
+        configure_oracle(&t);
         let bidder = Address::generate(&t.env);
+        let rival = Address::generate(&t.env);
         fund_xlm(&t, &bidder, 600_0000000);
-        t.client.bid_xlm(&0, &bidder, &600_0000000);
+        fund_usdc(&t, &bidder, 60_0000000);
+        fund_xlm(&t, &rival, 2000_0000000);
+
+        let first = create_xlm_auction(&t);
+        let token_id = mint_nft(&t, &t.admin);
+        let second = t.client.create_item_auction(
+            &t.admin,
+            &token_id,
+            &START_XLM,
+            &Currency::Xlm,
+            &AUCTION_DURATION,
+            &AuctionOptions::default(),
+        );
+        t.client.bid_xlm(&first, &bidder, &600_0000000);
+        t.client.bid_usdc(&second, &bidder, &60_0000000);
+        t.client.bid_xlm(&first, &rival, &700_0000000);
+        t.client.bid_xlm(&second, &rival, &700_0000000);
+        assert_eq!(t.client.pending_refunds(&bidder), (600_0000000, 60_0000000));
+
+        t.client.claim_refund(&bidder, &Currency::Usdc);
+        assert_eq!(t.usdc.balance(&bidder), 60_0000000);
+        assert_eq!(t.client.pending_refunds(&bidder), (600_0000000, 0));
+    }
 
+    #[test]
+    fn test_admin_withdrawals_cannot_touch_bids_or_refunds() {
+        let t = setup();
+        let id = create_xlm_auction(&t);
+        let bidder = Address::generate(&t.env);
+        let rival = Address::generate(&t.env);
+        fund_xlm(&t, &bidder, 600_0000000);
+        fund_xlm(&t, &rival, 700_0000000);
+        t.client.bid_xlm(&id, &bidder, &600_0000000);
+        t.client.bid_xlm(&id, &rival, &700_0000000);
+        // Anything sent to the contract outside an auction stays withdrawable.
+        fund_xlm(&t, &t.contract_addr, 50_0000000);
+        assert_eq!(t.client.held_funds(&Currency::Xlm), 1300_0000000);
+
+        assert_eq!(
+            t.client.try_withdraw_xlm(&50_0000001),
+            Err(Ok(AuctionError::WithdrawalExceedsFreeBalance))
+        );
+        t.client.withdraw_xlm(&50_0000000);
+        assert_eq!(t.xlm.balance(&t.contract_addr), 1300_0000000);
+
+        t.client.claim_refund(&bidder, &Currency::Xlm);
+        assert_eq!(t.client.held_funds(&Currency::Xlm), 700_0000000);
         t.env.ledger().with_mut(|li| {
             li.timestamp += AUCTION_DURATION + 1;
         });
-
-        t.client.finalize(&0);
-
-        let info = t.client.get_auction(&0);
-        assert_eq!(info.escrow_provider, EscrowProvider::Internal);
-        assert!(info.finalized);
-        assert!(info.escrow_settled);
+        t.client.finalize(&id);
+        assert_eq!(t.client.held_funds(&Currency::Xlm), 0);
         assert_eq!(t.xlm.balance(&t.contract_addr), 0);
-        assert_eq!(t.xlm.balance(&t.admin), 600_0000000);
-        assert_eq!(t.nft.owner_of(&0), bidder);
     }
 
     #[test]
-    fn test_item_auction_uses_trustless_config_when_available() {
+    fn test_migrate_rebuilds_held_funds_for_escrow_from_older_code() {
         let t = setup();
-        let xlm_destination = Address::generate(&t.env);
-        let usdc_destination = Address::generate(&t.env);
-        configure_trustless(&t, &xlm_destination, &usdc_destination);
-        create_xlm_auction(&t);
-
+        let english = create_xlm_auction(&t);
         let bidder = Address::generate(&t.env);
+        let rival = Address::generate(&t.env);
         fund_xlm(&t, &bidder, 600_0000000);
-        t.client.bid_xlm(&0, &bidder, &600_0000000);
-        assert_eq!(t.xlm.balance(&xlm_destination), 0);
+        fund_xlm(&t, &rival, 700_0000000);
+        t.client.bid_xlm(&english, &bidder, &600_0000000);
+        t.client.bid_xlm(&english, &rival, &700_0000000);
+
+        let sealed = create_sealed(&t, SealedPricing::SecondPrice);
+        let alice = Address::generate(&t.env);
+        let bob = Address::generate(&t.env);
+        commit(&t, sealed, &alice, 900_0000000, 1000_0000000, 1);
+        commit(&t, sealed, &bob, 700_0000000, 800_0000000, 2);
+        t.env.ledger().with_mut(|li| li.timestamp += AUCTION_DURATION + 1);
+        t.client.reveal_bid(&sealed, &alice, &900_0000000, &salt(&t, 1));
+        t.client.reveal_bid(&sealed, &bob, &700_0000000, &salt(&t, 2));
+        t.env.ledger().with_mut(|li| li.timestamp += REVEAL_SECONDS);
+        t.client.finalize(&sealed);
+
+        // Bidder's credited 600, rival's standing 700, alice's 1000 deposit less
+        // the 700 second price, and bob's whole 800 deposit.
+        let owed = 2400_0000000;
+        assert_eq!(t.client.held_funds(&Currency::Xlm), owed);
+
+        // Storage written before the total existed.
+        t.env.as_contract(&t.contract_addr, || {
+            let storage = t.env.storage().instance();
+            storage.remove(&DataKey::HeldFunds(Currency::Xlm));
+            storage.remove(&DataKey::HeldFunds(Currency::Usdc));
+            storage.set(&DataKey::SchemaVersion, &3u32);
+        });
+        assert_eq!(t.client.held_funds(&Currency::Xlm), 0);
+        let bidders = vec![&t.env, bidder.clone(), rival.clone(), alice, bob, bidder.clone()];
+        assert_eq!(t.client.migrate(&bidders), SCHEMA_VERSION);
+        assert_eq!(t.client.held_funds(&Currency::Xlm), owed);
+        assert_eq!(t.client.held_funds(&Currency::Usdc), 0);
+        assert_eq!(
+            t.client.try_withdraw_xlm(&1),
+            Err(Ok(AuctionError::WithdrawalExceedsFreeBalance))
+        );
 
-        t.env.ledger().with_mut(|li| {
-            li.timestamp += AUCTION_DURATION + 1;
+        // A payout the total never counted fails instead of being absorbed.
+        t.env.as_contract(&t.contract_addr, || {
+            t.env.storage().instance().set(&DataKey::HeldFunds(Currency::Xlm), &0i128);
         });
+        assert_eq!(
+            t.client.try_claim_refund(&bidder, &Currency::Xlm),
+            Err(Ok(AuctionError::HeldFundsUnderflow))
+        );
+    }
 
-        t.client.finalize(&0);
+    // ── Bid history ────────────────────────────────────────────
 
-        let info = t.client.get_auction(&0);
-        assert_eq!(info.escrow_provider, EscrowProvider::TrustlessWork);
-        assert!(info.escrow_settled);
-        assert_eq!(t.xlm.balance(&t.contract_addr), 0);
-        assert_eq!(t.xlm.balance(&xlm_destination), 600_0000000);
-        assert_eq!(t.xlm.balance(&t.admin), 0);
-        assert_eq!(t.nft.owner_of(&0), bidder);
+    #[test]
+    fn test_bid_log_pages_in_order_with_usdc_values() {
+        let t = setup();
+        configure_oracle(&t);
+        let id = create_xlm_auction(&t);
+        let alice = Address::generate(&t.env);
+        let bob = Address::generate(&t.env);
+        fund_xlm(&t, &alice, 2000_0000000);
+        fund_usdc(&t, &bob, 200_0000000);
+
+        t.client.bid_xlm(&id, &alice, &600_0000000);
+        t.env.ledger().set_timestamp(100);
+        t.client.bid_usdc(&id, &bob, &70_0000000);
+        t.env.ledger().set_timestamp(200);
+        t.client.bid_xlm(&id, &alice, &800_0000000);
+        assert_eq!(t.client.bid_count(&id), 3);
+
+        let bids = t.client.get_bids(&id, &0, &10);
+        assert_eq!(bids.len(), 3);
+        let first = bids.get(0).unwrap();
+        assert_eq!(first.bidder, alice);
+        assert_eq!(first.amount, 600_0000000);
+        assert_eq!(first.usdc_value, Some(60_0000000));
+        let second = bids.get(1).unwrap();
+        assert_eq!(second.bidder, bob);
+        assert_eq!(second.currency, Currency::Usdc);
+        assert_eq!(second.usdc_value, Some(70_0000000));
+        assert_eq!(second.timestamp, 100);
+        assert_eq!(bids.get(2).unwrap().timestamp, 200);
+
+        let page = t.client.get_bids(&id, &1, &1);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page.get(0).unwrap().bidder, bob);
+        assert_eq!(t.client.get_bids(&id, &3, &10).len(), 0);
+        assert_eq!(t.client.bid_count(&99), 0);
     }
 
     #[test]
-    fn test_bid_exactly_at_minimum() {
+    fn test_bid_log_keeps_only_the_most_recent_bids() {
         let t = setup();
-        create_xlm_auction(&t);
+        t.client.configure_bid_history(&2);
+        let id = create_xlm_auction(&t);
+        assert_eq!(t.client.get_auction(&id).bid_history_limit, 2);
 
         let bidder = Address::generate(&t.env);
-        fund_xlm(&t, &bidder, START_XLM);
-
-        // Bid exactly at starting price — should succeed
-        t.client.bid_xlm(&0, &bidder, &START_XLM);
-        assert_eq!(t.client.get_highest_bid(&0).amount, START_XLM);
+        fund_xlm(&t, &bidder, 5000_0000000);
+        let amounts = [600_0000000i128, 700_0000000, 800_0000000, 900_0000000];
+        for amount in amounts.iter() {
+            t.client.bid_xlm(&id, &bidder, amount);
+        }
+        assert_eq!(t.client.bid_count(&id), 4);
+
+        // XLM bids carry no USDC value without an oracle.
+        let bids = t.client.get_bids(&id, &0, &10);
+        assert_eq!(bids.len(), 2);
+        assert_eq!(bids.get(0).unwrap().amount, 800_0000000);
+        assert_eq!(bids.get(1).unwrap().amount, 900_0000000);
+        assert_eq!(bids.get(0).unwrap().usdc_value, None);
+
+        assert_eq!(
+            t.client.try_configure_bid_history(&(MAX_BID_HISTORY_LIMIT + 1)),
+            Err(Ok(AuctionError::InvalidBidHistoryLimit))
+        );
     }
 
-    // ── Edge cases ─────────────────────────────────────────────
+    // ── Cancellation ───────────────────────────────────────────
 
     #[test]
-    #[should_panic(expected = "bid below minimum starting price")]
-    fn test_bid_below_minimum_xlm() {
+    fn test_seller_can_cancel_before_first_bid() {
         let t = setup();
-        create_xlm_auction(&t); // 500 XLM minimum
+        let id = create_xlm_auction(&t);
+        let stranger = Address::generate(&t.env);
+        assert_eq!(
+            t.client.try_cancel_auction(&stranger, &id),
+            Err(Ok(AuctionError::NotSeller))
+        );
+
+        t.client.cancel_auction(&t.admin, &id);
+        let (topics, data) = auction_events(&t).last().unwrap();
+        assert_eq!(topics, (symbol_short!("cancelled"), id).into_val(&t.env));
+        let caller: Address = data.into_val(&t.env);
+        assert_eq!(caller, t.admin);
+        let info = t.client.get_auction(&id);
+        assert_eq!(info.status, AuctionStatus::Cancelled);
+        assert_eq!(t.nft.owner_of(&0), t.admin);
 
         let bidder = Address::generate(&t.env);
-        fund_xlm(&t, &bidder, 400_0000000);
-        t.client.bid_xlm(&0, &bidder, &400_0000000); // below 500 XLM min
+        fund_xlm(&t, &bidder, 600_0000000);
+        assert_eq!(
+            t.client.try_bid_xlm(&id, &bidder, &600_0000000),
+            Err(Ok(AuctionError::AuctionCancelled))
+        );
+        t.env.ledger().with_mut(|li| {
+            li.timestamp += AUCTION_DURATION + 1;
+        });
+        assert_eq!(t.client.try_finalize(&id), Err(Ok(AuctionError::AuctionCancelled)));
+        assert_eq!(
+            t.client.try_cancel_auction(&t.admin, &id),
+            Err(Ok(AuctionError::AuctionCancelled))
+        );
     }
 
     #[test]
-    #[should_panic(expected = "bid below minimum starting price")]
-    fn test_bid_below_minimum_usdc() {
+    fn test_admin_force_cancel_refunds_highest_bid() {
         let t = setup();
-        create_usdc_auction(&t); // 50 USDC minimum
-
+        let seller = Address::generate(&t.env);
+        let token_id = mint_nft(&t, &seller);
+        let id = t.client.create_item_auction(
+            &seller,
+            &token_id,
+            &START_XLM,
+            &Currency::Xlm,
+            &AUCTION_DURATION,
+            &AuctionOptions::default(),
+        );
         let bidder = Address::generate(&t.env);
-        fund_usdc(&t, &bidder, 40_0000000);
-        t.client.bid_usdc(&0, &bidder, &40_0000000); // below 50 USDC min
+        fund_xlm(&t, &bidder, 600_0000000);
+        t.client.bid_xlm(&id, &bidder, &600_0000000);
+        assert_eq!(
+            t.client.try_cancel_auction(&seller, &id),
+            Err(Ok(AuctionError::AuctionHasBids))
+        );
+
+        t.client.force_cancel_auction(&id);
+        assert_eq!(t.client.get_auction(&id).status, AuctionStatus::Cancelled);
+        assert!(t.client.try_get_highest_bid(&id).is_err());
+        t.client.claim_refund(&bidder, &Currency::Xlm);
+        assert_eq!(t.xlm.balance(&bidder), 600_0000000);
+        assert_eq!(t.xlm.balance(&t.contract_addr), 0);
+        assert_eq!(t.nft.owner_of(&token_id), seller);
     }
 
     #[test]
-    #[should_panic(expected = "bid must be at least 5% higher")]
-    fn test_bid_insufficient_increment() {
+    fn test_force_cancelled_sealed_auction_returns_deposits_in_full() {
         let t = setup();
-        create_xlm_auction(&t);
+        let id = create_sealed(&t, SealedPricing::SecondPrice);
+        let alice = Address::generate(&t.env);
+        commit(&t, id, &alice, 900_0000000, 1000_0000000, 1);
+        assert_eq!(
+            t.client.try_cancel_auction(&t.admin, &id),
+            Err(Ok(AuctionError::AuctionHasBids))
+        );
+
+        // Cancelled mid-reveal: no slashing even though nobody revealed.
+        let end_time = t.client.get_auction(&id).end_time;
+        t.env.ledger().set_timestamp(end_time + 1);
+        t.client.force_cancel_auction(&id);
+        assert_eq!(
+            t.client.try_reveal_bid(&id, &alice, &900_0000000, &salt(&t, 1)),
+            Err(Ok(AuctionError::AuctionCancelled))
+        );
+        t.client.release_sealed_deposit(&id, &alice);
+        assert_eq!(t.xlm.balance(&alice), 1000_0000000);
+        assert_eq!(t.xlm.balance(&t.admin), 0);
+        assert_eq!(t.nft.owner_of(&0), t.admin);
+    }
+
+    // ── Soft close ─────────────────────────────────────────────
+
+    #[test]
+    fn test_late_bids_extend_end_time_up_to_cap() {
+        let t = setup();
+        t.client.configure_soft_close(&300, &600, &900);
+        let id = create_xlm_auction(&t);
+        let end_time = t.client.get_auction(&id).end_time;
 
         let bidder1 = Address::generate(&t.env);
         let bidder2 = Address::generate(&t.env);
-        fund_xlm(&t, &bidder1, 600_0000000);
-        fund_xlm(&t, &bidder2, 610_0000000);
-
-        t.client.bid_xlm(&0, &bidder1, &600_0000000);
-        // 5% of 600 = 30, min required = 630. Bidding 610 should fail.
-        t.client.bid_xlm(&0, &bidder2, &610_0000000);
+        fund_xlm(&t, &bidder1, 2000_0000000);
+        fund_xlm(&t, &bidder2, 2000_0000000);
+
+        // Outside the window: no extension.
+        t.env.ledger().set_timestamp(end_time - 301);
+        t.client.bid_xlm(&id, &bidder1, &600_0000000);
+        assert_eq!(t.client.get_auction(&id).end_time, end_time);
+
+        // Inside the window: pushed out by the full extension.
+        t.env.ledger().set_timestamp(end_time - 10);
+        t.client.bid_xlm(&id, &bidder2, &700_0000000);
+        let events = auction_events(&t);
+        let (topics, data) = events.last().unwrap();
+        assert_eq!(topics, (symbol_short!("extended"), id).into_val(&t.env));
+        let new_end: u64 = data.into_val(&t.env);
+        assert_eq!(new_end, end_time + 600);
+        let info = t.client.get_auction(&id);
+        assert_eq!(info.end_time, end_time + 600);
+        assert_eq!(info.extended_seconds, 600);
+
+        // The old end time has passed but bidding is still open; only the
+        // remaining 300s of the cap are granted.
+        t.env.ledger().set_timestamp(end_time + 590);
+        t.client.bid_xlm(&id, &bidder1, &800_0000000);
+        assert_eq!(t.client.get_auction(&id).end_time, end_time + 900);
+
+        // Cap reached: late bids no longer extend.
+        t.env.ledger().set_timestamp(end_time + 899);
+        t.client.bid_xlm(&id, &bidder2, &900_0000000);
+        let info = t.client.get_auction(&id);
+        assert_eq!(info.end_time, end_time + 900);
+        assert_eq!(info.extended_seconds, 900);
+
+        assert_eq!(t.client.try_finalize(&id), Err(Ok(AuctionError::AuctionNotEnded)));
+        t.env.ledger().set_timestamp(end_time + 901);
+        assert_eq!(
+            t.client.try_bid_xlm(&id, &bidder1, &1000_0000000),
+            Err(Ok(AuctionError::AuctionEnded))
+        );
+        t.client.finalize(&id);
+        assert_eq!(t.nft.owner_of(&0), bidder2);
     }
 
     #[test]
-    #[should_panic(expected = "auction has ended")]
-    fn test_bid_after_auction_ends() {
+    fn test_running_auctions_keep_their_soft_close_rule() {
         let t = setup();
-        create_xlm_auction(&t);
+        let id = create_xlm_auction(&t);
+        assert_eq!(t.client.get_auction(&id).soft_close, t.client.get_soft_close());
+
+        // Turning soft close off only affects auctions created afterwards.
+        t.client.configure_soft_close(&0, &0, &0);
+        let end_time = t.client.get_auction(&id).end_time;
+        let bidder = Address::generate(&t.env);
+        fund_xlm(&t, &bidder, 600_0000000);
+        t.env.ledger().set_timestamp(end_time - 1);
+        t.client.bid_xlm(&id, &bidder, &600_0000000);
+        assert_eq!(
+            t.client.get_auction(&id).end_time,
+            end_time + DEFAULT_SOFT_CLOSE_EXTENSION
+        );
+
+        assert_eq!(
+            t.client.try_configure_soft_close(&300, &0, &900),
+            Err(Ok(AuctionError::InvalidSoftClose))
+        );
+    }
+
+    // ── Storage TTL ────────────────────────────────────────────
 
+    fn advance_ledgers(t: &TestEnv, ledgers: u32) {
         t.env.ledger().with_mut(|li| {
-            li.timestamp += AUCTION_DURATION + 1;
+            li.sequence_number += ledgers;
         });
+    }
 
+    #[test]
+    fn test_bumped_auction_outlives_default_ttl() {
+        let t = setup();
+        create_xlm_auction(&t);
         let bidder = Address::generate(&t.env);
         fund_xlm(&t, &bidder, 600_0000000);
         t.client.bid_xlm(&0, &bidder, &600_0000000);
+
+        // Far past the network's minimum persistent TTL, inside one bump.
+        advance_ledgers(&t, 20 * DAY_IN_LEDGERS);
+        t.client.bump_auction(&0);
+        // Without the keep-alive above the records would be archived by now.
+        advance_ledgers(&t, 20 * DAY_IN_LEDGERS);
+
+        assert_eq!(t.client.get_auction(&0).seller, t.admin);
+        assert_eq!(t.client.get_highest_bid(&0).bidder, bidder);
+        t.env.as_contract(&t.contract_addr, || {
+            let ttl = t.env.storage().persistent().get_ttl(&DataKey::Auction(0));
+            assert!(ttl > TTL_THRESHOLD);
+        });
+        assert_eq!(t.client.try_bump_auction(&99), Err(Ok(AuctionError::AuctionNotFound)));
     }
 
     #[test]
-    #[should_panic(expected = "auction has not ended yet")]
-    fn test_finalize_before_end() {
+    fn test_bumped_sealed_bids_and_refunds_outlive_default_ttl() {
         let t = setup();
-        create_xlm_auction(&t);
-        t.client.finalize(&0);
+        let english = create_xlm_auction(&t);
+        let bidder = Address::generate(&t.env);
+        let rival = Address::generate(&t.env);
+        fund_xlm(&t, &bidder, 600_0000000);
+        fund_xlm(&t, &rival, 700_0000000);
+        t.client.bid_xlm(&english, &bidder, &600_0000000);
+        t.client.bid_xlm(&english, &rival, &700_0000000);
+        let sealed = create_sealed(&t, SealedPricing::FirstPrice);
+        commit(&t, sealed, &bidder, 900_0000000, 1000_0000000, 1);
+
+        // Far past the network's minimum persistent TTL, inside one bump each.
+        advance_ledgers(&t, 20 * DAY_IN_LEDGERS);
+        t.client.bump_sealed_bid(&sealed, &bidder);
+        t.client.bump_refund(&bidder, &Currency::Xlm);
+        t.env.as_contract(&t.contract_addr, || {
+            let storage = t.env.storage().persistent();
+            let sealed_key = DataKey::SealedBid(sealed, bidder.clone());
+            assert!(storage.get_ttl(&sealed_key) > TTL_THRESHOLD);
+            let refund_key = DataKey::PendingRefund(bidder.clone(), Currency::Xlm);
+            assert!(storage.get_ttl(&refund_key) > TTL_THRESHOLD);
+        });
+        advance_ledgers(&t, 20 * DAY_IN_LEDGERS);
+
+        assert_eq!(t.client.get_sealed_bid(&sealed, &bidder).deposit, 1000_0000000);
+        assert_eq!(t.client.pending_refunds(&bidder), (600_0000000, 0));
+        assert_eq!(
+            t.client.try_bump_sealed_bid(&sealed, &rival),
+            Err(Ok(AuctionError::NoCommitment))
+        );
+        assert_eq!(
+            t.client.try_bump_refund(&bidder, &Currency::Usdc),
+            Err(Ok(AuctionError::NothingToClaim))
+        );
     }
 
+    // ── Upgrades ───────────────────────────────────────────────
+
+    /// Smallest module the host accepts as contract code: no exports, just the
+    /// `contractenvmetav0` section declaring protocol 22. Lets the tests swap the
+    /// executable without building a second contract to wasm.
+    const EMPTY_CONTRACT_WASM: [u8; 40] = [
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic + version
+        0x00, 0x1e, 0x11, // custom section, 30 bytes, 17-byte name
+        b'c', b'o', b'n', b't', b'r', b'a', b'c', b't', // "contract"
+        b'e', b'n', b'v', b'm', b'e', b't', b'a', b'v', b'0', // "envmetav0"
+        0x00, 0x00, 0x00, 0x00, // ScEnvMetaKind::InterfaceVersion
+        0x00, 0x00, 0x00, 0x16, // protocol 22
+        0x00, 0x00, 0x00, 0x00, // pre-release 0
+    ];
+
     #[test]
-    #[should_panic(expected = "auction already finalized")]
-    fn test_double_finalize() {
+    fn test_upgrade_mid_auction_preserves_state() {
         let t = setup();
         create_xlm_auction(&t);
+        let bidder = Address::generate(&t.env);
+        fund_xlm(&t, &bidder, 600_0000000);
+        t.client.bid_xlm(&0, &bidder, &600_0000000);
+        let end_time = t.client.get_auction(&0).end_time;
+
+        let wasm = soroban_sdk::Bytes::from_slice(&t.env, &EMPTY_CONTRACT_WASM);
+        let new_hash = t.env.deployer().upload_contract_wasm(wasm);
+        t.client.upgrade(&new_hash);
+        assert_eq!(
+            auction_events(&t),
+            vec![
+                &t.env,
+                (
+                    (symbol_short!("upgraded"),).into_val(&t.env),
+                    new_hash.into_val(&t.env)
+                ),
+            ]
+        );
 
-        t.env.ledger().with_mut(|li| {
-            li.timestamp += AUCTION_DURATION + 1;
+        // The new code exports nothing, so read storage directly.
+        t.env.as_contract(&t.contract_addr, || {
+            let storage = t.env.storage().instance();
+            let auction: AuctionInfo =
+                t.env.storage().persistent().get(&DataKey::Auction(0)).unwrap();
+            let bid: BidInfo = t.env.storage().persistent().get(&DataKey::HighestBid(0)).unwrap();
+            assert_eq!(auction.seller, t.admin);
+            assert_eq!(auction.end_time, end_time);
+            assert_eq!(auction.status, AuctionStatus::Active);
+            assert_eq!(bid.bidder, bidder);
+            assert_eq!(bid.amount, 600_0000000);
+            assert_eq!(storage.get::<_, u64>(&DataKey::NextAuctionId), Some(1));
+            assert_eq!(storage.get::<_, Address>(&DataKey::Admin), Some(t.admin.clone()));
+            assert_eq!(storage.get::<_, u32>(&DataKey::SchemaVersion), Some(SCHEMA_VERSION));
         });
-
-        t.client.finalize(&0);
-        t.client.finalize(&0); // second finalize should panic
+        // Escrowed bid stays with the contract.
+        assert_eq!(t.xlm.balance(&t.contract_addr), 600_0000000);
     }
 
     #[test]
-    #[should_panic(expected = "auction does not exist")]
-    fn test_bid_nonexistent_auction() {
+    fn test_v1_auctions_are_upgraded_when_read() {
         let t = setup();
+        let legacy = AuctionInfoV1 {
+            token_uri: String::from_str(&t.env, "ipfs://legacy"),
+            is_item_auction: true,
+            seller: t.admin.clone(),
+            token_id: 7,
+            start_time: 0,
+            end_time: AUCTION_DURATION,
+            starting_price: START_XLM,
+            currency: Currency::Xlm,
+            finalized: false,
+            escrow_provider: EscrowProvider::Internal,
+            escrow_settled: false,
+        };
+        t.env.as_contract(&t.contract_addr, || {
+            t.env.storage().persistent().set(&DataKey::Auction(0), &legacy);
+            t.env.storage().instance().set(&DataKey::NextAuctionId, &1u64);
+            t.env.storage().instance().set(&DataKey::SchemaVersion, &1u32);
+        });
+
+        // Readable straight after the wasm upgrade, before migrate has run.
+        let info = t.client.get_auction(&0);
+        assert_eq!(info.token_id, 7);
+        assert_eq!(info.starting_price, START_XLM);
+        assert_eq!(info.reserve_price, None);
+        assert!(!info.reserve_hidden);
+        assert_eq!(info.soft_close.window_seconds, 0);
+        assert_eq!(info.extended_seconds, 0);
+        assert_eq!(info.buy_now_price, None);
+        assert_eq!(info.format, AuctionFormat::English);
+        assert_eq!(info.status, AuctionStatus::Active);
+        assert_eq!(info.bid_history_limit, DEFAULT_BID_HISTORY_LIMIT);
+        assert!(t.client.reserve_met(&0));
+
+        // Legacy auctions keep taking bids without ever being rewritten.
         let bidder = Address::generate(&t.env);
         fund_xlm(&t, &bidder, 600_0000000);
-        t.client.bid_xlm(&99, &bidder, &600_0000000);
+        t.client.bid_xlm(&0, &bidder, &600_0000000);
+        assert_eq!(t.client.get_highest_bid(&0).amount, 600_0000000);
+        assert_eq!(t.client.migrate(&Vec::new(&t.env)), SCHEMA_VERSION);
+        assert_eq!(t.client.get_auction(&0).token_id, 7);
     }
 
     #[test]
-    #[should_panic(expected = "already initialized")]
-    fn test_double_initialize() {
+    fn test_v2_auction_flags_are_read_as_a_status() {
         let t = setup();
-        let nft = Address::generate(&t.env);
-        let usdc = Address::generate(&t.env);
-        let xlm = Address::generate(&t.env);
-        t.client.initialize(&t.admin, &nft, &usdc, &xlm);
+        let legacy = |finalized: bool, cancelled: bool| AuctionInfoV2 {
+            token_uri: String::from_str(&t.env, "ipfs://legacy"),
+            is_item_auction: true,
+            seller: t.admin.clone(),
+            token_id: 7,
+            start_time: 0,
+            end_time: AUCTION_DURATION,
+            starting_price: START_XLM,
+            currency: Currency::Xlm,
+            finalized,
+            escrow_provider: EscrowProvider::Internal,
+            escrow_settled: false,
+            cancelled,
+            reserve_price: Some(START_XLM * 2),
+            reserve_hidden: false,
+            soft_close: ShimejiAuction::disabled_soft_close(),
+            extended_seconds: 0,
+            bid_history_limit: DEFAULT_BID_HISTORY_LIMIT,
+            buy_now_price: None,
+            format: AuctionFormat::English,
+        };
+        t.env.as_contract(&t.contract_addr, || {
+            let storage = t.env.storage().persistent();
+            storage.set(&DataKey::Auction(0), &legacy(false, false));
+            storage.set(&DataKey::Auction(1), &legacy(true, false));
+            // v2 cancellation set both flags.
+            storage.set(&DataKey::Auction(2), &legacy(true, true));
+            t.env.storage().instance().set(&DataKey::NextAuctionId, &3u64);
+            t.env.storage().instance().set(&DataKey::SchemaVersion, &2u32);
+        });
+
+        assert_eq!(t.client.get_auction(&0).status, AuctionStatus::Active);
+        assert_eq!(t.client.get_auction(&0).reserve_price, Some(START_XLM * 2));
+        assert_eq!(t.client.get_auction(&1).status, AuctionStatus::Finalized);
+        assert_eq!(t.client.get_auction(&2).status, AuctionStatus::Cancelled);
+        assert_eq!(t.client.try_finalize(&2), Err(Ok(AuctionError::AuctionCancelled)));
+        assert_eq!(t.client.migrate(&Vec::new(&t.env)), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_migrate_stamps_schema_version() {
+        let t = setup();
+        assert_eq!(t.client.schema_version(), SCHEMA_VERSION);
+        assert_eq!(t.client.migrate(&Vec::new(&t.env)), SCHEMA_VERSION);
+
+        // Deployments from before versioning have no stamp at all.
+        t.env.as_contract(&t.contract_addr, || {
+            t.env.storage().instance().remove(&DataKey::SchemaVersion);
+        });
+        assert_eq!(t.client.schema_version(), 0);
+        assert_eq!(t.client.migrate(&Vec::new(&t.env)), SCHEMA_VERSION);
+        assert_eq!(t.client.schema_version(), SCHEMA_VERSION);
+
+        // Storage written by newer code cannot be migrated down.
+        t.env.as_contract(&t.contract_addr, || {
+            t.env.storage().instance().set(&DataKey::SchemaVersion, &(SCHEMA_VERSION + 1));
+        });
+        assert_eq!(
+            t.client.try_migrate(&Vec::new(&t.env)),
+            Err(Ok(AuctionError::UnsupportedSchemaVersion))
+        );
     }
 }
@@ -15,7 +15,39 @@
 //!      Alternatively, the buyer calls `cancel_commission` while still Open to get a refund.
 //!   6. Admin can force-cancel an Accepted commission and refund the buyer (dispute resolution).
 
-use soroban_sdk::{contract, contractimpl, contracttype, token, Address, Env, String};
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, token, Address, BytesN, Env,
+    IntoVal, String, TryFromVal, Val,
+};
+pub use shimeji_common::{PauseFlag, Role};
+
+// ── Errors ───────────────────────────────────────────────────────────────────
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum CommissionError {
+    AlreadyInitialized = 1,
+    CommissionNotFound = 2,
+    NotOpen = 3,
+    NotAccepted = 4,
+    NotDelivered = 5,
+    NotCancellable = 6,
+    EmptyIntention = 7,
+    IntentionTooLong = 8,
+    ReferenceImageTooLong = 9,
+    InvalidPrice = 10,
+    InvalidRate = 11,
+    NotAssignedArtist = 12,
+    NotBuyer = 13,
+    NotBuyerOrAdmin = 14,
+    NotAdmin = 15,
+    UnsupportedSchemaVersion = 16,
+    NoPendingAdmin = 17,
+    AdminRoleNotGrantable = 18,
+    Paused = 19,
+    NotPauser = 20,
+}
 
 // ── Commission status ─────────────────────────────────────────────────────────
 
@@ -81,11 +113,24 @@ pub enum DataKey {
     XlmToken,
     NextCommissionId,
     Commission(u64),
+    SchemaVersion,
+    PendingAdmin,
+    RoleMember(Role, Address),
+    Paused(PauseFlag),
 }
 
 const ZERO_ADDR: &str = "GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF";
 const MAX_INTENTION_LEN: u32 = 500;
 const MAX_REF_IMAGE_LEN: u32 = 512;
+/// Storage layout version written by this code. Bump it together with a new
+/// step in `migrate` whenever a release changes how existing records are stored.
+const SCHEMA_VERSION: u32 = 1;
+/// Ledgers close roughly every 5 seconds.
+const DAY_IN_LEDGERS: u32 = 17_280;
+/// Entries touched by a call are topped back up to ~30 days of TTL once they
+/// drop under ~29 days, so anything in active use never nears archival.
+const TTL_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const TTL_THRESHOLD: u32 = TTL_BUMP_AMOUNT - DAY_IN_LEDGERS;
 
 // ── Contract ──────────────────────────────────────────────────────────────────
 
@@ -101,14 +146,209 @@ impl ShimejiCommission {
         admin: Address,
         usdc_token: Address,
         xlm_token: Address,
-    ) {
+    ) -> Result<(), CommissionError> {
         if env.storage().instance().has(&DataKey::Admin) {
-            panic!("already initialized");
+            return Err(CommissionError::AlreadyInitialized);
         }
         env.storage().instance().set(&DataKey::Admin, &admin);
         env.storage().instance().set(&DataKey::UsdcToken, &usdc_token);
         env.storage().instance().set(&DataKey::XlmToken, &xlm_token);
         env.storage().instance().set(&DataKey::NextCommissionId, &0u64);
+        env.storage().instance().set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(TTL_THRESHOLD, TTL_BUMP_AMOUNT);
+    }
+
+    /// Reads a persistent entry and, if it exists, tops its TTL back up.
+    fn read_entry<V: TryFromVal<Env, Val>>(env: &Env, key: &DataKey) -> Option<V> {
+        let value = env.storage().persistent().get(key);
+        if value.is_some() {
+            env.storage()
+                .persistent()
+                .extend_ttl(key, TTL_THRESHOLD, TTL_BUMP_AMOUNT);
+            Self::extend_instance(env);
+        }
+        value
+    }
+
+    /// Writes a persistent entry with a full TTL.
+    fn write_entry<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+        env.storage().persistent().set(key, value);
+        env.storage()
+            .persistent()
+            .extend_ttl(key, TTL_THRESHOLD, TTL_BUMP_AMOUNT);
+        Self::extend_instance(env);
+    }
+
+    /// Tops up `key`'s TTL if it exists; returns whether it did.
+    fn extend_entry(env: &Env, key: &DataKey) -> bool {
+        if !env.storage().persistent().has(key) {
+            return false;
+        }
+        env.storage()
+            .persistent()
+            .extend_ttl(key, TTL_THRESHOLD, TTL_BUMP_AMOUNT);
+        true
+    }
+
+    // ── Admin: upgrades ───────────────────────────────────────────────────────
+
+    /// Swaps this contract's code for an already-uploaded wasm. Storage is left
+    /// untouched; call `migrate` afterwards if the new code bumps the schema.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        env.deployer()
+            .update_current_contract_wasm(new_wasm_hash.clone());
+        env.events()
+            .publish((symbol_short!("upgraded"),), new_wasm_hash);
+    }
+
+    /// Storage layout version. Deployments from before versioning report 0.
+    pub fn schema_version(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::SchemaVersion)
+            .unwrap_or(0)
+    }
+
+    /// Brings stored data from the recorded schema version up to the one this
+    /// code expects. Safe to call repeatedly; returns the resulting version.
+    pub fn migrate(env: Env) -> Result<u32, CommissionError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        let from = Self::schema_version(env.clone());
+        if from > SCHEMA_VERSION {
+            return Err(CommissionError::UnsupportedSchemaVersion);
+        }
+        if from == SCHEMA_VERSION {
+            return Ok(from);
+        }
+        // v0 -> v1: layout unchanged, only the version stamp is new.
+        env.storage()
+            .instance()
+            .set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+        env.events()
+            .publish((symbol_short!("migrated"),), (from, SCHEMA_VERSION));
+        Ok(SCHEMA_VERSION)
+    }
+
+    /// First half of an admin handover: nothing changes until `new_admin`
+    /// calls `accept_admin`. A later proposal replaces an earlier one.
+    pub fn propose_admin(env: Env, new_admin: Address) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        env.storage().instance().set(&DataKey::PendingAdmin, &new_admin);
+        env.events()
+            .publish((symbol_short!("adm_prop"), admin), new_admin);
+    }
+
+    pub fn accept_admin(env: Env) -> Result<(), CommissionError> {
+        let new_admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::PendingAdmin)
+            .ok_or(CommissionError::NoPendingAdmin)?;
+        new_admin.require_auth();
+        let previous: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        env.storage().instance().set(&DataKey::Admin, &new_admin);
+        env.storage().instance().remove(&DataKey::PendingAdmin);
+        env.events()
+            .publish((symbol_short!("adm_xfer"), previous), new_admin);
+        Ok(())
+    }
+
+    pub fn pending_admin(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::PendingAdmin)
+    }
+
+    pub fn admin(env: Env) -> Address {
+        env.storage().instance().get(&DataKey::Admin).unwrap()
+    }
+
+    pub fn grant_role(env: Env, role: Role, account: Address) -> Result<(), CommissionError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if role == Role::Admin {
+            return Err(CommissionError::AdminRoleNotGrantable);
+        }
+        let key = DataKey::RoleMember(role, account.clone());
+        if !env.storage().instance().has(&key) {
+            env.storage().instance().set(&key, &true);
+            env.events()
+                .publish((symbol_short!("role_grnt"), account), role);
+        }
+        Ok(())
+    }
+
+    pub fn revoke_role(env: Env, role: Role, account: Address) -> Result<(), CommissionError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if role == Role::Admin {
+            return Err(CommissionError::AdminRoleNotGrantable);
+        }
+        let key = DataKey::RoleMember(role, account.clone());
+        if env.storage().instance().has(&key) {
+            env.storage().instance().remove(&key);
+            env.events()
+                .publish((symbol_short!("role_rvk"), account), role);
+        }
+        Ok(())
+    }
+
+    pub fn has_role(env: Env, role: Role, account: Address) -> bool {
+        match role {
+            Role::Admin => {
+                let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+                admin == account
+            }
+            _ => env
+                .storage()
+                .instance()
+                .has(&DataKey::RoleMember(role, account)),
+        }
+    }
+
+    /// Halts one class of operations until `unpause`. Admin or pausers only.
+    pub fn pause(env: Env, caller: Address, flag: PauseFlag) -> Result<(), CommissionError> {
+        Self::require_pauser(&env, &caller)?;
+        env.storage().instance().set(&DataKey::Paused(flag), &true);
+        env.events().publish((symbol_short!("paused"), caller), flag);
+        Ok(())
+    }
+
+    pub fn unpause(env: Env, caller: Address, flag: PauseFlag) -> Result<(), CommissionError> {
+        Self::require_pauser(&env, &caller)?;
+        env.storage().instance().remove(&DataKey::Paused(flag));
+        env.events().publish((symbol_short!("unpaused"), caller), flag);
+        Ok(())
+    }
+
+    pub fn is_paused(env: Env, flag: PauseFlag) -> bool {
+        env.storage().instance().has(&DataKey::Paused(flag))
+    }
+
+    fn require_pauser(env: &Env, caller: &Address) -> Result<(), CommissionError> {
+        caller.require_auth();
+        if !Self::has_role(env.clone(), Role::Admin, caller.clone())
+            && !Self::has_role(env.clone(), Role::Pauser, caller.clone())
+        {
+            return Err(CommissionError::NotPauser);
+        }
+        Ok(())
+    }
+
+    fn require_not_paused(env: &Env, flag: PauseFlag) -> Result<(), CommissionError> {
+        if env.storage().instance().has(&DataKey::Paused(flag)) {
+            return Err(CommissionError::Paused);
+        }
+        Ok(())
     }
 
     // ── Buyer: post a commission request ──────────────────────────────────────
@@ -116,6 +356,7 @@ impl ShimejiCommission {
     /// Buyer posts a commission. Payment is escrowed in this contract until
     /// the commission is completed or cancelled. The `reference_image` field
     /// can be an IPFS URI or an empty string if no reference is provided.
+    #[allow(clippy::too_many_arguments)] // deployed entry point, the signature is fixed
     pub fn create_commission(
         env: Env,
         buyer: Address,
@@ -125,26 +366,27 @@ impl ShimejiCommission {
         price_usdc: i128,
         xlm_usdc_rate: i128,
         currency: Currency,
-    ) -> u64 {
+    ) -> Result<u64, CommissionError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
         buyer.require_auth();
 
         if intention.is_empty() {
-            panic!("intention cannot be empty");
+            return Err(CommissionError::EmptyIntention);
         }
         if intention.len() > MAX_INTENTION_LEN {
-            panic!("intention too long");
+            return Err(CommissionError::IntentionTooLong);
         }
         if reference_image.len() > MAX_REF_IMAGE_LEN {
-            panic!("reference_image url too long");
+            return Err(CommissionError::ReferenceImageTooLong);
         }
         if price_xlm <= 0 {
-            panic!("price_xlm must be positive");
+            return Err(CommissionError::InvalidPrice);
         }
         if price_usdc <= 0 {
-            panic!("price_usdc must be positive");
+            return Err(CommissionError::InvalidPrice);
         }
         if xlm_usdc_rate <= 0 {
-            panic!("xlm_usdc_rate must be positive");
+            return Err(CommissionError::InvalidRate);
         }
 
         // Escrow buyer payment upfront
@@ -184,14 +426,16 @@ impl ShimejiCommission {
             created_at: env.ledger().timestamp(),
         };
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::Commission(commission_id), &commission);
+        Self::write_entry(&env, &DataKey::Commission(commission_id), &commission);
+        env.events().publish(
+            (symbol_short!("status"), commission_id),
+            CommissionStatus::Open,
+        );
         env.storage()
             .instance()
             .set(&DataKey::NextCommissionId, &(commission_id + 1));
 
-        commission_id
+        Ok(commission_id)
     }
 
     // ── Admin: assign an artist ────────────────────────────────────────────────
@@ -205,27 +449,28 @@ impl ShimejiCommission {
         commission_id: u64,
         artist: Address,
         token_id: u64,
-    ) {
+    ) -> Result<(), CommissionError> {
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         admin.require_auth();
 
-        let mut commission: CommissionRequest = env
-            .storage()
-            .persistent()
-            .get(&DataKey::Commission(commission_id))
-            .unwrap_or_else(|| panic!("commission does not exist"));
+        let mut commission: CommissionRequest =
+            Self::read_entry(&env, &DataKey::Commission(commission_id))
+                .ok_or(CommissionError::CommissionNotFound)?;
 
         if commission.status != CommissionStatus::Open {
-            panic!("commission is not open");
+            return Err(CommissionError::NotOpen);
         }
 
         commission.status = CommissionStatus::Accepted;
         commission.artist = artist;
         commission.token_id = token_id;
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::Commission(commission_id), &commission);
+        Self::write_entry(&env, &DataKey::Commission(commission_id), &commission);
+        env.events().publish(
+            (symbol_short!("status"), commission_id),
+            CommissionStatus::Accepted,
+        );
+        Ok(())
     }
 
     // ── Artist: mark as delivered ──────────────────────────────────────────────
@@ -233,46 +478,54 @@ impl ShimejiCommission {
     /// The artist flags the commission as delivered after updating the egg NFT
     /// metadata on the NFT contract via `update_token_uri_as_creator`. Status
     /// → Delivered.
-    pub fn mark_delivered(env: Env, artist: Address, commission_id: u64) {
+    pub fn mark_delivered(
+        env: Env,
+        artist: Address,
+        commission_id: u64,
+    ) -> Result<(), CommissionError> {
         artist.require_auth();
 
-        let mut commission: CommissionRequest = env
-            .storage()
-            .persistent()
-            .get(&DataKey::Commission(commission_id))
-            .unwrap_or_else(|| panic!("commission does not exist"));
+        let mut commission: CommissionRequest =
+            Self::read_entry(&env, &DataKey::Commission(commission_id))
+                .ok_or(CommissionError::CommissionNotFound)?;
 
         if commission.status != CommissionStatus::Accepted {
-            panic!("commission is not in accepted state");
+            return Err(CommissionError::NotAccepted);
         }
         if commission.artist != artist {
-            panic!("only the assigned artist can mark this commission as delivered");
+            return Err(CommissionError::NotAssignedArtist);
         }
 
         commission.status = CommissionStatus::Delivered;
-        env.storage()
-            .persistent()
-            .set(&DataKey::Commission(commission_id), &commission);
+        Self::write_entry(&env, &DataKey::Commission(commission_id), &commission);
+        env.events().publish(
+            (symbol_short!("status"), commission_id),
+            CommissionStatus::Delivered,
+        );
+        Ok(())
     }
 
     // ── Buyer: approve delivery ───────────────────────────────────────────────
 
     /// Buyer approves the delivered art and releases escrowed payment to the
     /// artist. Status → Completed.
-    pub fn approve_delivery(env: Env, buyer: Address, commission_id: u64) {
+    pub fn approve_delivery(
+        env: Env,
+        buyer: Address,
+        commission_id: u64,
+    ) -> Result<(), CommissionError> {
+        Self::require_not_paused(&env, PauseFlag::Settlements)?;
         buyer.require_auth();
 
-        let mut commission: CommissionRequest = env
-            .storage()
-            .persistent()
-            .get(&DataKey::Commission(commission_id))
-            .unwrap_or_else(|| panic!("commission does not exist"));
+        let mut commission: CommissionRequest =
+            Self::read_entry(&env, &DataKey::Commission(commission_id))
+                .ok_or(CommissionError::CommissionNotFound)?;
 
         if commission.status != CommissionStatus::Delivered {
-            panic!("commission has not been marked as delivered yet");
+            return Err(CommissionError::NotDelivered);
         }
         if commission.buyer != buyer {
-            panic!("only the buyer can approve delivery");
+            return Err(CommissionError::NotBuyer);
         }
 
         // Release escrowed payment to artist
@@ -298,39 +551,46 @@ impl ShimejiCommission {
         }
 
         commission.status = CommissionStatus::Completed;
-        env.storage()
-            .persistent()
-            .set(&DataKey::Commission(commission_id), &commission);
+        Self::write_entry(&env, &DataKey::Commission(commission_id), &commission);
+        env.events().publish(
+            (symbol_short!("status"), commission_id),
+            CommissionStatus::Completed,
+        );
+        Ok(())
     }
 
     // ── Cancel commission ─────────────────────────────────────────────────────
 
     /// Cancel an Open commission (buyer or admin) or an Accepted commission
-    /// (admin only for dispute resolution). Payment is refunded to the buyer.
-    pub fn cancel_commission(env: Env, caller: Address, commission_id: u64) {
+    /// (admin only for dispute resolution). Arbiters may act wherever the admin
+    /// can. Payment is refunded to the buyer.
+    pub fn cancel_commission(
+        env: Env,
+        caller: Address,
+        commission_id: u64,
+    ) -> Result<(), CommissionError> {
         caller.require_auth();
 
-        let mut commission: CommissionRequest = env
-            .storage()
-            .persistent()
-            .get(&DataKey::Commission(commission_id))
-            .unwrap_or_else(|| panic!("commission does not exist"));
+        let mut commission: CommissionRequest =
+            Self::read_entry(&env, &DataKey::Commission(commission_id))
+                .ok_or(CommissionError::CommissionNotFound)?;
 
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        let arbitrates = Self::has_role(env.clone(), Role::Admin, caller.clone())
+            || Self::has_role(env.clone(), Role::Arbiter, caller.clone());
 
         match commission.status {
             CommissionStatus::Open => {
-                if commission.buyer != caller && admin != caller {
-                    panic!("only the buyer or admin can cancel an open commission");
+                if commission.buyer != caller && !arbitrates {
+                    return Err(CommissionError::NotBuyerOrAdmin);
                 }
             }
             CommissionStatus::Accepted => {
-                if admin != caller {
-                    panic!("only admin can cancel an accepted commission (dispute)");
+                if !arbitrates {
+                    return Err(CommissionError::NotAdmin);
                 }
             }
             _ => {
-                panic!("commission cannot be cancelled in its current state");
+                return Err(CommissionError::NotCancellable);
             }
         }
 
@@ -357,18 +617,32 @@ impl ShimejiCommission {
         }
 
         commission.status = CommissionStatus::Cancelled;
-        env.storage()
-            .persistent()
-            .set(&DataKey::Commission(commission_id), &commission);
+        Self::write_entry(&env, &DataKey::Commission(commission_id), &commission);
+        env.events().publish(
+            (symbol_short!("status"), commission_id),
+            CommissionStatus::Cancelled,
+        );
+        Ok(())
     }
 
     // ── Queries ───────────────────────────────────────────────────────────────
 
-    pub fn get_commission(env: Env, commission_id: u64) -> CommissionRequest {
-        env.storage()
-            .persistent()
-            .get(&DataKey::Commission(commission_id))
-            .unwrap_or_else(|| panic!("commission does not exist"))
+    pub fn get_commission(
+        env: Env,
+        commission_id: u64,
+    ) -> Result<CommissionRequest, CommissionError> {
+        Self::read_entry(&env, &DataKey::Commission(commission_id))
+            .ok_or(CommissionError::CommissionNotFound)
+    }
+
+    /// Permissionless keep-alive for a commission record and the contract
+    /// instance. Anyone may call it; it only extends TTLs.
+    pub fn bump_commission(env: Env, commission_id: u64) -> Result<(), CommissionError> {
+        if !Self::extend_entry(&env, &DataKey::Commission(commission_id)) {
+            return Err(CommissionError::CommissionNotFound);
+        }
+        Self::extend_instance(&env);
+        Ok(())
     }
 
     pub fn total_commissions(env: Env) -> u64 {
@@ -385,9 +659,9 @@ impl ShimejiCommission {
 mod test {
     use super::*;
     use soroban_sdk::{
-        testutils::Address as _,
+        testutils::{Address as _, Events},
         token::{StellarAssetClient, TokenClient},
-        Env, String,
+        Env, IntoVal, String,
     };
 
     struct TestEnv {
@@ -397,7 +671,6 @@ mod test {
         usdc: TokenClient<'static>,
         xlm_sac: StellarAssetClient<'static>,
         usdc_sac: StellarAssetClient<'static>,
-        admin: Address,
     }
 
     fn setup() -> TestEnv {
@@ -419,7 +692,15 @@ mod test {
         let commission = ShimejiCommissionClient::new(&env, &commission_addr);
         commission.initialize(&admin, &usdc_addr, &xlm_addr);
 
-        TestEnv { env, commission, xlm, usdc, xlm_sac, usdc_sac, admin }
+        TestEnv { env, commission, xlm, usdc, xlm_sac, usdc_sac }
+    }
+
+    /// Status carried by the last `status` event this contract emitted for `commission_id`.
+    fn last_status_event(t: &TestEnv, commission_id: u64) -> CommissionStatus {
+        let (emitter, topics, data) = t.env.events().all().last().unwrap();
+        assert_eq!(emitter, t.commission.address);
+        assert_eq!(topics, (symbol_short!("status"), commission_id).into_val(&t.env));
+        data.into_val(&t.env)
     }
 
     #[test]
@@ -524,7 +805,6 @@ mod test {
     }
 
     #[test]
-    #[should_panic(expected = "commission is not open")]
     fn test_cannot_reassign_accepted_commission() {
         let t = setup();
         let buyer = Address::generate(&t.env);
@@ -547,6 +827,69 @@ mod test {
         );
 
         t.commission.assign_artist(&commission_id, &artist1, &0u64);
-        t.commission.assign_artist(&commission_id, &artist2, &1u64);
+        assert_eq!(
+            t.commission.try_assign_artist(&commission_id, &artist2, &1u64),
+            Err(Ok(CommissionError::NotOpen))
+        );
+    }
+
+    #[test]
+    fn test_status_transitions_emit_events() {
+        let t = setup();
+        let buyer = Address::generate(&t.env);
+        let artist = Address::generate(&t.env);
+        t.usdc_sac.mint(&buyer, &50_0000000i128);
+
+        let commission_id = t.commission.create_commission(
+            &buyer,
+            &String::from_str(&t.env, "A sleepy shimeji"),
+            &String::from_str(&t.env, ""),
+            &100_0000000i128,
+            &10_0000000i128,
+            &1000000i128,
+            &Currency::Usdc,
+        );
+        assert_eq!(last_status_event(&t, commission_id), CommissionStatus::Open);
+
+        t.commission.assign_artist(&commission_id, &artist, &7u64);
+        assert_eq!(last_status_event(&t, commission_id), CommissionStatus::Accepted);
+
+        t.commission.mark_delivered(&artist, &commission_id);
+        assert_eq!(last_status_event(&t, commission_id), CommissionStatus::Delivered);
+
+        t.commission.approve_delivery(&buyer, &commission_id);
+        assert_eq!(last_status_event(&t, commission_id), CommissionStatus::Completed);
+    }
+
+    #[test]
+    fn test_arbiter_can_cancel_accepted_commission() {
+        let t = setup();
+        let buyer = Address::generate(&t.env);
+        let artist = Address::generate(&t.env);
+        let arbiter = Address::generate(&t.env);
+
+        t.xlm_sac.mint(&buyer, &100_0000000i128);
+        let commission_id = t.commission.create_commission(
+            &buyer,
+            &String::from_str(&t.env, "Disputed shimeji"),
+            &String::from_str(&t.env, ""),
+            &100_0000000i128,
+            &10_0000000i128,
+            &1000000i128,
+            &Currency::Xlm,
+        );
+        t.commission.assign_artist(&commission_id, &artist, &7u64);
+
+        assert_eq!(
+            t.commission.try_cancel_commission(&arbiter, &commission_id),
+            Err(Ok(CommissionError::NotAdmin))
+        );
+        t.commission.grant_role(&Role::Arbiter, &arbiter);
+        assert!(t.commission.has_role(&Role::Arbiter, &arbiter));
+        t.commission.cancel_commission(&arbiter, &commission_id);
+
+        assert_eq!(t.xlm.balance(&buyer), 100_0000000i128);
+        let c = t.commission.get_commission(&commission_id);
+        assert_eq!(c.status, CommissionStatus::Cancelled);
     }
 }
@@ -0,0 +1,34 @@
+#![no_std]
+
+//! Contract types shared by every Shimeji contract, so a role or pause flag
+//! means the same thing (and encodes the same way) wherever it is passed.
+
+use soroban_sdk::contracttype;
+
+/// Access roles shared by every Shimeji contract. `Admin` is the single address
+/// moved with `propose_admin`/`accept_admin`; every other role is granted per address.
+/// `Operator` is the escrow vault's payout caller (normally the marketplace).
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Role {
+    Admin,
+    Pauser,
+    Minter,
+    MetadataUpdater,
+    Arbiter,
+    Treasurer,
+    Operator,
+}
+
+/// Independent circuit breakers. Refunds and cancellations are never paused,
+/// so funds already in escrow can always go back to whoever put them there.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PauseFlag {
+    /// New auctions, listings and requests, and bids or purchases against them.
+    Trading,
+    /// Releasing escrowed funds to the winning side of a finished deal.
+    Settlements,
+    /// Discretionary payouts out of the contract's custody.
+    Withdrawals,
+}
@@ -1,11 +1,46 @@
 #![no_std]
 
-use soroban_sdk::{contract, contractimpl, contracttype, token, Address, Env};
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, token, Address, BytesN,
+    Env, Symbol,
+};
+pub use shimeji_common::{PauseFlag, Role};
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum VaultError {
+    AlreadyInitialized = 1,
+    InvalidAmount = 2,
+    /// No longer returned: the operator is now a `Role::Operator` grant.
+    OperatorNotConfigured = 3,
+    Unauthorized = 4,
+    UnsupportedSchemaVersion = 5,
+    NoPendingAdmin = 6,
+    AdminRoleNotGrantable = 7,
+    Paused = 8,
+    NotPauser = 9,
+}
+
+/// Storage layout version written by this code. Bump it together with a new
+/// step in `migrate` whenever a release changes how existing records are stored.
+const SCHEMA_VERSION: u32 = 2;
+/// Ledgers close roughly every 5 seconds.
+const DAY_IN_LEDGERS: u32 = 17_280;
+/// Entries touched by a call are topped back up to ~30 days of TTL once they
+/// drop under ~29 days, so anything in active use never nears archival.
+const TTL_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const TTL_THRESHOLD: u32 = TTL_BUMP_AMOUNT - DAY_IN_LEDGERS;
 
 #[contracttype]
 enum DataKey {
     Admin,
+    /// Schema v1 single-slot operator; `migrate` folds it into `RoleMember`.
     Operator,
+    SchemaVersion,
+    PendingAdmin,
+    RoleMember(Role, Address),
+    Paused(PauseFlag),
 }
 
 #[contract]
@@ -19,46 +54,233 @@ impl ShimejiEscrowVault {
         admin
     }
 
-    pub fn initialize(env: Env, admin: Address) {
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(TTL_THRESHOLD, TTL_BUMP_AMOUNT);
+    }
+
+    pub fn initialize(env: Env, admin: Address) -> Result<(), VaultError> {
         if env.storage().instance().has(&DataKey::Admin) {
-            panic!("already initialized");
+            return Err(VaultError::AlreadyInitialized);
         }
         env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+        Self::extend_instance(&env);
+        Ok(())
     }
 
-    pub fn admin(env: Env) -> Address {
-        env.storage().instance().get(&DataKey::Admin).unwrap()
+    /// Swaps this contract's code for an already-uploaded wasm. Storage is left
+    /// untouched; call `migrate` afterwards if the new code bumps the schema.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) {
+        Self::require_admin(&env);
+        env.deployer()
+            .update_current_contract_wasm(new_wasm_hash.clone());
+        env.events()
+            .publish((symbol_short!("upgraded"),), new_wasm_hash);
     }
 
-    pub fn set_operator(env: Env, operator: Address) {
-        Self::require_admin(&env);
-        env.storage().instance().set(&DataKey::Operator, &operator);
+    /// Storage layout version. Deployments from before versioning report 0.
+    pub fn schema_version(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::SchemaVersion)
+            .unwrap_or(0)
     }
 
-    pub fn operator(env: Env) -> Address {
+    /// Brings stored data from the recorded schema version up to the one this
+    /// code expects. Safe to call repeatedly; returns the resulting version.
+    pub fn migrate(env: Env) -> Result<u32, VaultError> {
+        Self::require_admin(&env);
+        let from = Self::schema_version(env.clone());
+        if from > SCHEMA_VERSION {
+            return Err(VaultError::UnsupportedSchemaVersion);
+        }
+        if from == SCHEMA_VERSION {
+            return Ok(from);
+        }
+        // v0 -> v1: layout unchanged, only the version stamp is new.
+        // v1 -> v2: the single-slot operator becomes a role grant.
+        let legacy_operator: Option<Address> = env.storage().instance().get(&DataKey::Operator);
+        if let Some(operator) = legacy_operator {
+            env.storage()
+                .instance()
+                .set(&DataKey::RoleMember(Role::Operator, operator), &true);
+            env.storage().instance().remove(&DataKey::Operator);
+        }
         env.storage()
             .instance()
-            .get(&DataKey::Operator)
-            .unwrap_or_else(|| panic!("operator not configured"))
+            .set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+        env.events()
+            .publish((symbol_short!("migrated"),), (from, SCHEMA_VERSION));
+        Ok(SCHEMA_VERSION)
+    }
+
+    /// First half of an admin handover: nothing changes until `new_admin`
+    /// calls `accept_admin`. A later proposal replaces an earlier one.
+    pub fn propose_admin(env: Env, new_admin: Address) {
+        let admin = Self::require_admin(&env);
+        env.storage().instance().set(&DataKey::PendingAdmin, &new_admin);
+        env.events()
+            .publish((symbol_short!("adm_prop"), admin), new_admin);
+    }
+
+    pub fn accept_admin(env: Env) -> Result<(), VaultError> {
+        let new_admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::PendingAdmin)
+            .ok_or(VaultError::NoPendingAdmin)?;
+        new_admin.require_auth();
+        let previous: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        env.storage().instance().set(&DataKey::Admin, &new_admin);
+        env.storage().instance().remove(&DataKey::PendingAdmin);
+        env.events()
+            .publish((symbol_short!("adm_xfer"), previous), new_admin);
+        Ok(())
+    }
+
+    pub fn pending_admin(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::PendingAdmin)
+    }
+
+    pub fn grant_role(env: Env, role: Role, account: Address) -> Result<(), VaultError> {
+        Self::require_admin(&env);
+        if role == Role::Admin {
+            return Err(VaultError::AdminRoleNotGrantable);
+        }
+        let key = DataKey::RoleMember(role, account.clone());
+        if !env.storage().instance().has(&key) {
+            env.storage().instance().set(&key, &true);
+            env.events()
+                .publish((symbol_short!("role_grnt"), account), role);
+        }
+        Ok(())
+    }
+
+    pub fn revoke_role(env: Env, role: Role, account: Address) -> Result<(), VaultError> {
+        Self::require_admin(&env);
+        if role == Role::Admin {
+            return Err(VaultError::AdminRoleNotGrantable);
+        }
+        let key = DataKey::RoleMember(role, account.clone());
+        if env.storage().instance().has(&key) {
+            env.storage().instance().remove(&key);
+            env.events()
+                .publish((symbol_short!("role_rvk"), account), role);
+        }
+        Ok(())
+    }
+
+    pub fn has_role(env: Env, role: Role, account: Address) -> bool {
+        match role {
+            Role::Admin => {
+                let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+                admin == account
+            }
+            _ => env
+                .storage()
+                .instance()
+                .has(&DataKey::RoleMember(role, account)),
+        }
+    }
+
+    /// Halts one class of operations until `unpause`. Admin or pausers only.
+    pub fn pause(env: Env, caller: Address, flag: PauseFlag) -> Result<(), VaultError> {
+        Self::require_pauser(&env, &caller)?;
+        env.storage().instance().set(&DataKey::Paused(flag), &true);
+        env.events().publish((symbol_short!("paused"), caller), flag);
+        Ok(())
+    }
+
+    pub fn unpause(env: Env, caller: Address, flag: PauseFlag) -> Result<(), VaultError> {
+        Self::require_pauser(&env, &caller)?;
+        env.storage().instance().remove(&DataKey::Paused(flag));
+        env.events().publish((symbol_short!("unpaused"), caller), flag);
+        Ok(())
+    }
+
+    pub fn is_paused(env: Env, flag: PauseFlag) -> bool {
+        env.storage().instance().has(&DataKey::Paused(flag))
+    }
+
+    fn require_pauser(env: &Env, caller: &Address) -> Result<(), VaultError> {
+        caller.require_auth();
+        if !Self::has_role(env.clone(), Role::Admin, caller.clone())
+            && !Self::has_role(env.clone(), Role::Pauser, caller.clone())
+        {
+            return Err(VaultError::NotPauser);
+        }
+        Ok(())
+    }
+
+    fn require_not_paused(env: &Env, flag: PauseFlag) -> Result<(), VaultError> {
+        if env.storage().instance().has(&DataKey::Paused(flag)) {
+            return Err(VaultError::Paused);
+        }
+        Ok(())
+    }
+
+    /// Permissionless keep-alive for the vault's configuration. Anyone may call
+    /// it; it only extends the instance TTL.
+    pub fn bump(env: Env) {
+        Self::extend_instance(&env);
+    }
+
+    pub fn admin(env: Env) -> Address {
+        env.storage().instance().get(&DataKey::Admin).unwrap()
+    }
+
+    /// Sends vault funds to `to`. Callable by operators, treasurers and the admin.
+    pub fn payout_token(
+        env: Env,
+        caller: Address,
+        token_id: Address,
+        to: Address,
+        amount: i128,
+    ) -> Result<(), VaultError> {
+        Self::require_not_paused(&env, PauseFlag::Withdrawals)?;
+        Self::transfer_out(&env, caller, token_id, to, amount, symbol_short!("payout"))
     }
 
-    pub fn payout_token(env: Env, caller: Address, token_id: Address, to: Address, amount: i128) {
+    /// Sends escrow back to the buyer who funded it. The vault does not know
+    /// who funded what, so this has the same permissions and the same
+    /// `Withdrawals` pause as `payout_token`; only the `refund` event differs.
+    pub fn refund_token(
+        env: Env,
+        caller: Address,
+        token_id: Address,
+        to: Address,
+        amount: i128,
+    ) -> Result<(), VaultError> {
+        Self::require_not_paused(&env, PauseFlag::Withdrawals)?;
+        Self::transfer_out(&env, caller, token_id, to, amount, symbol_short!("refund"))
+    }
+
+    fn transfer_out(
+        env: &Env,
+        caller: Address,
+        token_id: Address,
+        to: Address,
+        amount: i128,
+        topic: Symbol,
+    ) -> Result<(), VaultError> {
         if amount <= 0 {
-            panic!("amount must be positive");
+            return Err(VaultError::InvalidAmount);
         }
 
         caller.require_auth();
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
-        let operator: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::Operator)
-            .unwrap_or_else(|| panic!("operator not configured"));
-        if caller != admin && caller != operator {
-            panic!("unauthorized payout caller");
+        if !Self::has_role(env.clone(), Role::Operator, caller.clone())
+            && !Self::has_role(env.clone(), Role::Admin, caller.clone())
+            && !Self::has_role(env.clone(), Role::Treasurer, caller.clone())
+        {
+            return Err(VaultError::Unauthorized);
         }
 
-        token::Client::new(&env, &token_id).transfer(&env.current_contract_address(), &to, &amount);
+        Self::extend_instance(env);
+        token::Client::new(env, &token_id).transfer(&env.current_contract_address(), &to, &amount);
+        env.events().publish((topic, token_id, to), (caller, amount));
+        Ok(())
     }
 }
 
@@ -66,8 +288,9 @@ impl ShimejiEscrowVault {
 mod test {
     use super::*;
     use soroban_sdk::{
-        testutils::Address as _,
+        testutils::{Address as _, Events},
         token::{StellarAssetClient, TokenClient},
+        IntoVal,
     };
 
     #[test]
@@ -82,15 +305,17 @@ mod test {
     }
 
     #[test]
-    #[should_panic(expected = "already initialized")]
-    fn test_double_initialize_panics() {
+    fn test_double_initialize_is_rejected() {
         let env = Env::default();
         let admin = Address::generate(&env);
         let contract_id = env.register(ShimejiEscrowVault, ());
         let client = ShimejiEscrowVaultClient::new(&env, &contract_id);
 
         client.initialize(&admin);
-        client.initialize(&admin);
+        assert_eq!(
+            client.try_initialize(&admin),
+            Err(Ok(VaultError::AlreadyInitialized))
+        );
     }
 
     #[test]
@@ -109,12 +334,128 @@ mod test {
         let contract_id = env.register(ShimejiEscrowVault, ());
         let client = ShimejiEscrowVaultClient::new(&env, &contract_id);
         client.initialize(&admin);
-        client.set_operator(&operator);
+        client.grant_role(&Role::Operator, &operator);
 
         xlm_sac.mint(&contract_id, &500_0000000i128);
         client.payout_token(&operator, &xlm_addr, &recipient, &200_0000000i128);
 
+        let events = env.events().all();
+        let (emitter, topics, data) = events.last().unwrap();
+        assert_eq!(emitter, contract_id);
+        assert_eq!(
+            topics,
+            (symbol_short!("payout"), xlm_addr.clone(), recipient.clone()).into_val(&env)
+        );
+        let (payer, paid): (Address, i128) = data.into_val(&env);
+        assert_eq!((payer, paid), (operator.clone(), 200_0000000i128));
+
         assert_eq!(xlm.balance(&contract_id), 300_0000000i128);
         assert_eq!(xlm.balance(&recipient), 200_0000000i128);
+
+        client.revoke_role(&Role::Operator, &operator);
+        assert_eq!(
+            client.try_payout_token(&operator, &xlm_addr, &recipient, &1i128),
+            Err(Ok(VaultError::Unauthorized))
+        );
+    }
+
+    #[test]
+    fn test_migrate_folds_legacy_operator_into_role() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let admin = Address::generate(&env);
+        let operator = Address::generate(&env);
+        let contract_id = env.register(ShimejiEscrowVault, ());
+        let client = ShimejiEscrowVaultClient::new(&env, &contract_id);
+        client.initialize(&admin);
+        env.as_contract(&contract_id, || {
+            env.storage().instance().set(&DataKey::Operator, &operator);
+            env.storage().instance().set(&DataKey::SchemaVersion, &1u32);
+        });
+        assert!(!client.has_role(&Role::Operator, &operator));
+
+        assert_eq!(client.migrate(), SCHEMA_VERSION);
+        assert!(client.has_role(&Role::Operator, &operator));
+        env.as_contract(&contract_id, || {
+            assert!(!env.storage().instance().has(&DataKey::Operator));
+        });
+    }
+
+    #[test]
+    fn test_admin_handover_and_treasurer_payouts() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let admin = Address::generate(&env);
+        let new_admin = Address::generate(&env);
+        let treasurer = Address::generate(&env);
+        let stranger = Address::generate(&env);
+
+        let xlm_issuer = Address::generate(&env);
+        let xlm_addr = env.register_stellar_asset_contract_v2(xlm_issuer).address();
+        let xlm_sac = StellarAssetClient::new(&env, &xlm_addr);
+
+        let contract_id = env.register(ShimejiEscrowVault, ());
+        let client = ShimejiEscrowVaultClient::new(&env, &contract_id);
+        client.initialize(&admin);
+        xlm_sac.mint(&contract_id, &500_0000000i128);
+
+        client.propose_admin(&new_admin);
+        assert_eq!(client.admin(), admin);
+        client.accept_admin();
+        assert_eq!(client.admin(), new_admin);
+        assert_eq!(client.pending_admin(), None);
+
+        assert_eq!(
+            client.try_payout_token(&stranger, &xlm_addr, &stranger, &1i128),
+            Err(Ok(VaultError::Unauthorized))
+        );
+        client.grant_role(&Role::Treasurer, &treasurer);
+        client.payout_token(&treasurer, &xlm_addr, &stranger, &100_0000000i128);
+        client.revoke_role(&Role::Treasurer, &treasurer);
+        assert_eq!(
+            client.try_payout_token(&treasurer, &xlm_addr, &stranger, &1i128),
+            Err(Ok(VaultError::Unauthorized))
+        );
+    }
+
+    #[test]
+    fn test_paused_withdrawals_block_payouts_and_refunds() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let admin = Address::generate(&env);
+        let operator = Address::generate(&env);
+        let buyer = Address::generate(&env);
+        let third_party = Address::generate(&env);
+
+        let xlm_issuer = Address::generate(&env);
+        let xlm_addr = env.register_stellar_asset_contract_v2(xlm_issuer).address();
+        let xlm = TokenClient::new(&env, &xlm_addr);
+        let xlm_sac = StellarAssetClient::new(&env, &xlm_addr);
+
+        let contract_id = env.register(ShimejiEscrowVault, ());
+        let client = ShimejiEscrowVaultClient::new(&env, &contract_id);
+        client.initialize(&admin);
+        client.grant_role(&Role::Operator, &operator);
+        xlm_sac.mint(&contract_id, &500_0000000i128);
+
+        client.pause(&admin, &PauseFlag::Withdrawals);
+        assert_eq!(
+            client.try_payout_token(&operator, &xlm_addr, &buyer, &100_0000000i128),
+            Err(Ok(VaultError::Paused))
+        );
+        for caller in [&operator, &admin] {
+            assert_eq!(
+                client.try_refund_token(caller, &xlm_addr, &third_party, &100_0000000i128),
+                Err(Ok(VaultError::Paused))
+            );
+        }
+        assert_eq!(xlm.balance(&third_party), 0);
+        assert_eq!(xlm.balance(&contract_id), 500_0000000i128);
+
+        client.unpause(&admin, &PauseFlag::Withdrawals);
+        client.refund_token(&operator, &xlm_addr, &buyer, &100_0000000i128);
+        client.payout_token(&operator, &xlm_addr, &buyer, &100_0000000i128);
+        assert_eq!(xlm.balance(&buyer), 200_0000000i128);
+        assert_eq!(xlm.balance(&contract_id), 300_0000000i128);
     }
 }
@@ -1,8 +1,10 @@
 #![no_std]
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, token, Address, Env, IntoVal, String, Symbol,
+    contract, contracterror, contractimpl, contracttype, symbol_short, token, Address, BytesN, Env,
+    IntoVal, Map, String, Symbol, TryFromVal, Val, Vec,
 };
+pub use shimeji_common::{PauseFlag, Role};
 
 const MAX_SWAP_INTENTION_LEN: u32 = 280;
 const MAX_COMMISSION_INTENTION_LEN: u32 = 500;
@@ -10,10 +12,85 @@ const MAX_REFERENCE_IMAGE_URL_LEN: u32 = 512;
 const MAX_COMMISSION_TURNAROUND_DAYS: u64 = 365;
 const MAX_COMMISSION_REVISION_REQUESTS: u64 = 3;
 const COMMISSION_AUTO_RELEASE_AFTER_DELIVERY_SECS: u64 = 7 * 24 * 60 * 60;
+const DEFAULT_ORACLE_MAX_AGE: u64 = 900; // Reflector publishes every 5 minutes
+const MAX_FEE_BPS: u32 = 1_000; // platform fee is capped at 10%
+const MAX_OPEN_OFFERS: u32 = 50; // per token and per buyer, keeps the indexes cheap to scan
+const MAX_COLLECTION_OFFER_QUANTITY: u32 = 100;
+const MAX_BUNDLE_SIZE: u32 = 10;
 
 /// Shared scale factor used for token amounts and oracle rate (10^7).
 const TOKEN_SCALE: i128 = 10_000_000;
 
+/// Storage layout version written by this code. Bump it together with a new
+/// step in `migrate` whenever a release changes how existing records are stored.
+const SCHEMA_VERSION: u32 = 2;
+
+/// Ledgers close roughly every 5 seconds.
+const DAY_IN_LEDGERS: u32 = 17_280;
+/// Entries touched by a call are topped back up to ~30 days of TTL once they
+/// drop under ~29 days, so anything in active use never nears archival.
+const TTL_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const TTL_THRESHOLD: u32 = TTL_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Contract specs allow at most 50 error variants and this enum is full: the
+/// last few are generic so several features share them, and new failure modes
+/// must reuse an existing variant.
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum MarketplaceError {
+    AlreadyInitialized = 1,
+    ListingNotFound = 2,
+    ListingNotActive = 3,
+    NotCommissionListing = 4,
+    CommissionListing = 5,
+    NotSeller = 6,
+    NotBuyer = 7,
+    Unauthorized = 8,
+    InvalidPrice = 9,
+    OracleNotConfigured = 10,
+    InvalidOracleRate = 11,
+    EscrowDestinationNotConfigured = 12,
+    EmptyIntention = 13,
+    IntentionTooLong = 14,
+    ReferenceImageTooLong = 15,
+    InvalidCommissionEta = 16,
+    CommissionEggAlreadyListed = 17,
+    CommissionOrderPending = 18,
+    OrderNotFound = 19,
+    OrderNotAccepted = 20,
+    OrderNotDelivered = 21,
+    OrderNotRefundable = 22,
+    CommissionTimeoutNotReached = 23,
+    RevisionLimitReached = 24,
+    MetadataNotUpdated = 25,
+    SwapListingNotFound = 26,
+    SwapListingNotActive = 27,
+    SwapBidNotFound = 28,
+    SwapBidNotActive = 29,
+    SwapBidListingMismatch = 30,
+    SwapSameToken = 31,
+    SwapOwnListing = 32,
+    NotSwapCreator = 33,
+    NotSwapBidder = 34,
+    UnsupportedSchemaVersion = 35,
+    NoPendingAdmin = 36,
+    AdminRoleNotGrantable = 37,
+    Paused = 38,
+    ListingNotExpired = 39,
+    StaleOraclePrice = 40,
+    OraclePriceOutOfBounds = 41,
+    InvalidOracleGuard = 42,
+    InvalidFee = 43,
+    InvalidBundleSize = 44,
+    NoFeesAccrued = 45,
+    OfferNotFound = 46,
+    OfferNotActive = 47,
+    Expired = 48,
+    LimitReached = 49,
+    TokenNotEligible = 50,
+}
+
 #[contracttype]
 #[derive(Clone, Debug, PartialEq)]
 pub enum Currency {
@@ -37,6 +114,14 @@ pub enum CommissionOrderStatus {
     Refunded,
 }
 
+/// Mirrors `TokenKind` in `shimeji-nft`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenKind {
+    Finished,
+    CommissionEgg,
+}
+
 /// Oracle asset descriptor (Reflector protocol).
 #[contracttype]
 #[derive(Clone)]
@@ -53,6 +138,31 @@ pub struct OraclePriceData {
     pub timestamp: u64,
 }
 
+/// Sanity checks applied to every Reflector price before it is used.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct OracleGuard {
+    /// Oldest price accepted, in seconds behind the current ledger time.
+    pub max_age_seconds: u64,
+    /// Accepted XLM price range, in USDC at TOKEN_SCALE (10^7).
+    pub min_rate: i128,
+    pub max_rate: i128,
+    /// Average this many records through `twap`; 0 or 1 uses `lastprice` alone.
+    pub twap_records: u32,
+}
+
+/// Platform take rates, in basis points of the price paid. Fees stay in the
+/// contract, accrued per currency, until `collect_fees` sends them to `treasury`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeeConfig {
+    pub treasury: Address,
+    /// Fixed-price sales (`buy_xlm` / `buy_usdc`).
+    pub sale_bps: u32,
+    /// Commission egg purchases, taken before the upfront/escrow split.
+    pub commission_bps: u32,
+}
+
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct ListingInfo {
@@ -65,6 +175,33 @@ pub struct ListingInfo {
     pub commission_eta_days: u64,
     pub is_commission_egg: bool,
     pub active: bool,
+    /// Ledger timestamp after which the listing can no longer be bought; 0 never expires.
+    pub expires_at: u64,
+}
+
+/// `ListingInfo` as stored before schema v2; only read by `load_listing`.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct ListingInfoV1 {
+    pub seller: Address,
+    pub token_id: u64,
+    pub price: i128,
+    pub currency: Currency,
+    pub commission_eta_days: u64,
+    pub is_commission_egg: bool,
+    pub active: bool,
+}
+
+/// Several NFTs custodied and sold together for one price.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct BundleListing {
+    pub seller: Address,
+    pub token_ids: Vec<u64>,
+    /// Price for the whole lot, in the seller's chosen currency.
+    pub price: i128,
+    pub currency: Currency,
+    pub active: bool,
 }
 
 #[contracttype]
@@ -85,6 +222,43 @@ pub struct SwapBid {
     pub active: bool,
 }
 
+/// Funds locked by `buyer` for `token_id`, wherever the token currently sits.
+/// Whoever owns the token when the offer is accepted receives the funds.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct Offer {
+    pub buyer: Address,
+    pub token_id: u64,
+    pub amount: i128,
+    pub currency: Currency,
+    pub expires_at: u64,
+    pub active: bool,
+}
+
+/// Which tokens a collection offer accepts, by creator, kind, or both.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub enum CollectionScope {
+    Creator(Address),
+    Kind(TokenKind),
+    CreatorKind(Address, TokenKind),
+}
+
+/// Escrowed `price * quantity` from `buyer`, paid out `price` at a time to
+/// holders of matching tokens until `filled` reaches `quantity`.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct CollectionOffer {
+    pub buyer: Address,
+    pub scope: CollectionScope,
+    pub price: i128,
+    pub currency: Currency,
+    pub quantity: u32,
+    pub filled: u32,
+    pub expires_at: u64,
+    pub active: bool,
+}
+
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct CommissionOrder {
@@ -133,6 +307,22 @@ pub enum DataKey {
     CommissionOrder(u64),
     SellerActiveCommissionEggListing(Address),
     SellerOpenCommissionOrder(Address),
+    SchemaVersion,
+    PendingAdmin,
+    RoleMember(Role, Address),
+    Paused(PauseFlag),
+    OracleGuard,
+    FeeConfig,
+    AccruedFees(Currency),
+    NextOfferId,
+    Offer(u64),
+    TokenOffers(u64),
+    BuyerOffers(Address),
+    NextCollectionOfferId,
+    CollectionOffer(u64),
+    BuyerCollectionOffers(Address),
+    NextBundleId,
+    Bundle(u64),
 }
 
 #[contract]
@@ -145,6 +335,61 @@ impl ShimejiMarketplace {
         admin
     }
 
+    fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(TTL_THRESHOLD, TTL_BUMP_AMOUNT);
+    }
+
+    /// Reads a persistent entry and, if it exists, tops its TTL back up.
+    fn read_entry<V: TryFromVal<Env, Val>>(env: &Env, key: &DataKey) -> Option<V> {
+        let value = env.storage().persistent().get(key);
+        if value.is_some() {
+            env.storage()
+                .persistent()
+                .extend_ttl(key, TTL_THRESHOLD, TTL_BUMP_AMOUNT);
+            Self::extend_instance(env);
+        }
+        value
+    }
+
+    /// Writes a persistent entry with a full TTL.
+    fn write_entry<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+        env.storage().persistent().set(key, value);
+        env.storage()
+            .persistent()
+            .extend_ttl(key, TTL_THRESHOLD, TTL_BUMP_AMOUNT);
+        Self::extend_instance(env);
+    }
+
+    /// Tops up `key`'s TTL if it exists; returns whether it did.
+    fn extend_entry(env: &Env, key: &DataKey) -> bool {
+        if !env.storage().persistent().has(key) {
+            return false;
+        }
+        env.storage()
+            .persistent()
+            .extend_ttl(key, TTL_THRESHOLD, TTL_BUMP_AMOUNT);
+        true
+    }
+
+    fn require_pauser(env: &Env, caller: &Address) -> Result<(), MarketplaceError> {
+        caller.require_auth();
+        if !Self::has_role(env.clone(), Role::Admin, caller.clone())
+            && !Self::has_role(env.clone(), Role::Pauser, caller.clone())
+        {
+            return Err(MarketplaceError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    fn require_not_paused(env: &Env, flag: PauseFlag) -> Result<(), MarketplaceError> {
+        if env.storage().instance().has(&DataKey::Paused(flag)) {
+            return Err(MarketplaceError::Paused);
+        }
+        Ok(())
+    }
+
     fn nft_contract(env: &Env) -> Address {
         env.storage().instance().get(&DataKey::NftContract).unwrap()
     }
@@ -156,7 +401,10 @@ impl ShimejiMarketplace {
             .unwrap_or(EscrowProvider::Internal)
     }
 
-    fn trustless_escrow_destination(env: &Env, currency: &Currency) -> Address {
+    fn trustless_escrow_destination(
+        env: &Env,
+        currency: &Currency,
+    ) -> Result<Address, MarketplaceError> {
         let key = match currency {
             Currency::Xlm => DataKey::TrustlessEscrowXlm,
             Currency::Usdc => DataKey::TrustlessEscrowUsdc,
@@ -164,7 +412,7 @@ impl ShimejiMarketplace {
         env.storage()
             .instance()
             .get(&key)
-            .unwrap_or_else(|| panic!("trustless escrow destination not configured"))
+            .ok_or(MarketplaceError::EscrowDestinationNotConfigured)
     }
 
     fn token_address_for_currency(env: &Env, currency: &Currency) -> Address {
@@ -183,43 +431,49 @@ impl ShimejiMarketplace {
         );
     }
 
-    fn validate_swap_intention(intention: &String) {
+    fn validate_swap_intention(intention: &String) -> Result<(), MarketplaceError> {
         if intention.is_empty() {
-            panic!("intention cannot be empty");
+            return Err(MarketplaceError::EmptyIntention);
         }
         if intention.len() > MAX_SWAP_INTENTION_LEN {
-            panic!("intention too long");
+            return Err(MarketplaceError::IntentionTooLong);
         }
+        Ok(())
     }
 
-    fn validate_commission_payload(intention: &String, reference_image_url: &String) {
+    fn validate_commission_payload(
+        intention: &String,
+        reference_image_url: &String,
+    ) -> Result<(), MarketplaceError> {
         if intention.is_empty() {
-            panic!("commission intention cannot be empty");
+            return Err(MarketplaceError::EmptyIntention);
         }
         if intention.len() > MAX_COMMISSION_INTENTION_LEN {
-            panic!("commission intention too long");
+            return Err(MarketplaceError::IntentionTooLong);
         }
         if reference_image_url.len() > MAX_REFERENCE_IMAGE_URL_LEN {
-            panic!("reference image url too long");
+            return Err(MarketplaceError::ReferenceImageTooLong);
         }
+        Ok(())
     }
 
-    fn validate_commission_eta_days(commission_eta_days: u64) {
+    fn validate_commission_eta_days(commission_eta_days: u64) -> Result<(), MarketplaceError> {
         if commission_eta_days == 0 {
-            panic!("commission_eta_days must be positive");
+            return Err(MarketplaceError::InvalidCommissionEta);
         }
         if commission_eta_days > MAX_COMMISSION_TURNAROUND_DAYS {
-            panic!("commission_eta_days too large");
+            return Err(MarketplaceError::InvalidCommissionEta);
         }
+        Ok(())
     }
 
-    fn split_commission_payment(amount_paid: i128) -> (i128, i128) {
+    fn split_commission_payment(amount_paid: i128) -> Result<(i128, i128), MarketplaceError> {
         if amount_paid <= 0 {
-            panic!("commission amount must be positive");
+            return Err(MarketplaceError::InvalidPrice);
         }
         let upfront = amount_paid / 2;
         let escrow = amount_paid - upfront;
-        (upfront, escrow)
+        Ok((upfront, escrow))
     }
 
     fn transfer_currency(
@@ -248,16 +502,16 @@ impl ShimejiMarketplace {
         env: &Env,
         currency: &Currency,
         amount: i128,
-    ) -> (EscrowProvider, Address) {
+    ) -> Result<(EscrowProvider, Address), MarketplaceError> {
         if amount <= 0 {
-            return (EscrowProvider::Internal, env.current_contract_address());
+            return Ok((EscrowProvider::Internal, env.current_contract_address()));
         }
 
         let provider = Self::current_escrow_provider(env);
         match provider {
-            EscrowProvider::Internal => (EscrowProvider::Internal, env.current_contract_address()),
+            EscrowProvider::Internal => Ok((EscrowProvider::Internal, env.current_contract_address())),
             EscrowProvider::TrustlessWork => {
-                let destination = Self::trustless_escrow_destination(env, currency);
+                let destination = Self::trustless_escrow_destination(env, currency)?;
                 Self::transfer_currency(
                     env,
                     currency,
@@ -265,12 +519,21 @@ impl ShimejiMarketplace {
                     &destination,
                     amount,
                 );
-                (EscrowProvider::TrustlessWork, destination)
+                Ok((EscrowProvider::TrustlessWork, destination))
             }
         }
     }
 
-    fn settle_commission_escrow_to(env: &Env, order: &CommissionOrder, recipient: &Address, amount: i128) {
+    /// Moves `amount` of the order's escrow to `recipient`. Vault-held escrow is
+    /// released through `vault_fn`: `payout_token`, or `refund_token` when the
+    /// money goes back to the buyer.
+    fn settle_commission_escrow_to(
+        env: &Env,
+        order: &CommissionOrder,
+        recipient: &Address,
+        amount: i128,
+        vault_fn: &str,
+    ) {
         if amount <= 0 {
             return;
         }
@@ -289,7 +552,7 @@ impl ShimejiMarketplace {
                 let token_addr = Self::token_address_for_currency(env, &order.currency);
                 env.invoke_contract::<()>(
                     &order.escrow_holder,
-                    &Symbol::new(env, "payout_token"),
+                    &Symbol::new(env, vault_fn),
                     (
                         env.current_contract_address(),
                         token_addr,
@@ -302,6 +565,134 @@ impl ShimejiMarketplace {
         }
     }
 
+    /// Tells the NFT contract whether `token_id` is tied to an open commission order
+    /// (locked eggs cannot be burned). The marketplace must be a commission manager there.
+    fn nft_set_commission_lock(env: &Env, token_id: u64, locked: bool) {
+        let nft = Self::nft_contract(env);
+        env.invoke_contract::<()>(
+            &nft,
+            &Symbol::new(env, "set_commission_lock"),
+            (env.current_contract_address(), token_id, locked).into_val(env),
+        );
+    }
+
+    fn nft_owner_of(env: &Env, token_id: u64) -> Address {
+        let nft = Self::nft_contract(env);
+        env.invoke_contract::<Address>(
+            &nft,
+            &Symbol::new(env, "owner_of"),
+            (token_id,).into_val(env),
+        )
+    }
+
+    fn nft_creator_of(env: &Env, token_id: u64) -> Address {
+        let nft = Self::nft_contract(env);
+        env.invoke_contract::<Address>(
+            &nft,
+            &Symbol::new(env, "creator_of"),
+            (token_id,).into_val(env),
+        )
+    }
+
+    fn nft_token_kind(env: &Env, token_id: u64) -> TokenKind {
+        let nft = Self::nft_contract(env);
+        env.invoke_contract::<TokenKind>(
+            &nft,
+            &Symbol::new(env, "token_kind"),
+            (token_id,).into_val(env),
+        )
+    }
+
+    fn nft_royalty_info(env: &Env, token_id: u64, sale_price: i128) -> (Address, i128) {
+        let nft = Self::nft_contract(env);
+        env.invoke_contract::<(Address, i128)>(
+            &nft,
+            &Symbol::new(env, "royalty_info"),
+            (token_id, sale_price).into_val(env),
+        )
+    }
+
+    /// Pays `amount` from `payer` for `token_id`: the platform fee is kept for the
+    /// treasury, the creator royalty goes to the creator and the remainder to the seller.
+    /// `payer` is the contract itself when the funds were already escrowed by an offer.
+    fn pay_sale_proceeds(
+        env: &Env,
+        currency: &Currency,
+        payer: &Address,
+        seller: &Address,
+        token_id: u64,
+        amount: i128,
+    ) {
+        let fee = Self::platform_fee(env, amount, false);
+        if *payer != env.current_contract_address() {
+            Self::transfer_currency(env, currency, payer, &env.current_contract_address(), fee);
+        }
+        Self::accrue_fee(env, currency, fee);
+
+        let (royalty_receiver, royalty) = Self::nft_royalty_info(env, token_id, amount);
+        if royalty > 0 && royalty_receiver != *seller {
+            Self::transfer_currency(env, currency, payer, &royalty_receiver, royalty);
+            Self::transfer_currency(env, currency, payer, seller, amount - fee - royalty);
+        } else {
+            Self::transfer_currency(env, currency, payer, seller, amount - fee);
+        }
+    }
+
+    /// Pays `amount` from `buyer` for a bundle. The platform fee is taken on the whole
+    /// amount; each token's creator royalty is computed on an equal share of it.
+    fn pay_bundle_proceeds(
+        env: &Env,
+        currency: &Currency,
+        buyer: &Address,
+        seller: &Address,
+        token_ids: &Vec<u64>,
+        amount: i128,
+    ) {
+        let fee = Self::platform_fee(env, amount, false);
+        Self::transfer_currency(env, currency, buyer, &env.current_contract_address(), fee);
+        Self::accrue_fee(env, currency, fee);
+
+        let count = token_ids.len() as i128;
+        let share = amount / count;
+        let mut royalties = 0i128;
+        for (index, token_id) in token_ids.iter().enumerate() {
+            // The last token's share absorbs the rounding remainder.
+            let token_share = if index as i128 == count - 1 {
+                amount - share * (count - 1)
+            } else {
+                share
+            };
+            let (royalty_receiver, royalty) = Self::nft_royalty_info(env, token_id, token_share);
+            if royalty > 0 && royalty_receiver != *seller {
+                Self::transfer_currency(env, currency, buyer, &royalty_receiver, royalty);
+                royalties += royalty;
+            }
+        }
+        Self::transfer_currency(env, currency, buyer, seller, amount - fee - royalties);
+    }
+
+    /// Platform fee on `amount` at the commission or the sale rate.
+    fn platform_fee(env: &Env, amount: i128, is_commission: bool) -> i128 {
+        let config: Option<FeeConfig> = env.storage().instance().get(&DataKey::FeeConfig);
+        match config {
+            Some(config) => {
+                let bps = if is_commission { config.commission_bps } else { config.sale_bps };
+                amount * bps as i128 / 10_000
+            }
+            None => 0,
+        }
+    }
+
+    /// Books `fee`, already held by the contract, for the treasury.
+    fn accrue_fee(env: &Env, currency: &Currency, fee: i128) {
+        if fee <= 0 {
+            return;
+        }
+        let key = DataKey::AccruedFees(currency.clone());
+        let accrued: i128 = env.storage().instance().get(&key).unwrap_or(0);
+        env.storage().instance().set(&key, &(accrued + fee));
+    }
+
     fn nft_token_uri(env: &Env, token_id: u64) -> String {
         let nft = Self::nft_contract(env);
         env.invoke_contract::<String>(
@@ -311,6 +702,7 @@ impl ShimejiMarketplace {
         )
     }
 
+    #[allow(clippy::too_many_arguments)] // one field per order attribute set at purchase
     fn create_commission_order_record(
         env: &Env,
         listing_id: u64,
@@ -359,35 +751,143 @@ impl ShimejiMarketplace {
             resolved_at: 0,
         };
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::CommissionOrder(order_id), &order);
-        env.storage().persistent().set(
+        Self::write_entry(env, &DataKey::CommissionOrder(order_id), &order);
+        Self::write_entry(
+            env,
             &DataKey::SellerOpenCommissionOrder(listing.seller.clone()),
             &order_id,
         );
+        Self::nft_set_commission_lock(env, listing.token_id, true);
         env.storage()
             .instance()
             .set(&DataKey::NextCommissionOrderId, &(order_id + 1));
+        Self::publish_order_status(env, order_id, &order.status);
 
         order_id
     }
 
-    fn ensure_commission_egg_slot_available(env: &Env, seller: &Address) {
+    fn buy_bundle(
+        env: &Env,
+        buyer: Address,
+        bundle_id: u64,
+        pay_currency: Currency,
+    ) -> Result<(), MarketplaceError> {
+        Self::require_not_paused(env, PauseFlag::Trading)?;
+        buyer.require_auth();
+
+        let mut bundle: BundleListing = Self::read_entry(env, &DataKey::Bundle(bundle_id))
+            .ok_or(MarketplaceError::ListingNotFound)?;
+        if !bundle.active {
+            return Err(MarketplaceError::ListingNotActive);
+        }
+
+        let amount = match (&bundle.currency, &pay_currency) {
+            (Currency::Xlm, Currency::Usdc) => {
+                bundle.price * Self::get_xlm_usdc_rate(env)? / TOKEN_SCALE
+            }
+            (Currency::Usdc, Currency::Xlm) => {
+                bundle.price * TOKEN_SCALE / Self::get_xlm_usdc_rate(env)?
+            }
+            _ => bundle.price,
+        };
+
+        Self::pay_bundle_proceeds(
+            env,
+            &pay_currency,
+            &buyer,
+            &bundle.seller,
+            &bundle.token_ids,
+            amount,
+        );
+        for token_id in bundle.token_ids.iter() {
+            Self::nft_transfer(env, env.current_contract_address(), buyer.clone(), token_id);
+        }
+
+        bundle.active = false;
+        Self::write_entry(env, &DataKey::Bundle(bundle_id), &bundle);
+        env.events().publish(
+            (symbol_short!("bndl_sold"), bundle_id, buyer),
+            (amount, pay_currency),
+        );
+        Ok(())
+    }
+
+    /// Reads a listing in the current layout. Listings written before schema v2 are
+    /// converted here and take the new layout the next time they are written, so an
+    /// upgrade never has to rewrite every listing in one transaction.
+    fn load_listing(env: &Env, listing_id: u64) -> Option<ListingInfo> {
+        let fields: Map<Symbol, Val> = Self::read_entry(env, &DataKey::Listing(listing_id))?;
+        // Decoding a struct from a map with other fields traps, so check the shape first.
+        if fields.contains_key(Symbol::new(env, "expires_at")) {
+            return ListingInfo::try_from_val(env, &fields.to_val()).ok();
+        }
+        let legacy = ListingInfoV1::try_from_val(env, &fields.to_val()).ok()?;
+        Some(ListingInfo {
+            seller: legacy.seller,
+            token_id: legacy.token_id,
+            price: legacy.price,
+            currency: legacy.currency,
+            commission_eta_days: legacy.commission_eta_days,
+            is_commission_egg: legacy.is_commission_egg,
+            active: legacy.active,
+            expires_at: 0,
+        })
+    }
+
+    fn listing_expired(env: &Env, listing: &ListingInfo) -> bool {
+        listing.expires_at != 0 && env.ledger().timestamp() >= listing.expires_at
+    }
+
+    fn publish_listed(env: &Env, listing_id: u64, listing: &ListingInfo) {
+        env.events().publish(
+            (symbol_short!("listed"), listing_id, listing.seller.clone()),
+            (
+                listing.token_id,
+                listing.price,
+                listing.currency.clone(),
+                listing.is_commission_egg,
+            ),
+        );
+    }
+
+    fn publish_sold(
+        env: &Env,
+        listing_id: u64,
+        listing: &ListingInfo,
+        buyer: &Address,
+        amount: i128,
+        currency: Currency,
+    ) {
+        env.events().publish(
+            (symbol_short!("sold"), listing_id, buyer.clone()),
+            (listing.token_id, amount, currency),
+        );
+    }
+
+    fn publish_order_status(env: &Env, order_id: u64, status: &CommissionOrderStatus) {
+        env.events()
+            .publish((symbol_short!("order"), order_id), status.clone());
+    }
+
+    fn ensure_commission_egg_slot_available(
+        env: &Env,
+        seller: &Address,
+    ) -> Result<(), MarketplaceError> {
         if env
             .storage()
             .persistent()
             .has(&DataKey::SellerActiveCommissionEggListing(seller.clone()))
         {
-            panic!("seller already has an active commission egg listing");
+            return Err(MarketplaceError::CommissionEggAlreadyListed);
         }
         if env
             .storage()
             .persistent()
             .has(&DataKey::SellerOpenCommissionOrder(seller.clone()))
         {
-            panic!("seller has a pending commission order");
+            return Err(MarketplaceError::CommissionOrderPending);
         }
+        Ok(())
     }
 
     fn clear_active_commission_egg_listing(env: &Env, seller: &Address) {
@@ -416,19 +916,189 @@ impl ShimejiMarketplace {
             .unwrap_or(0u64)
     }
 
+    fn next_offer_id(env: &Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::NextOfferId)
+            .unwrap_or(0u64)
+    }
+
+    fn offer_index(env: &Env, key: &DataKey) -> Vec<u64> {
+        Self::read_entry(env, key).unwrap_or(Vec::new(env))
+    }
+
+    fn add_to_offer_index(env: &Env, key: &DataKey, offer_id: u64) -> Result<(), MarketplaceError> {
+        let mut ids = Self::offer_index(env, key);
+        if ids.len() >= MAX_OPEN_OFFERS {
+            return Err(MarketplaceError::LimitReached);
+        }
+        ids.push_back(offer_id);
+        Self::write_entry(env, key, &ids);
+        Ok(())
+    }
+
+    fn remove_from_offer_index(env: &Env, key: &DataKey, offer_id: u64) {
+        let mut ids = Self::offer_index(env, key);
+        if let Some(pos) = ids.first_index_of(offer_id) {
+            ids.remove(pos);
+        }
+        if ids.is_empty() {
+            env.storage().persistent().remove(key);
+        } else {
+            Self::write_entry(env, key, &ids);
+        }
+    }
+
+    /// Makes room in a token's offer index for a new offer of `amount` in `currency`.
+    /// Expired and closed offers leave the index first. If it is still full, the new
+    /// offer has to beat the lowest open one, which leaves the index in its place, so
+    /// a wall of tiny offers can never lock a token out of real ones. Offers that
+    /// leave the index stay in their buyer's index and are reclaimed with `cancel_offer`;
+    /// nothing is pushed back, so an unreachable buyer cannot block the new offer.
+    fn make_room_for_offer(
+        env: &Env,
+        token_id: u64,
+        amount: i128,
+        currency: &Currency,
+    ) -> Result<(), MarketplaceError> {
+        let key = DataKey::TokenOffers(token_id);
+        let now = env.ledger().timestamp();
+        let mut open: Vec<u64> = Vec::new(env);
+        for offer_id in Self::offer_index(env, &key).iter() {
+            let offer: Option<Offer> = Self::read_entry(env, &DataKey::Offer(offer_id));
+            if matches!(offer, Some(offer) if offer.active && now < offer.expires_at) {
+                open.push_back(offer_id);
+            }
+        }
+        if open.is_empty() {
+            env.storage().persistent().remove(&key);
+        } else {
+            Self::write_entry(env, &key, &open);
+        }
+        if open.len() < MAX_OPEN_OFFERS {
+            return Ok(());
+        }
+
+        // Compare in the new offer's currency; the oracle is only read if some
+        // open offer is in the other one.
+        let mut rate: Option<i128> = None;
+        let mut lowest: Option<(u64, Offer, i128)> = None;
+        for offer_id in open.iter() {
+            let offer: Offer = Self::read_entry(env, &DataKey::Offer(offer_id)).unwrap();
+            let value = if offer.currency == *currency {
+                offer.amount
+            } else {
+                let rate = match rate {
+                    Some(rate) => rate,
+                    None => *rate.insert(Self::get_xlm_usdc_rate(env)?),
+                };
+                match offer.currency {
+                    Currency::Xlm => offer.amount * rate / TOKEN_SCALE,
+                    Currency::Usdc => offer.amount * TOKEN_SCALE / rate,
+                }
+            };
+            if lowest.as_ref().is_none_or(|(_, _, lowest_value)| value < *lowest_value) {
+                lowest = Some((offer_id, offer, value));
+            }
+        }
+        let (offer_id, offer, value) = lowest.unwrap();
+        if amount <= value {
+            return Err(MarketplaceError::LimitReached);
+        }
+        Self::remove_from_offer_index(env, &key, offer_id);
+        env.events()
+            .publish((symbol_short!("offr_evct"), offer_id, offer.buyer), offer.amount);
+        Ok(())
+    }
+
+    /// Marks an offer inactive and drops it from both indexes; the caller moves the funds.
+    fn close_offer(env: &Env, offer_id: u64, offer: &mut Offer) {
+        offer.active = false;
+        Self::write_entry(env, &DataKey::Offer(offer_id), offer);
+        Self::remove_from_offer_index(env, &DataKey::TokenOffers(offer.token_id), offer_id);
+        Self::remove_from_offer_index(env, &DataKey::BuyerOffers(offer.buyer.clone()), offer_id);
+    }
+
+    fn next_collection_offer_id(env: &Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::NextCollectionOfferId)
+            .unwrap_or(0u64)
+    }
+
+    fn scope_matches(env: &Env, scope: &CollectionScope, token_id: u64) -> bool {
+        match scope {
+            CollectionScope::Creator(creator) => Self::nft_creator_of(env, token_id) == *creator,
+            CollectionScope::Kind(kind) => Self::nft_token_kind(env, token_id) == *kind,
+            CollectionScope::CreatorKind(creator, kind) => {
+                Self::nft_creator_of(env, token_id) == *creator
+                    && Self::nft_token_kind(env, token_id) == *kind
+            }
+        }
+    }
+
+    fn close_collection_offer(env: &Env, offer_id: u64, offer: &mut CollectionOffer) {
+        offer.active = false;
+        Self::write_entry(env, &DataKey::CollectionOffer(offer_id), offer);
+        Self::remove_from_offer_index(
+            env,
+            &DataKey::BuyerCollectionOffers(offer.buyer.clone()),
+            offer_id,
+        );
+    }
+
     /// Fetch XLM/USDC rate from Reflector oracle.
-    /// Returns XLM price expressed in USDC at TOKEN_SCALE (10^7), or None if no oracle configured.
-    fn get_xlm_usdc_rate(env: &Env) -> Option<i128> {
-        let oracle: Option<Address> = env.storage().instance().get(&DataKey::OracleContract);
-        let oracle = oracle?;
+    /// Returns XLM price expressed in USDC at TOKEN_SCALE (10^7). Prices older than
+    /// the oracle guard allows or outside its bounds are rejected, and the rate
+    /// used is published as an `xlm_rate` event.
+    fn get_xlm_usdc_rate(env: &Env) -> Result<i128, MarketplaceError> {
+        let oracle: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::OracleContract)
+            .ok_or(MarketplaceError::OracleNotConfigured)?;
+        let guard = Self::current_oracle_guard(env);
         let asset = OracleAsset::Other(Symbol::new(env, "XLM"));
         let data: Option<OraclePriceData> = env.invoke_contract(
             &oracle,
             &Symbol::new(env, "lastprice"),
-            (asset,).into_val(env),
+            (asset.clone(),).into_val(env),
         );
+        let data = data.ok_or(MarketplaceError::InvalidOracleRate)?;
+        if env.ledger().timestamp().saturating_sub(data.timestamp) > guard.max_age_seconds {
+            return Err(MarketplaceError::StaleOraclePrice);
+        }
+
+        let price = if guard.twap_records > 1 {
+            let twap: Option<i128> = env.invoke_contract(
+                &oracle,
+                &Symbol::new(env, "twap"),
+                (asset, guard.twap_records).into_val(env),
+            );
+            twap.ok_or(MarketplaceError::StaleOraclePrice)?
+        } else {
+            data.price
+        };
         // Reflector returns price at 10^14 scale; convert to 10^7 by dividing by 10^7
-        data.map(|pd| pd.price / 10_000_000)
+        let rate = price / 10_000_000;
+        if rate < guard.min_rate || rate > guard.max_rate {
+            return Err(MarketplaceError::OraclePriceOutOfBounds);
+        }
+        env.events()
+            .publish((symbol_short!("xlm_rate"),), (rate, data.timestamp));
+        Ok(rate)
+    }
+
+    fn current_oracle_guard(env: &Env) -> OracleGuard {
+        env.storage()
+            .instance()
+            .get(&DataKey::OracleGuard)
+            .unwrap_or(OracleGuard {
+                max_age_seconds: DEFAULT_ORACLE_MAX_AGE,
+                min_rate: 1,
+                max_rate: i128::MAX,
+                twap_records: 0,
+            })
     }
 
     fn refund_active_swap_bids_for_listing(env: &Env, listing_id: u64, except_bid_id: Option<u64>) {
@@ -440,7 +1110,7 @@ impl ShimejiMarketplace {
                 }
             }
 
-            let maybe_bid: Option<SwapBid> = env.storage().persistent().get(&DataKey::SwapBid(bid_id));
+            let maybe_bid: Option<SwapBid> = Self::read_entry(env, &DataKey::SwapBid(bid_id));
             let Some(mut bid) = maybe_bid else {
                 continue;
             };
@@ -455,7 +1125,9 @@ impl ShimejiMarketplace {
                 bid.bidder_token_id,
             );
             bid.active = false;
-            env.storage().persistent().set(&DataKey::SwapBid(bid_id), &bid);
+            Self::write_entry(env, &DataKey::SwapBid(bid_id), &bid);
+            env.events()
+                .publish((symbol_short!("bid_cncl"), listing_id, bid_id), ());
         }
     }
 }
@@ -468,9 +1140,9 @@ impl ShimejiMarketplace {
         nft_contract: Address,
         usdc_token: Address,
         xlm_token: Address,
-    ) {
+    ) -> Result<(), MarketplaceError> {
         if env.storage().instance().has(&DataKey::Admin) {
-            panic!("already initialized");
+            return Err(MarketplaceError::AlreadyInitialized);
         }
         env.storage().instance().set(&DataKey::Admin, &admin);
         env.storage()
@@ -491,25 +1163,162 @@ impl ShimejiMarketplace {
         env.storage()
             .instance()
             .set(&DataKey::NextCommissionOrderId, &0u64);
+        env.storage()
+            .instance()
+            .set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+        Self::extend_instance(&env);
+        Ok(())
     }
 
-    pub fn configure_internal_escrow(env: Env) {
+    /// Swaps this contract's code for an already-uploaded wasm. Storage is left
+    /// untouched; call `migrate` afterwards if the new code bumps the schema.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) {
         Self::require_admin(&env);
+        env.deployer()
+            .update_current_contract_wasm(new_wasm_hash.clone());
+        env.events()
+            .publish((symbol_short!("upgraded"),), new_wasm_hash);
+    }
+
+    /// Storage layout version. Deployments from before versioning report 0.
+    pub fn schema_version(env: Env) -> u32 {
         env.storage()
             .instance()
-            .set(&DataKey::EscrowProvider, &EscrowProvider::Internal);
+            .get(&DataKey::SchemaVersion)
+            .unwrap_or(0)
     }
 
-    pub fn configure_trustless_escrow(env: Env, xlm_destination: Address, usdc_destination: Address) {
+    /// Brings stored data from the recorded schema version up to the one this
+    /// code expects. Safe to call repeatedly; returns the resulting version.
+    pub fn migrate(env: Env) -> Result<u32, MarketplaceError> {
         Self::require_admin(&env);
-        env.storage().instance().set(&DataKey::TrustlessEscrowXlm, &xlm_destination);
-        env.storage().instance().set(&DataKey::TrustlessEscrowUsdc, &usdc_destination);
+        let from = Self::schema_version(env.clone());
+        if from > SCHEMA_VERSION {
+            return Err(MarketplaceError::UnsupportedSchemaVersion);
+        }
+        if from == SCHEMA_VERSION {
+            return Ok(from);
+        }
+        // v0 -> v1: layout unchanged, only the version stamp is new.
+        // v1 -> v2: listings gain `expires_at`; `load_listing` reads v1 listings as never
+        // expiring, so no pass over every listing is needed here.
         env.storage()
             .instance()
-            .set(&DataKey::EscrowProvider, &EscrowProvider::TrustlessWork);
+            .set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+        env.events()
+            .publish((symbol_short!("migrated"),), (from, SCHEMA_VERSION));
+        Ok(SCHEMA_VERSION)
     }
 
-    pub fn get_escrow_provider(env: Env) -> EscrowProvider {
+    /// First half of an admin handover: nothing changes until `new_admin`
+    /// calls `accept_admin`. A later proposal replaces an earlier one.
+    pub fn propose_admin(env: Env, new_admin: Address) {
+        let admin = Self::require_admin(&env);
+        env.storage().instance().set(&DataKey::PendingAdmin, &new_admin);
+        env.events()
+            .publish((symbol_short!("adm_prop"), admin), new_admin);
+    }
+
+    pub fn accept_admin(env: Env) -> Result<(), MarketplaceError> {
+        let new_admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::PendingAdmin)
+            .ok_or(MarketplaceError::NoPendingAdmin)?;
+        new_admin.require_auth();
+        let previous: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        env.storage().instance().set(&DataKey::Admin, &new_admin);
+        env.storage().instance().remove(&DataKey::PendingAdmin);
+        env.events()
+            .publish((symbol_short!("adm_xfer"), previous), new_admin);
+        Ok(())
+    }
+
+    pub fn pending_admin(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::PendingAdmin)
+    }
+
+    pub fn admin(env: Env) -> Address {
+        env.storage().instance().get(&DataKey::Admin).unwrap()
+    }
+
+    pub fn grant_role(env: Env, role: Role, account: Address) -> Result<(), MarketplaceError> {
+        Self::require_admin(&env);
+        if role == Role::Admin {
+            return Err(MarketplaceError::AdminRoleNotGrantable);
+        }
+        let key = DataKey::RoleMember(role, account.clone());
+        if !env.storage().instance().has(&key) {
+            env.storage().instance().set(&key, &true);
+            env.events()
+                .publish((symbol_short!("role_grnt"), account), role);
+        }
+        Ok(())
+    }
+
+    pub fn revoke_role(env: Env, role: Role, account: Address) -> Result<(), MarketplaceError> {
+        Self::require_admin(&env);
+        if role == Role::Admin {
+            return Err(MarketplaceError::AdminRoleNotGrantable);
+        }
+        let key = DataKey::RoleMember(role, account.clone());
+        if env.storage().instance().has(&key) {
+            env.storage().instance().remove(&key);
+            env.events()
+                .publish((symbol_short!("role_rvk"), account), role);
+        }
+        Ok(())
+    }
+
+    pub fn has_role(env: Env, role: Role, account: Address) -> bool {
+        match role {
+            Role::Admin => {
+                let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+                admin == account
+            }
+            _ => env
+                .storage()
+                .instance()
+                .has(&DataKey::RoleMember(role, account)),
+        }
+    }
+
+    /// Halts one class of operations until `unpause`. Admin or pausers only.
+    pub fn pause(env: Env, caller: Address, flag: PauseFlag) -> Result<(), MarketplaceError> {
+        Self::require_pauser(&env, &caller)?;
+        env.storage().instance().set(&DataKey::Paused(flag), &true);
+        env.events().publish((symbol_short!("paused"), caller), flag);
+        Ok(())
+    }
+
+    pub fn unpause(env: Env, caller: Address, flag: PauseFlag) -> Result<(), MarketplaceError> {
+        Self::require_pauser(&env, &caller)?;
+        env.storage().instance().remove(&DataKey::Paused(flag));
+        env.events().publish((symbol_short!("unpaused"), caller), flag);
+        Ok(())
+    }
+
+    pub fn is_paused(env: Env, flag: PauseFlag) -> bool {
+        env.storage().instance().has(&DataKey::Paused(flag))
+    }
+
+    pub fn configure_internal_escrow(env: Env) {
+        Self::require_admin(&env);
+        env.storage()
+            .instance()
+            .set(&DataKey::EscrowProvider, &EscrowProvider::Internal);
+    }
+
+    pub fn configure_trustless_escrow(env: Env, xlm_destination: Address, usdc_destination: Address) {
+        Self::require_admin(&env);
+        env.storage().instance().set(&DataKey::TrustlessEscrowXlm, &xlm_destination);
+        env.storage().instance().set(&DataKey::TrustlessEscrowUsdc, &usdc_destination);
+        env.storage()
+            .instance()
+            .set(&DataKey::EscrowProvider, &EscrowProvider::TrustlessWork);
+    }
+
+    pub fn get_escrow_provider(env: Env) -> EscrowProvider {
         Self::current_escrow_provider(&env)
     }
 
@@ -532,19 +1341,93 @@ impl ShimejiMarketplace {
         env.storage().instance().set(&DataKey::OracleContract, &oracle);
     }
 
+    /// Sets the staleness and bounds checks for oracle prices (default: 15 minutes,
+    /// any positive rate, `lastprice` only).
+    pub fn configure_oracle_guard(env: Env, guard: OracleGuard) -> Result<(), MarketplaceError> {
+        Self::require_admin(&env);
+        if guard.max_age_seconds == 0 || guard.min_rate <= 0 || guard.max_rate < guard.min_rate {
+            return Err(MarketplaceError::InvalidOracleGuard);
+        }
+        env.storage().instance().set(&DataKey::OracleGuard, &guard);
+        Ok(())
+    }
+
+    pub fn get_oracle_guard(env: Env) -> OracleGuard {
+        Self::current_oracle_guard(&env)
+    }
+
+    /// Sets the treasury and the fees taken from sales and commission purchases.
+    pub fn configure_fees(env: Env, config: FeeConfig) -> Result<(), MarketplaceError> {
+        Self::require_admin(&env);
+        if config.sale_bps > MAX_FEE_BPS || config.commission_bps > MAX_FEE_BPS {
+            return Err(MarketplaceError::InvalidFee);
+        }
+        env.storage().instance().set(&DataKey::FeeConfig, &config);
+        Ok(())
+    }
+
+    pub fn get_fee_config(env: Env) -> Option<FeeConfig> {
+        env.storage().instance().get(&DataKey::FeeConfig)
+    }
+
+    pub fn accrued_fees(env: Env, currency: Currency) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::AccruedFees(currency))
+            .unwrap_or(0)
+    }
+
+    /// Sends every accrued fee in `currency` to the treasury. Admin or treasurers only.
+    pub fn collect_fees(
+        env: Env,
+        caller: Address,
+        currency: Currency,
+    ) -> Result<i128, MarketplaceError> {
+        Self::require_not_paused(&env, PauseFlag::Withdrawals)?;
+        caller.require_auth();
+        if !Self::has_role(env.clone(), Role::Admin, caller.clone())
+            && !Self::has_role(env.clone(), Role::Treasurer, caller.clone())
+        {
+            return Err(MarketplaceError::Unauthorized);
+        }
+        let amount = Self::accrued_fees(env.clone(), currency.clone());
+        if amount == 0 {
+            return Err(MarketplaceError::NoFeesAccrued);
+        }
+        let config: FeeConfig = env.storage().instance().get(&DataKey::FeeConfig).unwrap();
+        env.storage().instance().remove(&DataKey::AccruedFees(currency.clone()));
+        Self::transfer_currency(
+            &env,
+            &currency,
+            &env.current_contract_address(),
+            &config.treasury,
+            amount,
+        );
+        env.events()
+            .publish((symbol_short!("fees"), config.treasury), (amount, currency));
+        Ok(amount)
+    }
+
     // ── Fixed-price listings ────────────────────────────────────────────────
 
+    /// Lists `token_id` at a fixed price. A non-zero `expires_at` (ledger timestamp)
+    /// stops purchases from then on and lets anyone `expire_listing` it.
     pub fn list_for_sale(
         env: Env,
         seller: Address,
         token_id: u64,
         price: i128,
         currency: Currency,
-    ) -> u64 {
+        expires_at: u64,
+    ) -> Result<u64, MarketplaceError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
         seller.require_auth();
 
         if price <= 0 {
-            panic!("price must be positive");
+            return Err(MarketplaceError::InvalidPrice);
+        }
+        if expires_at != 0 && expires_at <= env.ledger().timestamp() {
+            return Err(MarketplaceError::Expired);
         }
 
         // Take custody of NFT from seller
@@ -562,23 +1445,23 @@ impl ShimejiMarketplace {
             .unwrap();
 
         let listing = ListingInfo {
-            seller,
+            seller: seller.clone(),
             token_id,
             price,
-            currency,
+            currency: currency.clone(),
             commission_eta_days: 0,
             is_commission_egg: false,
             active: true,
+            expires_at,
         };
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::Listing(listing_id), &listing);
+        Self::write_entry(&env, &DataKey::Listing(listing_id), &listing);
         env.storage()
             .instance()
             .set(&DataKey::NextListingId, &(listing_id + 1));
+        Self::publish_listed(&env, listing_id, &listing);
 
-        listing_id
+        Ok(listing_id)
     }
 
     pub fn list_commission_egg(
@@ -588,13 +1471,14 @@ impl ShimejiMarketplace {
         price: i128,
         currency: Currency,
         commission_eta_days: u64,
-    ) -> u64 {
+    ) -> Result<u64, MarketplaceError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
         seller.require_auth();
-        Self::ensure_commission_egg_slot_available(&env, &seller);
-        Self::validate_commission_eta_days(commission_eta_days);
+        Self::ensure_commission_egg_slot_available(&env, &seller)?;
+        Self::validate_commission_eta_days(commission_eta_days)?;
 
         if price <= 0 {
-            panic!("price must be positive");
+            return Err(MarketplaceError::InvalidPrice);
         }
 
         Self::nft_transfer(
@@ -618,108 +1502,119 @@ impl ShimejiMarketplace {
             commission_eta_days,
             is_commission_egg: true,
             active: true,
+            expires_at: 0,
         };
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::Listing(listing_id), &listing);
-        env.storage().persistent().set(
-            &DataKey::SellerActiveCommissionEggListing(seller),
+        Self::write_entry(&env, &DataKey::Listing(listing_id), &listing);
+        Self::write_entry(
+            &env,
+            &DataKey::SellerActiveCommissionEggListing(seller.clone()),
             &listing_id,
         );
         env.storage()
             .instance()
             .set(&DataKey::NextListingId, &(listing_id + 1));
+        Self::publish_listed(&env, listing_id, &listing);
 
-        listing_id
+        Ok(listing_id)
     }
 
-    pub fn buy_xlm(env: Env, buyer: Address, listing_id: u64) {
+    pub fn buy_xlm(env: Env, buyer: Address, listing_id: u64) -> Result<(), MarketplaceError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
         buyer.require_auth();
 
-        let mut listing: ListingInfo = env
-            .storage()
-            .persistent()
-            .get(&DataKey::Listing(listing_id))
-            .unwrap_or_else(|| panic!("listing does not exist"));
+        let mut listing: ListingInfo = Self::load_listing(&env, listing_id)
+            .ok_or(MarketplaceError::ListingNotFound)?;
 
         if !listing.active {
-            panic!("listing is not active");
+            return Err(MarketplaceError::ListingNotActive);
         }
         if listing.is_commission_egg {
-            panic!("use buy_commission_xlm for commission listings");
+            return Err(MarketplaceError::CommissionListing);
+        }
+        if Self::listing_expired(&env, &listing) {
+            return Err(MarketplaceError::Expired);
         }
 
         // Compute XLM amount: direct if listed in XLM, or convert via oracle if listed in USDC
         let amount_xlm = match listing.currency {
             Currency::Xlm => listing.price,
             Currency::Usdc => {
-                let rate = Self::get_xlm_usdc_rate(&env)
-                    .unwrap_or_else(|| panic!("oracle not configured for XLM/USDC conversion"));
-                if rate <= 0 { panic!("invalid oracle rate"); }
+                let rate = Self::get_xlm_usdc_rate(&env)?;
                 listing.price * TOKEN_SCALE / rate
             }
         };
 
-        let xlm_token: Address = env.storage().instance().get(&DataKey::XlmToken).unwrap();
-        token::Client::new(&env, &xlm_token).transfer(&buyer, &listing.seller, &amount_xlm);
+        Self::pay_sale_proceeds(
+            &env,
+            &Currency::Xlm,
+            &buyer,
+            &listing.seller,
+            listing.token_id,
+            amount_xlm,
+        );
 
         // Transfer NFT from marketplace to buyer
         Self::nft_transfer(
             &env,
             env.current_contract_address(),
-            buyer,
+            buyer.clone(),
             listing.token_id,
         );
 
         listing.active = false;
-        env.storage()
-            .persistent()
-            .set(&DataKey::Listing(listing_id), &listing);
+        Self::write_entry(&env, &DataKey::Listing(listing_id), &listing);
+        Self::publish_sold(&env, listing_id, &listing, &buyer, amount_xlm, Currency::Xlm);
+        Ok(())
     }
 
-    pub fn buy_usdc(env: Env, buyer: Address, listing_id: u64) {
+    pub fn buy_usdc(env: Env, buyer: Address, listing_id: u64) -> Result<(), MarketplaceError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
         buyer.require_auth();
 
-        let mut listing: ListingInfo = env
-            .storage()
-            .persistent()
-            .get(&DataKey::Listing(listing_id))
-            .unwrap_or_else(|| panic!("listing does not exist"));
+        let mut listing: ListingInfo = Self::load_listing(&env, listing_id)
+            .ok_or(MarketplaceError::ListingNotFound)?;
 
         if !listing.active {
-            panic!("listing is not active");
+            return Err(MarketplaceError::ListingNotActive);
         }
         if listing.is_commission_egg {
-            panic!("use buy_commission_usdc for commission listings");
+            return Err(MarketplaceError::CommissionListing);
+        }
+        if Self::listing_expired(&env, &listing) {
+            return Err(MarketplaceError::Expired);
         }
 
         // Compute USDC amount: direct if listed in USDC, or convert via oracle if listed in XLM
         let amount_usdc = match listing.currency {
             Currency::Usdc => listing.price,
             Currency::Xlm => {
-                let rate = Self::get_xlm_usdc_rate(&env)
-                    .unwrap_or_else(|| panic!("oracle not configured for XLM/USDC conversion"));
-                if rate <= 0 { panic!("invalid oracle rate"); }
+                let rate = Self::get_xlm_usdc_rate(&env)?;
                 listing.price * rate / TOKEN_SCALE
             }
         };
 
-        let usdc_token: Address = env.storage().instance().get(&DataKey::UsdcToken).unwrap();
-        token::Client::new(&env, &usdc_token).transfer(&buyer, &listing.seller, &amount_usdc);
+        Self::pay_sale_proceeds(
+            &env,
+            &Currency::Usdc,
+            &buyer,
+            &listing.seller,
+            listing.token_id,
+            amount_usdc,
+        );
 
         // Transfer NFT from marketplace to buyer
         Self::nft_transfer(
             &env,
             env.current_contract_address(),
-            buyer,
+            buyer.clone(),
             listing.token_id,
         );
 
         listing.active = false;
-        env.storage()
-            .persistent()
-            .set(&DataKey::Listing(listing_id), &listing);
+        Self::write_entry(&env, &DataKey::Listing(listing_id), &listing);
+        Self::publish_sold(&env, listing_id, &listing, &buyer, amount_usdc, Currency::Usdc);
+        Ok(())
     }
 
     pub fn buy_commission_xlm(
@@ -728,30 +1623,26 @@ impl ShimejiMarketplace {
         listing_id: u64,
         intention: String,
         reference_image_url: String,
-    ) -> u64 {
+    ) -> Result<u64, MarketplaceError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
         buyer.require_auth();
-        Self::validate_commission_payload(&intention, &reference_image_url);
+        Self::validate_commission_payload(&intention, &reference_image_url)?;
 
-        let mut listing: ListingInfo = env
-            .storage()
-            .persistent()
-            .get(&DataKey::Listing(listing_id))
-            .unwrap_or_else(|| panic!("listing does not exist"));
+        let mut listing: ListingInfo = Self::load_listing(&env, listing_id)
+            .ok_or(MarketplaceError::ListingNotFound)?;
 
         if !listing.active {
-            panic!("listing is not active");
+            return Err(MarketplaceError::ListingNotActive);
         }
         if !listing.is_commission_egg {
-            panic!("listing is not a commission egg");
+            return Err(MarketplaceError::NotCommissionListing);
         }
 
         // Compute XLM amount to pay based on listing currency
         let amount_xlm = match listing.currency {
             Currency::Xlm => listing.price,
             Currency::Usdc => {
-                let rate = Self::get_xlm_usdc_rate(&env)
-                    .unwrap_or_else(|| panic!("oracle not configured for XLM/USDC conversion"));
-                if rate <= 0 { panic!("invalid oracle rate"); }
+                let rate = Self::get_xlm_usdc_rate(&env)?;
                 listing.price * TOKEN_SCALE / rate
             }
         };
@@ -759,7 +1650,10 @@ impl ShimejiMarketplace {
         let xlm_token: Address = env.storage().instance().get(&DataKey::XlmToken).unwrap();
         let token_client = token::Client::new(&env, &xlm_token);
         token_client.transfer(&buyer, &env.current_contract_address(), &amount_xlm);
-        let (upfront_paid_to_seller, escrow_remaining) = Self::split_commission_payment(amount_xlm);
+        let fee = Self::platform_fee(&env, amount_xlm, true);
+        Self::accrue_fee(&env, &Currency::Xlm, fee);
+        let (upfront_paid_to_seller, escrow_remaining) =
+            Self::split_commission_payment(amount_xlm - fee)?;
         if upfront_paid_to_seller > 0 {
             token_client.transfer(
                 &env.current_contract_address(),
@@ -768,7 +1662,7 @@ impl ShimejiMarketplace {
             );
         }
         let (escrow_provider, escrow_holder) =
-            Self::route_commission_escrow_after_purchase(&env, &Currency::Xlm, escrow_remaining);
+            Self::route_commission_escrow_after_purchase(&env, &Currency::Xlm, escrow_remaining)?;
 
         Self::nft_transfer(
             &env,
@@ -777,6 +1671,7 @@ impl ShimejiMarketplace {
             listing.token_id,
         );
 
+        Self::publish_sold(&env, listing_id, &listing, &buyer, amount_xlm, Currency::Xlm);
         let order_id = Self::create_commission_order_record(
             &env,
             listing_id,
@@ -793,12 +1688,10 @@ impl ShimejiMarketplace {
         );
 
         listing.active = false;
-        env.storage()
-            .persistent()
-            .set(&DataKey::Listing(listing_id), &listing);
+        Self::write_entry(&env, &DataKey::Listing(listing_id), &listing);
         Self::clear_active_commission_egg_listing(&env, &listing.seller);
 
-        order_id
+        Ok(order_id)
     }
 
     pub fn buy_commission_usdc(
@@ -807,30 +1700,26 @@ impl ShimejiMarketplace {
         listing_id: u64,
         intention: String,
         reference_image_url: String,
-    ) -> u64 {
+    ) -> Result<u64, MarketplaceError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
         buyer.require_auth();
-        Self::validate_commission_payload(&intention, &reference_image_url);
+        Self::validate_commission_payload(&intention, &reference_image_url)?;
 
-        let mut listing: ListingInfo = env
-            .storage()
-            .persistent()
-            .get(&DataKey::Listing(listing_id))
-            .unwrap_or_else(|| panic!("listing does not exist"));
+        let mut listing: ListingInfo = Self::load_listing(&env, listing_id)
+            .ok_or(MarketplaceError::ListingNotFound)?;
 
         if !listing.active {
-            panic!("listing is not active");
+            return Err(MarketplaceError::ListingNotActive);
         }
         if !listing.is_commission_egg {
-            panic!("listing is not a commission egg");
+            return Err(MarketplaceError::NotCommissionListing);
         }
 
         // Compute USDC amount to pay based on listing currency
         let amount_usdc = match listing.currency {
             Currency::Usdc => listing.price,
             Currency::Xlm => {
-                let rate = Self::get_xlm_usdc_rate(&env)
-                    .unwrap_or_else(|| panic!("oracle not configured for XLM/USDC conversion"));
-                if rate <= 0 { panic!("invalid oracle rate"); }
+                let rate = Self::get_xlm_usdc_rate(&env)?;
                 listing.price * rate / TOKEN_SCALE
             }
         };
@@ -838,7 +1727,10 @@ impl ShimejiMarketplace {
         let usdc_token: Address = env.storage().instance().get(&DataKey::UsdcToken).unwrap();
         let token_client = token::Client::new(&env, &usdc_token);
         token_client.transfer(&buyer, &env.current_contract_address(), &amount_usdc);
-        let (upfront_paid_to_seller, escrow_remaining) = Self::split_commission_payment(amount_usdc);
+        let fee = Self::platform_fee(&env, amount_usdc, true);
+        Self::accrue_fee(&env, &Currency::Usdc, fee);
+        let (upfront_paid_to_seller, escrow_remaining) =
+            Self::split_commission_payment(amount_usdc - fee)?;
         if upfront_paid_to_seller > 0 {
             token_client.transfer(
                 &env.current_contract_address(),
@@ -847,7 +1739,7 @@ impl ShimejiMarketplace {
             );
         }
         let (escrow_provider, escrow_holder) =
-            Self::route_commission_escrow_after_purchase(&env, &Currency::Usdc, escrow_remaining);
+            Self::route_commission_escrow_after_purchase(&env, &Currency::Usdc, escrow_remaining)?;
 
         Self::nft_transfer(
             &env,
@@ -856,6 +1748,7 @@ impl ShimejiMarketplace {
             listing.token_id,
         );
 
+        Self::publish_sold(&env, listing_id, &listing, &buyer, amount_usdc, Currency::Usdc);
         let order_id = Self::create_commission_order_record(
             &env,
             listing_id,
@@ -872,35 +1765,34 @@ impl ShimejiMarketplace {
         );
 
         listing.active = false;
-        env.storage()
-            .persistent()
-            .set(&DataKey::Listing(listing_id), &listing);
+        Self::write_entry(&env, &DataKey::Listing(listing_id), &listing);
         Self::clear_active_commission_egg_listing(&env, &listing.seller);
 
-        order_id
+        Ok(order_id)
     }
 
-    pub fn cancel_listing(env: Env, seller: Address, listing_id: u64) {
+    pub fn cancel_listing(
+        env: Env,
+        seller: Address,
+        listing_id: u64,
+    ) -> Result<(), MarketplaceError> {
         seller.require_auth();
 
-        let mut listing: ListingInfo = env
-            .storage()
-            .persistent()
-            .get(&DataKey::Listing(listing_id))
-            .unwrap_or_else(|| panic!("listing does not exist"));
+        let mut listing: ListingInfo = Self::load_listing(&env, listing_id)
+            .ok_or(MarketplaceError::ListingNotFound)?;
 
         if listing.seller != seller {
-            panic!("only the seller can cancel this listing");
+            return Err(MarketplaceError::NotSeller);
         }
         if !listing.active {
-            panic!("listing is not active");
+            return Err(MarketplaceError::ListingNotActive);
         }
 
         // Return NFT to seller
         Self::nft_transfer(
             &env,
             env.current_contract_address(),
-            seller,
+            seller.clone(),
             listing.token_id,
         );
 
@@ -909,25 +1801,94 @@ impl ShimejiMarketplace {
         }
 
         listing.active = false;
-        env.storage()
-            .persistent()
-            .set(&DataKey::Listing(listing_id), &listing);
+        Self::write_entry(&env, &DataKey::Listing(listing_id), &listing);
+        env.events().publish(
+            (symbol_short!("unlisted"), listing_id, seller),
+            listing.token_id,
+        );
+        Ok(())
+    }
+
+    /// Returns an expired listing's NFT to its seller. Anyone may call it once
+    /// `expires_at` has passed; before that only the seller can `cancel_listing`.
+    /// A listing that has not expired yet (or never expires) fails with `ListingNotExpired`.
+    pub fn expire_listing(env: Env, listing_id: u64) -> Result<(), MarketplaceError> {
+        let mut listing: ListingInfo = Self::load_listing(&env, listing_id)
+            .ok_or(MarketplaceError::ListingNotFound)?;
+        if !listing.active {
+            return Err(MarketplaceError::ListingNotActive);
+        }
+        if !Self::listing_expired(&env, &listing) {
+            return Err(MarketplaceError::ListingNotExpired);
+        }
+
+        Self::nft_transfer(
+            &env,
+            env.current_contract_address(),
+            listing.seller.clone(),
+            listing.token_id,
+        );
+
+        listing.active = false;
+        Self::write_entry(&env, &DataKey::Listing(listing_id), &listing);
+        env.events().publish(
+            (symbol_short!("expired"), listing_id, listing.seller),
+            listing.token_id,
+        );
+        Ok(())
     }
 
-    pub fn mark_commission_delivered(env: Env, seller: Address, order_id: u64) {
+    /// Reprices an active listing in place, keeping the NFT in custody.
+    pub fn update_listing_price(
+        env: Env,
+        seller: Address,
+        listing_id: u64,
+        new_price: i128,
+        new_currency: Currency,
+    ) -> Result<(), MarketplaceError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
         seller.require_auth();
 
-        let mut order: CommissionOrder = env
-            .storage()
-            .persistent()
-            .get(&DataKey::CommissionOrder(order_id))
-            .unwrap_or_else(|| panic!("commission order does not exist"));
+        let mut listing: ListingInfo = Self::load_listing(&env, listing_id)
+            .ok_or(MarketplaceError::ListingNotFound)?;
+        if listing.seller != seller {
+            return Err(MarketplaceError::NotSeller);
+        }
+        if !listing.active {
+            return Err(MarketplaceError::ListingNotActive);
+        }
+        if Self::listing_expired(&env, &listing) {
+            return Err(MarketplaceError::Expired);
+        }
+        if new_price <= 0 {
+            return Err(MarketplaceError::InvalidPrice);
+        }
+
+        listing.price = new_price;
+        listing.currency = new_currency.clone();
+        Self::write_entry(&env, &DataKey::Listing(listing_id), &listing);
+        env.events().publish(
+            (symbol_short!("repriced"), listing_id, seller),
+            (new_price, new_currency),
+        );
+        Ok(())
+    }
+
+    pub fn mark_commission_delivered(
+        env: Env,
+        seller: Address,
+        order_id: u64,
+    ) -> Result<(), MarketplaceError> {
+        seller.require_auth();
+
+        let mut order: CommissionOrder = Self::read_entry(&env, &DataKey::CommissionOrder(order_id))
+            .ok_or(MarketplaceError::OrderNotFound)?;
 
         if order.seller != seller {
-            panic!("only seller can mark commission delivered");
+            return Err(MarketplaceError::NotSeller);
         }
         if order.status != CommissionOrderStatus::Accepted {
-            panic!("commission is not in accepted state");
+            return Err(MarketplaceError::OrderNotAccepted);
         }
 
         let current_token_uri = Self::nft_token_uri(&env, order.token_id);
@@ -938,15 +1899,15 @@ impl ShimejiMarketplace {
         };
 
         if current_token_uri == baseline_token_uri {
-            panic!("update nft metadata before marking commission delivered");
+            return Err(MarketplaceError::MetadataNotUpdated);
         }
 
         order.status = CommissionOrderStatus::Delivered;
         order.last_delivered_metadata_uri = current_token_uri;
         order.delivered_at = env.ledger().timestamp();
-        env.storage()
-            .persistent()
-            .set(&DataKey::CommissionOrder(order_id), &order);
+        Self::write_entry(&env, &DataKey::CommissionOrder(order_id), &order);
+        Self::publish_order_status(&env, order_id, &order.status);
+        Ok(())
     }
 
     pub fn request_commission_revision(
@@ -955,24 +1916,21 @@ impl ShimejiMarketplace {
         order_id: u64,
         intention: String,
         reference_image_url: String,
-    ) {
+    ) -> Result<(), MarketplaceError> {
         buyer.require_auth();
-        Self::validate_commission_payload(&intention, &reference_image_url);
+        Self::validate_commission_payload(&intention, &reference_image_url)?;
 
-        let mut order: CommissionOrder = env
-            .storage()
-            .persistent()
-            .get(&DataKey::CommissionOrder(order_id))
-            .unwrap_or_else(|| panic!("commission order does not exist"));
+        let mut order: CommissionOrder = Self::read_entry(&env, &DataKey::CommissionOrder(order_id))
+            .ok_or(MarketplaceError::OrderNotFound)?;
 
         if order.buyer != buyer {
-            panic!("only buyer can request commission changes");
+            return Err(MarketplaceError::NotBuyer);
         }
         if order.status != CommissionOrderStatus::Delivered {
-            panic!("commission order is not awaiting buyer review");
+            return Err(MarketplaceError::OrderNotDelivered);
         }
         if order.revision_request_count >= order.max_revision_requests {
-            panic!("maximum revision requests reached");
+            return Err(MarketplaceError::RevisionLimitReached);
         }
 
         order.revision_request_count += 1;
@@ -980,105 +1938,233 @@ impl ShimejiMarketplace {
         order.latest_revision_ref_url = reference_image_url;
         order.status = CommissionOrderStatus::Accepted;
         order.delivered_at = 0;
-        env.storage()
-            .persistent()
-            .set(&DataKey::CommissionOrder(order_id), &order);
+        Self::write_entry(&env, &DataKey::CommissionOrder(order_id), &order);
+        Self::publish_order_status(&env, order_id, &order.status);
+        Ok(())
     }
 
-    pub fn approve_commission_delivery(env: Env, buyer: Address, order_id: u64) {
+    pub fn approve_commission_delivery(
+        env: Env,
+        buyer: Address,
+        order_id: u64,
+    ) -> Result<(), MarketplaceError> {
+        Self::require_not_paused(&env, PauseFlag::Settlements)?;
         buyer.require_auth();
 
-        let mut order: CommissionOrder = env
-            .storage()
-            .persistent()
-            .get(&DataKey::CommissionOrder(order_id))
-            .unwrap_or_else(|| panic!("commission order does not exist"));
+        let mut order: CommissionOrder = Self::read_entry(&env, &DataKey::CommissionOrder(order_id))
+            .ok_or(MarketplaceError::OrderNotFound)?;
 
         if order.buyer != buyer {
-            panic!("only buyer can approve commission delivery");
+            return Err(MarketplaceError::NotBuyer);
         }
         if order.status != CommissionOrderStatus::Delivered {
-            panic!("commission order is not delivered");
+            return Err(MarketplaceError::OrderNotDelivered);
         }
 
-        Self::settle_commission_escrow_to(&env, &order, &order.seller, order.escrow_remaining);
+        Self::settle_commission_escrow_to(
+            &env,
+            &order,
+            &order.seller,
+            order.escrow_remaining,
+            "payout_token",
+        );
 
         order.escrow_remaining = 0;
         order.status = CommissionOrderStatus::Completed;
         order.resolved_at = env.ledger().timestamp();
-        env.storage()
-            .persistent()
-            .set(&DataKey::CommissionOrder(order_id), &order);
+        Self::write_entry(&env, &DataKey::CommissionOrder(order_id), &order);
         Self::clear_seller_open_commission_order(&env, &order.seller);
+        Self::nft_set_commission_lock(&env, order.token_id, false);
+        Self::publish_order_status(&env, order_id, &order.status);
+        Ok(())
     }
 
-    pub fn claim_commission_timeout(env: Env, seller: Address, order_id: u64) {
+    pub fn claim_commission_timeout(
+        env: Env,
+        seller: Address,
+        order_id: u64,
+    ) -> Result<(), MarketplaceError> {
+        Self::require_not_paused(&env, PauseFlag::Settlements)?;
         seller.require_auth();
 
-        let mut order: CommissionOrder = env
-            .storage()
-            .persistent()
-            .get(&DataKey::CommissionOrder(order_id))
-            .unwrap_or_else(|| panic!("commission order does not exist"));
+        let mut order: CommissionOrder = Self::read_entry(&env, &DataKey::CommissionOrder(order_id))
+            .ok_or(MarketplaceError::OrderNotFound)?;
 
         if order.seller != seller {
-            panic!("only seller can claim commission timeout");
+            return Err(MarketplaceError::NotSeller);
         }
         if order.status != CommissionOrderStatus::Delivered {
-            panic!("commission order is not delivered");
+            return Err(MarketplaceError::OrderNotDelivered);
         }
         if order.delivered_at == 0 {
-            panic!("commission delivery timestamp missing");
+            return Err(MarketplaceError::OrderNotDelivered);
         }
 
         let now = env.ledger().timestamp();
         if now < order.delivered_at + COMMISSION_AUTO_RELEASE_AFTER_DELIVERY_SECS {
-            panic!("commission timeout not reached yet");
+            return Err(MarketplaceError::CommissionTimeoutNotReached);
         }
 
-        Self::settle_commission_escrow_to(&env, &order, &order.seller, order.escrow_remaining);
+        Self::settle_commission_escrow_to(
+            &env,
+            &order,
+            &order.seller,
+            order.escrow_remaining,
+            "payout_token",
+        );
 
         order.escrow_remaining = 0;
         order.status = CommissionOrderStatus::Completed;
         order.resolved_at = now;
-        env.storage()
-            .persistent()
-            .set(&DataKey::CommissionOrder(order_id), &order);
+        Self::write_entry(&env, &DataKey::CommissionOrder(order_id), &order);
         Self::clear_seller_open_commission_order(&env, &order.seller);
+        Self::nft_set_commission_lock(&env, order.token_id, false);
+        Self::publish_order_status(&env, order_id, &order.status);
+        Ok(())
     }
 
-    pub fn refund_commission_order(env: Env, caller: Address, order_id: u64) {
+    pub fn refund_commission_order(
+        env: Env,
+        caller: Address,
+        order_id: u64,
+    ) -> Result<(), MarketplaceError> {
         caller.require_auth();
 
-        let mut order: CommissionOrder = env
-            .storage()
-            .persistent()
-            .get(&DataKey::CommissionOrder(order_id))
-            .unwrap_or_else(|| panic!("commission order does not exist"));
+        let mut order: CommissionOrder = Self::read_entry(&env, &DataKey::CommissionOrder(order_id))
+            .ok_or(MarketplaceError::OrderNotFound)?;
 
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         let caller_is_buyer = caller == order.buyer;
         let caller_is_seller = caller == order.seller;
-        let caller_is_admin = caller == admin;
+        let caller_can_arbitrate = Self::has_role(env.clone(), Role::Admin, caller.clone())
+            || Self::has_role(env.clone(), Role::Arbiter, caller.clone());
 
-        if !(caller_is_buyer || caller_is_seller || caller_is_admin) {
-            panic!("unauthorized refund caller");
+        if !(caller_is_buyer || caller_is_seller || caller_can_arbitrate) {
+            return Err(MarketplaceError::Unauthorized);
         }
         if order.status != CommissionOrderStatus::Accepted
             && order.status != CommissionOrderStatus::Delivered
         {
-            panic!("commission order cannot be refunded");
+            return Err(MarketplaceError::OrderNotRefundable);
         }
 
-        Self::settle_commission_escrow_to(&env, &order, &order.buyer, order.escrow_remaining);
+        Self::settle_commission_escrow_to(
+            &env,
+            &order,
+            &order.buyer,
+            order.escrow_remaining,
+            "refund_token",
+        );
 
         order.escrow_remaining = 0;
         order.status = CommissionOrderStatus::Refunded;
         order.resolved_at = env.ledger().timestamp();
-        env.storage()
-            .persistent()
-            .set(&DataKey::CommissionOrder(order_id), &order);
+        Self::write_entry(&env, &DataKey::CommissionOrder(order_id), &order);
         Self::clear_seller_open_commission_order(&env, &order.seller);
+        Self::nft_set_commission_lock(&env, order.token_id, false);
+        Self::publish_order_status(&env, order_id, &order.status);
+        Ok(())
+    }
+
+    // ── Bundle listings ─────────────────────────────────────────────────────
+
+    /// Lists 2 to `MAX_BUNDLE_SIZE` distinct tokens as one lot for `price`.
+    /// Any other count fails with `InvalidBundleSize`; a duplicated token or a
+    /// commission egg (sold only through `list_commission_egg`) with `TokenNotEligible`.
+    pub fn list_bundle(
+        env: Env,
+        seller: Address,
+        token_ids: Vec<u64>,
+        price: i128,
+        currency: Currency,
+    ) -> Result<u64, MarketplaceError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
+        seller.require_auth();
+
+        if price <= 0 {
+            return Err(MarketplaceError::InvalidPrice);
+        }
+        if token_ids.len() < 2 || token_ids.len() > MAX_BUNDLE_SIZE {
+            return Err(MarketplaceError::InvalidBundleSize);
+        }
+        for (index, token_id) in token_ids.iter().enumerate() {
+            if token_ids.last_index_of(token_id) != Some(index as u32)
+                || Self::nft_token_kind(&env, token_id) == TokenKind::CommissionEgg
+            {
+                return Err(MarketplaceError::TokenNotEligible);
+            }
+        }
+
+        for token_id in token_ids.iter() {
+            Self::nft_transfer(&env, seller.clone(), env.current_contract_address(), token_id);
+        }
+
+        let bundle_id: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::NextBundleId)
+            .unwrap_or(0u64);
+        let bundle = BundleListing {
+            seller: seller.clone(),
+            token_ids: token_ids.clone(),
+            price,
+            currency: currency.clone(),
+            active: true,
+        };
+
+        Self::write_entry(&env, &DataKey::Bundle(bundle_id), &bundle);
+        env.storage()
+            .instance()
+            .set(&DataKey::NextBundleId, &(bundle_id + 1));
+        env.events().publish(
+            (symbol_short!("bndl_new"), bundle_id, seller),
+            (token_ids, price, currency),
+        );
+
+        Ok(bundle_id)
+    }
+
+    pub fn buy_bundle_xlm(
+        env: Env,
+        buyer: Address,
+        bundle_id: u64,
+    ) -> Result<(), MarketplaceError> {
+        Self::buy_bundle(&env, buyer, bundle_id, Currency::Xlm)
+    }
+
+    pub fn buy_bundle_usdc(
+        env: Env,
+        buyer: Address,
+        bundle_id: u64,
+    ) -> Result<(), MarketplaceError> {
+        Self::buy_bundle(&env, buyer, bundle_id, Currency::Usdc)
+    }
+
+    /// Returns every token in an unsold bundle to its seller.
+    pub fn cancel_bundle(
+        env: Env,
+        seller: Address,
+        bundle_id: u64,
+    ) -> Result<(), MarketplaceError> {
+        seller.require_auth();
+
+        let mut bundle: BundleListing = Self::read_entry(&env, &DataKey::Bundle(bundle_id))
+            .ok_or(MarketplaceError::ListingNotFound)?;
+        if bundle.seller != seller {
+            return Err(MarketplaceError::NotSeller);
+        }
+        if !bundle.active {
+            return Err(MarketplaceError::ListingNotActive);
+        }
+
+        for token_id in bundle.token_ids.iter() {
+            Self::nft_transfer(&env, env.current_contract_address(), seller.clone(), token_id);
+        }
+
+        bundle.active = false;
+        Self::write_entry(&env, &DataKey::Bundle(bundle_id), &bundle);
+        env.events()
+            .publish((symbol_short!("bndl_cncl"), bundle_id, seller), ());
+        Ok(())
     }
 
     // ── Open swap listings + bids ───────────────────────────────────────────
@@ -1088,9 +2174,10 @@ impl ShimejiMarketplace {
         creator: Address,
         offered_token_id: u64,
         intention: String,
-    ) -> u64 {
+    ) -> Result<u64, MarketplaceError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
         creator.require_auth();
-        Self::validate_swap_intention(&intention);
+        Self::validate_swap_intention(&intention)?;
 
         Self::nft_transfer(
             &env,
@@ -1101,39 +2188,44 @@ impl ShimejiMarketplace {
 
         let listing_id = Self::next_swap_listing_id(&env);
         let listing = SwapListing {
-            creator,
+            creator: creator.clone(),
             offered_token_id,
             intention,
             active: true,
         };
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::SwapListing(listing_id), &listing);
+        Self::write_entry(&env, &DataKey::SwapListing(listing_id), &listing);
         env.storage()
             .instance()
             .set(&DataKey::NextSwapListingId, &(listing_id + 1));
-
-        listing_id
+        env.events().publish(
+            (symbol_short!("swap_new"), listing_id, creator),
+            offered_token_id,
+        );
+
+        Ok(listing_id)
     }
 
-    pub fn place_swap_bid(env: Env, bidder: Address, listing_id: u64, bidder_token_id: u64) -> u64 {
+    pub fn place_swap_bid(
+        env: Env,
+        bidder: Address,
+        listing_id: u64,
+        bidder_token_id: u64,
+    ) -> Result<u64, MarketplaceError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
         bidder.require_auth();
 
-        let listing: SwapListing = env
-            .storage()
-            .persistent()
-            .get(&DataKey::SwapListing(listing_id))
-            .unwrap_or_else(|| panic!("swap listing does not exist"));
+        let listing: SwapListing = Self::read_entry(&env, &DataKey::SwapListing(listing_id))
+            .ok_or(MarketplaceError::SwapListingNotFound)?;
 
         if !listing.active {
-            panic!("swap listing is not active");
+            return Err(MarketplaceError::SwapListingNotActive);
         }
         if listing.creator == bidder {
-            panic!("creator cannot bid on own swap listing");
+            return Err(MarketplaceError::SwapOwnListing);
         }
         if listing.offered_token_id == bidder_token_id {
-            panic!("cannot swap a token with itself");
+            return Err(MarketplaceError::SwapSameToken);
         }
 
         Self::nft_transfer(
@@ -1146,44 +2238,48 @@ impl ShimejiMarketplace {
         let bid_id = Self::next_swap_bid_id(&env);
         let bid = SwapBid {
             listing_id,
-            bidder,
+            bidder: bidder.clone(),
             bidder_token_id,
             active: true,
         };
 
-        env.storage().persistent().set(&DataKey::SwapBid(bid_id), &bid);
+        Self::write_entry(&env, &DataKey::SwapBid(bid_id), &bid);
         env.storage()
             .instance()
             .set(&DataKey::NextSwapBidId, &(bid_id + 1));
+        env.events().publish(
+            (symbol_short!("swap_bid"), listing_id, bid_id),
+            (bidder, bidder_token_id),
+        );
 
-        bid_id
+        Ok(bid_id)
     }
 
-    pub fn accept_swap_bid(env: Env, creator: Address, listing_id: u64, bid_id: u64) {
+    pub fn accept_swap_bid(
+        env: Env,
+        creator: Address,
+        listing_id: u64,
+        bid_id: u64,
+    ) -> Result<(), MarketplaceError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
         creator.require_auth();
 
-        let mut listing: SwapListing = env
-            .storage()
-            .persistent()
-            .get(&DataKey::SwapListing(listing_id))
-            .unwrap_or_else(|| panic!("swap listing does not exist"));
+        let mut listing: SwapListing = Self::read_entry(&env, &DataKey::SwapListing(listing_id))
+            .ok_or(MarketplaceError::SwapListingNotFound)?;
         if !listing.active {
-            panic!("swap listing is not active");
+            return Err(MarketplaceError::SwapListingNotActive);
         }
         if listing.creator != creator {
-            panic!("only the creator can accept swap bids");
+            return Err(MarketplaceError::NotSwapCreator);
         }
 
-        let mut bid: SwapBid = env
-            .storage()
-            .persistent()
-            .get(&DataKey::SwapBid(bid_id))
-            .unwrap_or_else(|| panic!("swap bid does not exist"));
+        let mut bid: SwapBid = Self::read_entry(&env, &DataKey::SwapBid(bid_id))
+            .ok_or(MarketplaceError::SwapBidNotFound)?;
         if !bid.active {
-            panic!("swap bid is not active");
+            return Err(MarketplaceError::SwapBidNotActive);
         }
         if bid.listing_id != listing_id {
-            panic!("swap bid does not belong to this listing");
+            return Err(MarketplaceError::SwapBidListingMismatch);
         }
 
         Self::nft_transfer(
@@ -1201,28 +2297,30 @@ impl ShimejiMarketplace {
 
         listing.active = false;
         bid.active = false;
-        env.storage()
-            .persistent()
-            .set(&DataKey::SwapListing(listing_id), &listing);
-        env.storage().persistent().set(&DataKey::SwapBid(bid_id), &bid);
+        Self::write_entry(&env, &DataKey::SwapListing(listing_id), &listing);
+        Self::write_entry(&env, &DataKey::SwapBid(bid_id), &bid);
+        env.events()
+            .publish((symbol_short!("swap_acpt"), listing_id, bid_id), ());
 
         Self::refund_active_swap_bids_for_listing(&env, listing_id, Some(bid_id));
+        Ok(())
     }
 
-    pub fn cancel_swap_listing(env: Env, creator: Address, listing_id: u64) {
+    pub fn cancel_swap_listing(
+        env: Env,
+        creator: Address,
+        listing_id: u64,
+    ) -> Result<(), MarketplaceError> {
         creator.require_auth();
 
-        let mut listing: SwapListing = env
-            .storage()
-            .persistent()
-            .get(&DataKey::SwapListing(listing_id))
-            .unwrap_or_else(|| panic!("swap listing does not exist"));
+        let mut listing: SwapListing = Self::read_entry(&env, &DataKey::SwapListing(listing_id))
+            .ok_or(MarketplaceError::SwapListingNotFound)?;
 
         if listing.creator != creator {
-            panic!("only the creator can cancel this swap listing");
+            return Err(MarketplaceError::NotSwapCreator);
         }
         if !listing.active {
-            panic!("swap listing is not active");
+            return Err(MarketplaceError::SwapListingNotActive);
         }
 
         Self::nft_transfer(
@@ -1233,27 +2331,25 @@ impl ShimejiMarketplace {
         );
 
         listing.active = false;
-        env.storage()
-            .persistent()
-            .set(&DataKey::SwapListing(listing_id), &listing);
+        Self::write_entry(&env, &DataKey::SwapListing(listing_id), &listing);
+        env.events()
+            .publish((symbol_short!("swap_cncl"), listing_id), ());
 
         Self::refund_active_swap_bids_for_listing(&env, listing_id, None);
+        Ok(())
     }
 
-    pub fn cancel_swap_bid(env: Env, bidder: Address, bid_id: u64) {
+    pub fn cancel_swap_bid(env: Env, bidder: Address, bid_id: u64) -> Result<(), MarketplaceError> {
         bidder.require_auth();
 
-        let mut bid: SwapBid = env
-            .storage()
-            .persistent()
-            .get(&DataKey::SwapBid(bid_id))
-            .unwrap_or_else(|| panic!("swap bid does not exist"));
+        let mut bid: SwapBid = Self::read_entry(&env, &DataKey::SwapBid(bid_id))
+            .ok_or(MarketplaceError::SwapBidNotFound)?;
 
         if bid.bidder != bidder {
-            panic!("only the bidder can cancel this swap bid");
+            return Err(MarketplaceError::NotSwapBidder);
         }
         if !bid.active {
-            panic!("swap bid is not active");
+            return Err(MarketplaceError::SwapBidNotActive);
         }
 
         Self::nft_transfer(
@@ -1264,37 +2360,395 @@ impl ShimejiMarketplace {
         );
 
         bid.active = false;
-        env.storage().persistent().set(&DataKey::SwapBid(bid_id), &bid);
+        Self::write_entry(&env, &DataKey::SwapBid(bid_id), &bid);
+        env.events()
+            .publish((symbol_short!("bid_cncl"), bid.listing_id, bid_id), ());
+        Ok(())
     }
 
-    // ── Queries ─────────────────────────────────────────────────────────────
+    // ── Offers ──────────────────────────────────────────────────────────────
+
+    /// Locks `amount` from `buyer` in the marketplace as a standing offer on
+    /// `token_id`, open until `expires_at` (ledger timestamp).
+    pub fn make_offer(
+        env: Env,
+        buyer: Address,
+        token_id: u64,
+        amount: i128,
+        currency: Currency,
+        expires_at: u64,
+    ) -> Result<u64, MarketplaceError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
+        buyer.require_auth();
+
+        if amount <= 0 {
+            return Err(MarketplaceError::InvalidPrice);
+        }
+        if expires_at <= env.ledger().timestamp() {
+            return Err(MarketplaceError::Expired);
+        }
+        // Fails for tokens that do not exist.
+        Self::nft_owner_of(&env, token_id);
+
+        Self::make_room_for_offer(&env, token_id, amount, &currency)?;
+        let offer_id = Self::next_offer_id(&env);
+        Self::add_to_offer_index(&env, &DataKey::TokenOffers(token_id), offer_id)?;
+        Self::add_to_offer_index(&env, &DataKey::BuyerOffers(buyer.clone()), offer_id)?;
 
-    pub fn get_listing(env: Env, listing_id: u64) -> ListingInfo {
+        Self::transfer_currency(
+            &env,
+            &currency,
+            &buyer,
+            &env.current_contract_address(),
+            amount,
+        );
+
+        let offer = Offer {
+            buyer: buyer.clone(),
+            token_id,
+            amount,
+            currency: currency.clone(),
+            expires_at,
+            active: true,
+        };
+        Self::write_entry(&env, &DataKey::Offer(offer_id), &offer);
         env.storage()
-            .persistent()
-            .get(&DataKey::Listing(listing_id))
-            .unwrap_or_else(|| panic!("listing does not exist"))
+            .instance()
+            .set(&DataKey::NextOfferId, &(offer_id + 1));
+        env.events().publish(
+            (symbol_short!("offer_new"), offer_id, buyer),
+            (token_id, amount, currency, expires_at),
+        );
+
+        Ok(offer_id)
     }
 
-    pub fn get_swap_listing(env: Env, listing_id: u64) -> SwapListing {
-        env.storage()
-            .persistent()
-            .get(&DataKey::SwapListing(listing_id))
-            .unwrap_or_else(|| panic!("swap listing does not exist"))
+    /// The token's current owner takes an unexpired offer: the token goes to the
+    /// buyer and the escrowed funds are paid out like a sale (fee, royalty, seller).
+    /// An offer evicted from the token's index can only be cancelled, not accepted.
+    pub fn accept_offer(env: Env, owner: Address, offer_id: u64) -> Result<(), MarketplaceError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
+        owner.require_auth();
+
+        let mut offer: Offer = Self::read_entry(&env, &DataKey::Offer(offer_id))
+            .ok_or(MarketplaceError::OfferNotFound)?;
+        if !offer.active
+            || !Self::offer_index(&env, &DataKey::TokenOffers(offer.token_id)).contains(offer_id)
+        {
+            return Err(MarketplaceError::OfferNotActive);
+        }
+        if env.ledger().timestamp() >= offer.expires_at {
+            return Err(MarketplaceError::Expired);
+        }
+        if Self::nft_owner_of(&env, offer.token_id) != owner {
+            return Err(MarketplaceError::NotSeller);
+        }
+
+        Self::nft_transfer(&env, owner.clone(), offer.buyer.clone(), offer.token_id);
+        Self::pay_sale_proceeds(
+            &env,
+            &offer.currency,
+            &env.current_contract_address(),
+            &owner,
+            offer.token_id,
+            offer.amount,
+        );
+
+        Self::close_offer(&env, offer_id, &mut offer);
+        env.events().publish(
+            (symbol_short!("offr_acpt"), offer_id, owner),
+            (offer.token_id, offer.amount, offer.currency),
+        );
+        Ok(())
     }
 
-    pub fn get_swap_bid(env: Env, bid_id: u64) -> SwapBid {
-        env.storage()
-            .persistent()
-            .get(&DataKey::SwapBid(bid_id))
-            .unwrap_or_else(|| panic!("swap bid does not exist"))
+    /// The buyer withdraws an offer and gets the locked funds back. This is also
+    /// how funds come back once an offer has expired.
+    pub fn cancel_offer(env: Env, buyer: Address, offer_id: u64) -> Result<(), MarketplaceError> {
+        buyer.require_auth();
+
+        let mut offer: Offer = Self::read_entry(&env, &DataKey::Offer(offer_id))
+            .ok_or(MarketplaceError::OfferNotFound)?;
+        if offer.buyer != buyer {
+            return Err(MarketplaceError::NotBuyer);
+        }
+        if !offer.active {
+            return Err(MarketplaceError::OfferNotActive);
+        }
+
+        Self::transfer_currency(
+            &env,
+            &offer.currency,
+            &env.current_contract_address(),
+            &buyer,
+            offer.amount,
+        );
+        Self::close_offer(&env, offer_id, &mut offer);
+        env.events()
+            .publish((symbol_short!("offr_cncl"), offer_id, buyer), offer.amount);
+        Ok(())
     }
 
-    pub fn get_commission_order(env: Env, order_id: u64) -> CommissionOrder {
+    // ── Collection offers ───────────────────────────────────────────────────
+
+    /// Locks `price * quantity` from `buyer` for up to `quantity` tokens matching
+    /// `scope`, open until `expires_at` (ledger timestamp).
+    pub fn make_collection_offer(
+        env: Env,
+        buyer: Address,
+        scope: CollectionScope,
+        price: i128,
+        currency: Currency,
+        quantity: u32,
+        expires_at: u64,
+    ) -> Result<u64, MarketplaceError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
+        buyer.require_auth();
+
+        if price <= 0 {
+            return Err(MarketplaceError::InvalidPrice);
+        }
+        if quantity == 0 || quantity > MAX_COLLECTION_OFFER_QUANTITY {
+            return Err(MarketplaceError::LimitReached);
+        }
+        if expires_at <= env.ledger().timestamp() {
+            return Err(MarketplaceError::Expired);
+        }
+
+        let offer_id = Self::next_collection_offer_id(&env);
+        Self::add_to_offer_index(
+            &env,
+            &DataKey::BuyerCollectionOffers(buyer.clone()),
+            offer_id,
+        )?;
+
+        Self::transfer_currency(
+            &env,
+            &currency,
+            &buyer,
+            &env.current_contract_address(),
+            price * quantity as i128,
+        );
+
+        let offer = CollectionOffer {
+            buyer: buyer.clone(),
+            scope: scope.clone(),
+            price,
+            currency: currency.clone(),
+            quantity,
+            filled: 0,
+            expires_at,
+            active: true,
+        };
+        Self::write_entry(&env, &DataKey::CollectionOffer(offer_id), &offer);
         env.storage()
-            .persistent()
-            .get(&DataKey::CommissionOrder(order_id))
-            .unwrap_or_else(|| panic!("commission order does not exist"))
+            .instance()
+            .set(&DataKey::NextCollectionOfferId, &(offer_id + 1));
+        env.events().publish(
+            (symbol_short!("col_offer"), offer_id, buyer),
+            (scope, price, currency, quantity, expires_at),
+        );
+
+        Ok(offer_id)
+    }
+
+    /// Sells one matching `token_id` into a collection offer: the token goes to the
+    /// buyer and `price` is paid out of escrow like a sale. The offer closes once
+    /// `quantity` tokens have been filled.
+    pub fn fill_collection_offer(
+        env: Env,
+        owner: Address,
+        offer_id: u64,
+        token_id: u64,
+    ) -> Result<(), MarketplaceError> {
+        Self::require_not_paused(&env, PauseFlag::Trading)?;
+        owner.require_auth();
+
+        let mut offer: CollectionOffer =
+            Self::read_entry(&env, &DataKey::CollectionOffer(offer_id))
+                .ok_or(MarketplaceError::OfferNotFound)?;
+        if !offer.active {
+            return Err(MarketplaceError::OfferNotActive);
+        }
+        if env.ledger().timestamp() >= offer.expires_at {
+            return Err(MarketplaceError::Expired);
+        }
+        if Self::nft_owner_of(&env, token_id) != owner {
+            return Err(MarketplaceError::NotSeller);
+        }
+        if !Self::scope_matches(&env, &offer.scope, token_id) {
+            return Err(MarketplaceError::TokenNotEligible);
+        }
+
+        Self::nft_transfer(&env, owner.clone(), offer.buyer.clone(), token_id);
+        Self::pay_sale_proceeds(
+            &env,
+            &offer.currency,
+            &env.current_contract_address(),
+            &owner,
+            token_id,
+            offer.price,
+        );
+
+        offer.filled += 1;
+        if offer.filled == offer.quantity {
+            Self::close_collection_offer(&env, offer_id, &mut offer);
+        } else {
+            Self::write_entry(&env, &DataKey::CollectionOffer(offer_id), &offer);
+        }
+        env.events().publish(
+            (symbol_short!("col_fill"), offer_id, owner),
+            (token_id, offer.price, offer.currency, offer.filled),
+        );
+        Ok(())
+    }
+
+    /// The buyer withdraws a collection offer, before or after it expires, and
+    /// gets back the escrow for the unfilled quantity.
+    pub fn cancel_collection_offer(
+        env: Env,
+        buyer: Address,
+        offer_id: u64,
+    ) -> Result<i128, MarketplaceError> {
+        buyer.require_auth();
+
+        let mut offer: CollectionOffer =
+            Self::read_entry(&env, &DataKey::CollectionOffer(offer_id))
+                .ok_or(MarketplaceError::OfferNotFound)?;
+        if offer.buyer != buyer {
+            return Err(MarketplaceError::NotBuyer);
+        }
+        if !offer.active {
+            return Err(MarketplaceError::OfferNotActive);
+        }
+
+        let refund = offer.price * (offer.quantity - offer.filled) as i128;
+        Self::transfer_currency(
+            &env,
+            &offer.currency,
+            &env.current_contract_address(),
+            &buyer,
+            refund,
+        );
+        Self::close_collection_offer(&env, offer_id, &mut offer);
+        env.events()
+            .publish((symbol_short!("col_cncl"), offer_id, buyer), refund);
+        Ok(refund)
+    }
+
+    // ── Queries ─────────────────────────────────────────────────────────────
+
+    pub fn get_listing(env: Env, listing_id: u64) -> Result<ListingInfo, MarketplaceError> {
+        Self::load_listing(&env, listing_id)
+            .ok_or(MarketplaceError::ListingNotFound)
+    }
+
+    pub fn get_bundle(env: Env, bundle_id: u64) -> Result<BundleListing, MarketplaceError> {
+        Self::read_entry(&env, &DataKey::Bundle(bundle_id)).ok_or(MarketplaceError::ListingNotFound)
+    }
+
+    pub fn get_swap_listing(env: Env, listing_id: u64) -> Result<SwapListing, MarketplaceError> {
+        Self::read_entry(&env, &DataKey::SwapListing(listing_id))
+            .ok_or(MarketplaceError::SwapListingNotFound)
+    }
+
+    pub fn get_swap_bid(env: Env, bid_id: u64) -> Result<SwapBid, MarketplaceError> {
+        Self::read_entry(&env, &DataKey::SwapBid(bid_id)).ok_or(MarketplaceError::SwapBidNotFound)
+    }
+
+    pub fn get_offer(env: Env, offer_id: u64) -> Result<Offer, MarketplaceError> {
+        Self::read_entry(&env, &DataKey::Offer(offer_id)).ok_or(MarketplaceError::OfferNotFound)
+    }
+
+    /// Ids of the open offers on `token_id`, oldest first: at most 50, and offers
+    /// that expired since the last `make_offer` on the token may still be listed.
+    pub fn get_token_offers(env: Env, token_id: u64) -> Vec<u64> {
+        Self::offer_index(&env, &DataKey::TokenOffers(token_id))
+    }
+
+    /// Ids of `buyer`'s open offers, oldest first.
+    pub fn get_buyer_offers(env: Env, buyer: Address) -> Vec<u64> {
+        Self::offer_index(&env, &DataKey::BuyerOffers(buyer))
+    }
+
+    pub fn get_collection_offer(
+        env: Env,
+        offer_id: u64,
+    ) -> Result<CollectionOffer, MarketplaceError> {
+        Self::read_entry(&env, &DataKey::CollectionOffer(offer_id))
+            .ok_or(MarketplaceError::OfferNotFound)
+    }
+
+    /// Ids of `buyer`'s open collection offers, oldest first.
+    pub fn get_buyer_collection_offers(env: Env, buyer: Address) -> Vec<u64> {
+        Self::offer_index(&env, &DataKey::BuyerCollectionOffers(buyer))
+    }
+
+    pub fn get_commission_order(
+        env: Env,
+        order_id: u64,
+    ) -> Result<CommissionOrder, MarketplaceError> {
+        Self::read_entry(&env, &DataKey::CommissionOrder(order_id))
+            .ok_or(MarketplaceError::OrderNotFound)
+    }
+
+    /// Permissionless keep-alive for a fixed-price listing and the contract
+    /// instance. Anyone may call it; it only extends TTLs.
+    pub fn bump_listing(env: Env, listing_id: u64) -> Result<(), MarketplaceError> {
+        if !Self::extend_entry(&env, &DataKey::Listing(listing_id)) {
+            return Err(MarketplaceError::ListingNotFound);
+        }
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    pub fn bump_bundle(env: Env, bundle_id: u64) -> Result<(), MarketplaceError> {
+        if !Self::extend_entry(&env, &DataKey::Bundle(bundle_id)) {
+            return Err(MarketplaceError::ListingNotFound);
+        }
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Keep-alive for a swap listing; its bids are bumped with `bump_swap_bid`.
+    pub fn bump_swap_listing(env: Env, listing_id: u64) -> Result<(), MarketplaceError> {
+        if !Self::extend_entry(&env, &DataKey::SwapListing(listing_id)) {
+            return Err(MarketplaceError::SwapListingNotFound);
+        }
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    pub fn bump_swap_bid(env: Env, bid_id: u64) -> Result<(), MarketplaceError> {
+        if !Self::extend_entry(&env, &DataKey::SwapBid(bid_id)) {
+            return Err(MarketplaceError::SwapBidNotFound);
+        }
+        Self::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Keep-alive for an offer together with its token and buyer indexes.
+    pub fn bump_offer(env: Env, offer_id: u64) -> Result<(), MarketplaceError> {
+        let offer: Offer = Self::read_entry(&env, &DataKey::Offer(offer_id))
+            .ok_or(MarketplaceError::OfferNotFound)?;
+        Self::extend_entry(&env, &DataKey::TokenOffers(offer.token_id));
+        Self::extend_entry(&env, &DataKey::BuyerOffers(offer.buyer));
+        Ok(())
+    }
+
+    pub fn bump_collection_offer(env: Env, offer_id: u64) -> Result<(), MarketplaceError> {
+        let offer: CollectionOffer = Self::read_entry(&env, &DataKey::CollectionOffer(offer_id))
+            .ok_or(MarketplaceError::OfferNotFound)?;
+        Self::extend_entry(&env, &DataKey::BuyerCollectionOffers(offer.buyer));
+        Ok(())
+    }
+
+    pub fn bump_commission_order(env: Env, order_id: u64) -> Result<(), MarketplaceError> {
+        if !Self::extend_entry(&env, &DataKey::CommissionOrder(order_id)) {
+            return Err(MarketplaceError::OrderNotFound);
+        }
+        Self::extend_instance(&env);
+        Ok(())
     }
 
     pub fn total_listings(env: Env) -> u64 {
@@ -1304,6 +2758,13 @@ impl ShimejiMarketplace {
             .unwrap_or(0u64)
     }
 
+    pub fn total_bundles(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::NextBundleId)
+            .unwrap_or(0u64)
+    }
+
     pub fn total_swap_listings(env: Env) -> u64 {
         env.storage()
             .instance()
@@ -1324,6 +2785,14 @@ impl ShimejiMarketplace {
             .get(&DataKey::NextCommissionOrderId)
             .unwrap_or(0u64)
     }
+
+    pub fn total_offers(env: Env) -> u64 {
+        Self::next_offer_id(&env)
+    }
+
+    pub fn total_collection_offers(env: Env) -> u64 {
+        Self::next_collection_offer_id(&env)
+    }
 }
 
 #[cfg(test)]
@@ -1331,9 +2800,9 @@ mod test {
     use super::*;
     use soroban_sdk::{
         contract, contractimpl,
-        testutils::{Address as _, Ledger as _},
+        testutils::{Address as _, Events, Ledger as _},
         token::{StellarAssetClient, TokenClient},
-        Env, String,
+        vec, Env, String, Val, Vec,
     };
 
     #[contracttype]
@@ -1341,6 +2810,10 @@ mod test {
     enum MockNftKey {
         Owner(u64),
         Uri(u64),
+        CommissionLocked(u64),
+        Royalty(u64),
+        Creator(u64),
+        Kind(u64),
     }
 
     #[contracttype]
@@ -1378,6 +2851,9 @@ mod test {
             env.storage()
                 .persistent()
                 .set(&MockNftKey::Uri(id), &_token_uri);
+            env.storage()
+                .persistent()
+                .set(&MockNftKey::Creator(id), &to);
             env.storage()
                 .instance()
                 .set(&soroban_sdk::symbol_short!("next"), &(id + 1));
@@ -1418,6 +2894,55 @@ mod test {
                 .persistent()
                 .set(&MockNftKey::Uri(token_id), &token_uri);
         }
+
+        pub fn set_commission_lock(env: Env, manager: Address, token_id: u64, locked: bool) {
+            manager.require_auth();
+            env.storage()
+                .persistent()
+                .set(&MockNftKey::CommissionLocked(token_id), &locked);
+        }
+
+        pub fn set_royalty(env: Env, token_id: u64, receiver: Address, royalty_bps: u32) {
+            env.storage()
+                .persistent()
+                .set(&MockNftKey::Royalty(token_id), &(receiver, royalty_bps));
+        }
+
+        pub fn royalty_info(env: Env, token_id: u64, sale_price: i128) -> (Address, i128) {
+            let royalty: Option<(Address, u32)> =
+                env.storage().persistent().get(&MockNftKey::Royalty(token_id));
+            match royalty {
+                Some((receiver, bps)) => (receiver, sale_price * bps as i128 / 10_000),
+                None => (Self::owner_of(env, token_id), 0),
+            }
+        }
+
+        pub fn creator_of(env: Env, token_id: u64) -> Address {
+            env.storage()
+                .persistent()
+                .get(&MockNftKey::Creator(token_id))
+                .unwrap()
+        }
+
+        pub fn set_token_kind(env: Env, token_id: u64, kind: TokenKind) {
+            env.storage()
+                .persistent()
+                .set(&MockNftKey::Kind(token_id), &kind);
+        }
+
+        pub fn token_kind(env: Env, token_id: u64) -> TokenKind {
+            env.storage()
+                .persistent()
+                .get(&MockNftKey::Kind(token_id))
+                .unwrap_or(TokenKind::Finished)
+        }
+
+        pub fn is_commission_locked(env: Env, token_id: u64) -> bool {
+            env.storage()
+                .persistent()
+                .get(&MockNftKey::CommissionLocked(token_id))
+                .unwrap_or(false)
+        }
     }
 
     #[contract]
@@ -1453,6 +2978,41 @@ mod test {
             }
             token::Client::new(&env, &token_id).transfer(&env.current_contract_address(), &to, &amount);
         }
+
+        pub fn refund_token(env: Env, caller: Address, token_id: Address, to: Address, amount: i128) {
+            Self::payout_token(env, caller, token_id, to, amount);
+        }
+    }
+
+    // ── Mock Reflector oracle ────────────────────────────────────────────────
+
+    #[contracttype]
+    #[derive(Clone)]
+    enum MockOracleKey {
+        Price,
+        Timestamp,
+    }
+
+    #[contract]
+    struct MockOracle;
+
+    #[contractimpl]
+    impl MockOracle {
+        /// $0.10 per XLM (10^14 scale) at the current ledger time unless overridden.
+        pub fn lastprice(env: Env, _asset: OracleAsset) -> Option<OraclePriceData> {
+            let storage = env.storage().instance();
+            Some(OraclePriceData {
+                price: storage.get(&MockOracleKey::Price).unwrap_or(10_000_000_000_000),
+                timestamp: storage
+                    .get(&MockOracleKey::Timestamp)
+                    .unwrap_or(env.ledger().timestamp()),
+            })
+        }
+
+        pub fn set_price(env: Env, price: i128, timestamp: u64) {
+            env.storage().instance().set(&MockOracleKey::Price, &price);
+            env.storage().instance().set(&MockOracleKey::Timestamp, &timestamp);
+        }
     }
 
     // ── Test setup ───────────────────────────────────────────────────────────
@@ -1527,6 +3087,17 @@ mod test {
         );
     }
 
+    /// Events the marketplace itself published during the last invocation.
+    fn marketplace_events(t: &TestEnv) -> Vec<(Vec<Val>, Val)> {
+        let mut events = Vec::new(&t.env);
+        for (emitter, topics, data) in t.env.events().all().iter() {
+            if emitter == t.marketplace_addr {
+                events.push_back((topics, data));
+            }
+        }
+        events
+    }
+
     // ── Listing tests ─────────────────────────────────────────────────────────
 
     #[test]
@@ -1543,6 +3114,7 @@ mod test {
             &token_id,
             &1000_0000000i128,
             &Currency::Xlm,
+            &0,
         );
         assert_eq!(listing_id, 0);
         assert_eq!(t.marketplace.total_listings(), 1);
@@ -1567,6 +3139,56 @@ mod test {
         assert!(!listing.active);
     }
 
+    #[test]
+    fn test_listing_lifecycle_emits_events() {
+        let t = setup();
+        let seller = Address::generate(&t.env);
+        let buyer = Address::generate(&t.env);
+        let token_id = mint_nft(&t, &seller);
+
+        let listing_id =
+            t.marketplace
+                .list_for_sale(&seller, &token_id, &1000_0000000i128, &Currency::Xlm, &0);
+        assert_eq!(
+            marketplace_events(&t),
+            vec![
+                &t.env,
+                (
+                    (symbol_short!("listed"), listing_id, seller.clone()).into_val(&t.env),
+                    (token_id, 1000_0000000i128, Currency::Xlm, false).into_val(&t.env),
+                ),
+            ]
+        );
+
+        t.marketplace.cancel_listing(&seller, &listing_id);
+        assert_eq!(
+            marketplace_events(&t),
+            vec![
+                &t.env,
+                (
+                    (symbol_short!("unlisted"), listing_id, seller.clone()).into_val(&t.env),
+                    token_id.into_val(&t.env),
+                ),
+            ]
+        );
+
+        let relist_id =
+            t.marketplace
+                .list_for_sale(&seller, &token_id, &1000_0000000i128, &Currency::Xlm, &0);
+        t.xlm_sac.mint(&buyer, &1000_0000000i128);
+        t.marketplace.buy_xlm(&buyer, &relist_id);
+        assert_eq!(
+            marketplace_events(&t),
+            vec![
+                &t.env,
+                (
+                    (symbol_short!("sold"), relist_id, buyer.clone()).into_val(&t.env),
+                    (token_id, 1000_0000000i128, Currency::Xlm).into_val(&t.env),
+                ),
+            ]
+        );
+    }
+
     #[test]
     fn test_list_and_buy_usdc() {
         let t = setup();
@@ -1580,6 +3202,7 @@ mod test {
             &token_id,
             &100_0000000i128,
             &Currency::Usdc,
+            &0,
         );
 
         t.usdc_sac.mint(&buyer, &100_0000000i128);
@@ -1591,20 +3214,191 @@ mod test {
     }
 
     #[test]
-    fn test_cancel_listing() {
+    fn test_usdc_listing_bought_in_xlm_needs_a_fresh_sane_rate() {
         let t = setup();
         let seller = Address::generate(&t.env);
+        let buyer = Address::generate(&t.env);
+        let oracle_addr = t.env.register(MockOracle, ());
+        let oracle = MockOracleClient::new(&t.env, &oracle_addr);
+        t.marketplace.configure_oracle(&oracle_addr);
+        t.xlm_sac.mint(&buyer, &1000_0000000i128);
 
         let token_id = mint_nft(&t, &seller);
         let listing_id = t.marketplace.list_for_sale(
             &seller,
             &token_id,
-            &1000_0000000i128,
-            &Currency::Xlm,
+            &100_0000000i128,
+            &Currency::Usdc,
+            &0,
         );
 
-        // Cancel listing - NFT returns to seller
-        t.marketplace.cancel_listing(&seller, &listing_id);
+        t.env.ledger().with_mut(|li| {
+            li.timestamp = 10_000;
+        });
+        oracle.set_price(&10_000_000_000_000, &(10_000 - 901));
+        assert_eq!(
+            t.marketplace.try_buy_xlm(&buyer, &listing_id),
+            Err(Ok(MarketplaceError::StaleOraclePrice))
+        );
+        oracle.set_price(&0, &10_000);
+        assert_eq!(
+            t.marketplace.try_buy_xlm(&buyer, &listing_id),
+            Err(Ok(MarketplaceError::OraclePriceOutOfBounds))
+        );
+        assert_eq!(
+            t.marketplace.try_configure_oracle_guard(&OracleGuard {
+                max_age_seconds: 0,
+                min_rate: 1,
+                max_rate: i128::MAX,
+                twap_records: 0,
+            }),
+            Err(Ok(MarketplaceError::InvalidOracleGuard))
+        );
+
+        // 100 USDC at $0.10/XLM.
+        oracle.set_price(&10_000_000_000_000, &10_000);
+        t.marketplace.buy_xlm(&buyer, &listing_id);
+        let rate_topics: Vec<Val> = (symbol_short!("xlm_rate"),).into_val(&t.env);
+        let events = marketplace_events(&t);
+        assert_eq!(events.iter().filter(|(topics, _)| *topics == rate_topics).count(), 1);
+        let (topics, data) = events.first().unwrap();
+        assert_eq!(topics, rate_topics);
+        let used: (i128, u64) = data.into_val(&t.env);
+        assert_eq!(used, (1_000_000, 10_000));
+        assert_eq!(t.xlm.balance(&seller), 1000_0000000i128);
+        assert_eq!(t.xlm.balance(&buyer), 0);
+    }
+
+    #[test]
+    fn test_buy_pays_creator_royalty() {
+        let t = setup();
+        let creator = Address::generate(&t.env);
+        let seller = Address::generate(&t.env);
+        let buyer = Address::generate(&t.env);
+
+        let token_id = mint_nft(&t, &seller);
+        let nft = MockNftClient::new(&t.env, &t.nft_addr);
+        nft.set_royalty(&token_id, &creator, &500);
+
+        let listing_id = t.marketplace.list_for_sale(
+            &seller,
+            &token_id,
+            &1000_0000000i128,
+            &Currency::Xlm,
+            &0,
+        );
+        t.xlm_sac.mint(&buyer, &1000_0000000i128);
+        t.marketplace.buy_xlm(&buyer, &listing_id);
+
+        assert_eq!(t.xlm.balance(&creator), 50_0000000i128);
+        assert_eq!(t.xlm.balance(&seller), 950_0000000i128);
+        assert_eq!(t.xlm.balance(&buyer), 0i128);
+        assert_eq!(nft.owner_of(&token_id), buyer);
+    }
+
+    #[test]
+    fn test_sale_fee_and_royalty_add_up_to_the_price() {
+        let t = setup();
+        let treasury = Address::generate(&t.env);
+        let creator = Address::generate(&t.env);
+        let seller = Address::generate(&t.env);
+        let buyer = Address::generate(&t.env);
+        assert_eq!(
+            t.marketplace.try_configure_fees(&FeeConfig {
+                treasury: treasury.clone(),
+                sale_bps: 250,
+                commission_bps: MAX_FEE_BPS + 1,
+            }),
+            Err(Ok(MarketplaceError::InvalidFee))
+        );
+        t.marketplace.configure_fees(&FeeConfig {
+            treasury: treasury.clone(),
+            sale_bps: 250,
+            commission_bps: 500,
+        });
+
+        let token_id = mint_nft(&t, &seller);
+        let nft = MockNftClient::new(&t.env, &t.nft_addr);
+        nft.set_royalty(&token_id, &creator, &500);
+        let price = 777_7777777i128;
+        let listing_id =
+            t.marketplace
+                .list_for_sale(&seller, &token_id, &price, &Currency::Usdc, &0);
+        t.usdc_sac.mint(&buyer, &price);
+        t.marketplace.buy_usdc(&buyer, &listing_id);
+
+        let fee = t.marketplace.accrued_fees(&Currency::Usdc);
+        assert_eq!(fee, price * 250 / 10_000);
+        assert_eq!(t.usdc.balance(&creator), price * 500 / 10_000);
+        assert_eq!(t.usdc.balance(&seller) + fee + t.usdc.balance(&creator), price);
+        assert_eq!(t.usdc.balance(&buyer), 0);
+
+        let treasurer = Address::generate(&t.env);
+        assert_eq!(
+            t.marketplace.try_collect_fees(&treasurer, &Currency::Usdc),
+            Err(Ok(MarketplaceError::Unauthorized))
+        );
+        t.marketplace.grant_role(&Role::Treasurer, &treasurer);
+        assert_eq!(t.marketplace.collect_fees(&treasurer, &Currency::Usdc), fee);
+        assert_eq!(t.usdc.balance(&treasury), fee);
+        assert_eq!(
+            t.marketplace.try_collect_fees(&treasurer, &Currency::Usdc),
+            Err(Ok(MarketplaceError::NoFeesAccrued))
+        );
+    }
+
+    #[test]
+    fn test_commission_fee_is_taken_before_the_escrow_split() {
+        let t = setup();
+        let treasury = Address::generate(&t.env);
+        t.marketplace.configure_fees(&FeeConfig {
+            treasury,
+            sale_bps: 0,
+            commission_bps: 500,
+        });
+        let seller = Address::generate(&t.env);
+        let buyer = Address::generate(&t.env);
+        let token_id = mint_nft(&t, &seller);
+        let listing_id = t.marketplace.list_commission_egg(
+            &seller,
+            &token_id,
+            &1000_0000000i128,
+            &Currency::Xlm,
+            &7u64,
+        );
+        t.xlm_sac.mint(&buyer, &1000_0000000i128);
+        let order_id = t.marketplace.buy_commission_xlm(
+            &buyer,
+            &listing_id,
+            &String::from_str(&t.env, "A sleepy shimeji"),
+            &String::from_str(&t.env, "https://example.com/reference.png"),
+        );
+
+        let order = t.marketplace.get_commission_order(&order_id);
+        assert_eq!(order.amount_paid, 1000_0000000i128);
+        assert_eq!(order.upfront_paid_to_seller, 475_0000000i128);
+        assert_eq!(order.escrow_remaining, 475_0000000i128);
+        assert_eq!(t.marketplace.accrued_fees(&Currency::Xlm), 50_0000000i128);
+        assert_eq!(t.xlm.balance(&seller), 475_0000000i128);
+        assert_eq!(t.xlm.balance(&t.marketplace_addr), 525_0000000i128);
+    }
+
+    #[test]
+    fn test_cancel_listing() {
+        let t = setup();
+        let seller = Address::generate(&t.env);
+
+        let token_id = mint_nft(&t, &seller);
+        let listing_id = t.marketplace.list_for_sale(
+            &seller,
+            &token_id,
+            &1000_0000000i128,
+            &Currency::Xlm,
+            &0,
+        );
+
+        // Cancel listing - NFT returns to seller
+        t.marketplace.cancel_listing(&seller, &listing_id);
 
         let nft = MockNftClient::new(&t.env, &t.nft_addr);
         assert_eq!(nft.owner_of(&token_id), seller);
@@ -1613,6 +3407,265 @@ mod test {
         assert!(!listing.active);
     }
 
+    #[test]
+    fn test_expired_listing_cannot_be_bought_and_anyone_can_return_it() {
+        let t = setup();
+        let seller = Address::generate(&t.env);
+        let buyer = Address::generate(&t.env);
+        let token_id = mint_nft(&t, &seller);
+        t.env.ledger().with_mut(|li| li.timestamp = 1_700_000_000);
+        let now = t.env.ledger().timestamp();
+        assert_eq!(
+            t.marketplace
+                .try_list_for_sale(&seller, &token_id, &100_0000000i128, &Currency::Xlm, &now),
+            Err(Ok(MarketplaceError::Expired))
+        );
+        let expires_at = now + 600;
+        let listing_id = t.marketplace.list_for_sale(
+            &seller,
+            &token_id,
+            &100_0000000i128,
+            &Currency::Xlm,
+            &expires_at,
+        );
+        assert_eq!(t.marketplace.get_listing(&listing_id).expires_at, expires_at);
+        assert_eq!(
+            t.marketplace.try_expire_listing(&listing_id),
+            Err(Ok(MarketplaceError::ListingNotExpired))
+        );
+        let forever_token = mint_nft(&t, &seller);
+        let forever = t.marketplace.list_for_sale(
+            &seller,
+            &forever_token,
+            &100_0000000i128,
+            &Currency::Xlm,
+            &0,
+        );
+
+        t.env.ledger().with_mut(|li| li.timestamp = expires_at);
+        t.xlm_sac.mint(&buyer, &100_0000000i128);
+        assert_eq!(
+            t.marketplace.try_buy_xlm(&buyer, &listing_id),
+            Err(Ok(MarketplaceError::Expired))
+        );
+        assert_eq!(
+            t.marketplace.try_update_listing_price(
+                &seller,
+                &listing_id,
+                &50_0000000i128,
+                &Currency::Xlm,
+            ),
+            Err(Ok(MarketplaceError::Expired))
+        );
+
+        t.marketplace.expire_listing(&listing_id);
+        assert_eq!(
+            marketplace_events(&t),
+            vec![
+                &t.env,
+                (
+                    (symbol_short!("expired"), listing_id, seller.clone()).into_val(&t.env),
+                    token_id.into_val(&t.env),
+                ),
+            ]
+        );
+        let nft = MockNftClient::new(&t.env, &t.nft_addr);
+        assert_eq!(nft.owner_of(&token_id), seller);
+        assert!(!t.marketplace.get_listing(&listing_id).active);
+        assert_eq!(t.xlm.balance(&buyer), 100_0000000i128);
+        // A listing without an expiry never becomes expirable.
+        assert_eq!(
+            t.marketplace.try_expire_listing(&forever),
+            Err(Ok(MarketplaceError::ListingNotExpired))
+        );
+    }
+
+    #[test]
+    fn test_seller_updates_listing_price_in_place() {
+        let t = setup();
+        let seller = Address::generate(&t.env);
+        let buyer = Address::generate(&t.env);
+        let token_id = mint_nft(&t, &seller);
+        let listing_id =
+            t.marketplace
+                .list_for_sale(&seller, &token_id, &1000_0000000i128, &Currency::Xlm, &0);
+
+        assert_eq!(
+            t.marketplace
+                .try_update_listing_price(&buyer, &listing_id, &1i128, &Currency::Xlm),
+            Err(Ok(MarketplaceError::NotSeller))
+        );
+        assert_eq!(
+            t.marketplace
+                .try_update_listing_price(&seller, &listing_id, &0i128, &Currency::Xlm),
+            Err(Ok(MarketplaceError::InvalidPrice))
+        );
+        t.marketplace
+            .update_listing_price(&seller, &listing_id, &80_0000000i128, &Currency::Usdc);
+        assert_eq!(
+            marketplace_events(&t),
+            vec![
+                &t.env,
+                (
+                    (symbol_short!("repriced"), listing_id, seller.clone()).into_val(&t.env),
+                    (80_0000000i128, Currency::Usdc).into_val(&t.env),
+                ),
+            ]
+        );
+
+        let listing = t.marketplace.get_listing(&listing_id);
+        assert_eq!((listing.price, listing.currency), (80_0000000i128, Currency::Usdc));
+        assert_eq!(t.marketplace.total_listings(), 1);
+        t.usdc_sac.mint(&buyer, &80_0000000i128);
+        t.marketplace.buy_usdc(&buyer, &listing_id);
+        assert_eq!(t.usdc.balance(&seller), 80_0000000i128);
+    }
+
+    #[test]
+    fn test_bundle_sells_every_token_at_once_with_fee_and_royalties() {
+        let t = setup();
+        let treasury = Address::generate(&t.env);
+        let creator = Address::generate(&t.env);
+        let seller = Address::generate(&t.env);
+        let buyer = Address::generate(&t.env);
+        t.marketplace.configure_fees(&FeeConfig {
+            treasury,
+            sale_bps: 250,
+            commission_bps: 500,
+        });
+        let nft = MockNftClient::new(&t.env, &t.nft_addr);
+        let token_ids = vec![
+            &t.env,
+            mint_nft(&t, &seller),
+            mint_nft(&t, &seller),
+            mint_nft(&t, &seller),
+        ];
+        nft.set_royalty(&token_ids.get(1).unwrap(), &creator, &500);
+
+        let price = 300_0000000i128;
+        let bundle_id = t.marketplace.list_bundle(&seller, &token_ids, &price, &Currency::Xlm);
+        for token_id in token_ids.iter() {
+            assert_eq!(nft.owner_of(&token_id), t.marketplace_addr);
+        }
+
+        t.xlm_sac.mint(&buyer, &price);
+        t.marketplace.buy_bundle_xlm(&buyer, &bundle_id);
+        assert_eq!(
+            marketplace_events(&t),
+            vec![
+                &t.env,
+                (
+                    (symbol_short!("bndl_sold"), bundle_id, buyer.clone()).into_val(&t.env),
+                    (price, Currency::Xlm).into_val(&t.env),
+                ),
+            ]
+        );
+        for token_id in token_ids.iter() {
+            assert_eq!(nft.owner_of(&token_id), buyer);
+        }
+        let fee = t.marketplace.accrued_fees(&Currency::Xlm);
+        assert_eq!(fee, price * 250 / 10_000);
+        assert_eq!(t.xlm.balance(&creator), (price / 3) * 500 / 10_000);
+        assert_eq!(t.xlm.balance(&seller) + fee + t.xlm.balance(&creator), price);
+        assert!(!t.marketplace.get_bundle(&bundle_id).active);
+        assert_eq!(
+            t.marketplace.try_buy_bundle_xlm(&buyer, &bundle_id),
+            Err(Ok(MarketplaceError::ListingNotActive))
+        );
+    }
+
+    #[test]
+    fn test_bundle_rejects_bad_sizes_and_ineligible_tokens() {
+        let t = setup();
+        let seller = Address::generate(&t.env);
+        let nft = MockNftClient::new(&t.env, &t.nft_addr);
+        let first = mint_nft(&t, &seller);
+        let egg = mint_nft(&t, &seller);
+        nft.set_token_kind(&egg, &TokenKind::CommissionEgg);
+        let price = 50_0000000i128;
+
+        let mut too_many = Vec::new(&t.env);
+        for token_id in 0..(MAX_BUNDLE_SIZE as u64 + 1) {
+            too_many.push_back(token_id);
+        }
+        for token_ids in [vec![&t.env, first], too_many] {
+            assert_eq!(
+                t.marketplace
+                    .try_list_bundle(&seller, &token_ids, &price, &Currency::Usdc),
+                Err(Ok(MarketplaceError::InvalidBundleSize))
+            );
+        }
+        assert_eq!(
+            t.marketplace
+                .try_list_bundle(&seller, &vec![&t.env, first, first], &price, &Currency::Usdc),
+            Err(Ok(MarketplaceError::TokenNotEligible))
+        );
+        assert_eq!(
+            t.marketplace
+                .try_list_bundle(&seller, &vec![&t.env, first, egg], &price, &Currency::Usdc),
+            Err(Ok(MarketplaceError::TokenNotEligible))
+        );
+        assert_eq!(nft.owner_of(&first), seller);
+        assert_eq!(nft.owner_of(&egg), seller);
+        assert_eq!(t.marketplace.total_bundles(), 0);
+    }
+
+    #[test]
+    fn test_bundle_cancellation_returns_every_token() {
+        let t = setup();
+        let seller = Address::generate(&t.env);
+        let first = mint_nft(&t, &seller);
+        let second = mint_nft(&t, &seller);
+        let price = 50_0000000i128;
+
+        let token_ids = vec![&t.env, first, second];
+        let bundle_id = t.marketplace.list_bundle(&seller, &token_ids, &price, &Currency::Usdc);
+        assert_eq!(t.marketplace.total_bundles(), 1);
+        let stranger = Address::generate(&t.env);
+        assert_eq!(
+            t.marketplace.try_cancel_bundle(&stranger, &bundle_id),
+            Err(Ok(MarketplaceError::NotSeller))
+        );
+
+        t.marketplace.pause(&t.marketplace.admin(), &PauseFlag::Trading);
+        t.marketplace.cancel_bundle(&seller, &bundle_id);
+        let nft = MockNftClient::new(&t.env, &t.nft_addr);
+        assert_eq!(nft.owner_of(&first), seller);
+        assert_eq!(nft.owner_of(&second), seller);
+        assert!(!t.marketplace.get_bundle(&bundle_id).active);
+    }
+
+    #[test]
+    fn test_trading_pause_blocks_buys_but_not_cancellations() {
+        let t = setup();
+        let seller = Address::generate(&t.env);
+        let buyer = Address::generate(&t.env);
+        let token_id = mint_nft(&t, &seller);
+        let listing_id =
+            t.marketplace
+                .list_for_sale(&seller, &token_id, &1000_0000000i128, &Currency::Xlm, &0);
+        t.xlm_sac.mint(&buyer, &1000_0000000i128);
+
+        assert_eq!(
+            t.marketplace.try_pause(&buyer, &PauseFlag::Trading),
+            Err(Ok(MarketplaceError::Unauthorized))
+        );
+        t.marketplace.pause(&t.marketplace.admin(), &PauseFlag::Trading);
+        assert_eq!(
+            t.marketplace.try_buy_xlm(&buyer, &listing_id),
+            Err(Ok(MarketplaceError::Paused))
+        );
+        assert_eq!(
+            t.marketplace
+                .try_list_for_sale(&seller, &token_id, &1i128, &Currency::Xlm, &0),
+            Err(Ok(MarketplaceError::Paused))
+        );
+
+        t.marketplace.cancel_listing(&seller, &listing_id);
+        assert!(!t.marketplace.get_listing(&listing_id).active);
+        assert_eq!(t.xlm.balance(&buyer), 1000_0000000i128);
+    }
+
     #[test]
     fn test_list_commission_and_buy_xlm_creates_order() {
         let t = setup();
@@ -1657,6 +3710,7 @@ mod test {
         assert_eq!(order.status, CommissionOrderStatus::Accepted);
 
         let nft = MockNftClient::new(&t.env, &t.nft_addr);
+        assert!(nft.is_commission_locked(&token_id));
         nft.update_token_uri(&token_id, &String::from_str(&t.env, "ipfs://shimeji/final-1.json"));
         t.marketplace.mark_commission_delivered(&seller, &order_id);
         let updated = t.marketplace.get_commission_order(&order_id);
@@ -1667,6 +3721,7 @@ mod test {
         assert_eq!(completed.status, CommissionOrderStatus::Completed);
         assert_eq!(t.xlm.balance(&seller), 1000_0000000i128);
         assert_eq!(t.xlm.balance(&t.marketplace_addr), 0i128);
+        assert!(!nft.is_commission_locked(&token_id));
     }
 
     #[test]
@@ -1715,7 +3770,6 @@ mod test {
     }
 
     #[test]
-    #[should_panic(expected = "seller already has an active commission egg listing")]
     fn test_cannot_list_second_active_commission_egg() {
         let t = setup();
         let seller = Address::generate(&t.env);
@@ -1730,17 +3784,19 @@ mod test {
             &7u64,
         );
 
-        t.marketplace.list_commission_egg(
-            &seller,
-            &token_b,
-            &1000_0000000i128,
-            &Currency::Xlm,
-            &7u64,
+        assert_eq!(
+            t.marketplace.try_list_commission_egg(
+                &seller,
+                &token_b,
+                &1000_0000000i128,
+                &Currency::Xlm,
+                &7u64,
+            ),
+            Err(Ok(MarketplaceError::CommissionEggAlreadyListed))
         );
     }
 
     #[test]
-    #[should_panic(expected = "seller has a pending commission order")]
     fn test_cannot_list_new_commission_egg_while_order_pending() {
         let t = setup();
         let seller = Address::generate(&t.env);
@@ -1763,12 +3819,15 @@ mod test {
         t.marketplace
             .buy_commission_xlm(&buyer, &listing_id, &intention, &reference);
 
-        t.marketplace.list_commission_egg(
-            &seller,
-            &token_b,
-            &1000_0000000i128,
-            &Currency::Xlm,
-            &7u64,
+        assert_eq!(
+            t.marketplace.try_list_commission_egg(
+                &seller,
+                &token_b,
+                &1000_0000000i128,
+                &Currency::Xlm,
+                &7u64,
+            ),
+            Err(Ok(MarketplaceError::CommissionOrderPending))
         );
     }
 
@@ -1795,14 +3854,29 @@ mod test {
         let order_id =
             t.marketplace
                 .buy_commission_xlm(&buyer, &listing_id, &intention, &reference);
+        let (topics, data) = marketplace_events(&t).last().unwrap();
+        assert_eq!(topics, (symbol_short!("order"), order_id).into_val(&t.env));
+        let status: CommissionOrderStatus = data.into_val(&t.env);
+        assert_eq!(status, CommissionOrderStatus::Accepted);
 
         assert_eq!(t.xlm.balance(&buyer), 0i128);
         assert_eq!(t.xlm.balance(&seller), 500_0000000i128);
         assert_eq!(t.xlm.balance(&t.marketplace_addr), 500_0000000i128);
 
         t.marketplace.refund_commission_order(&seller, &order_id);
+        assert_eq!(
+            marketplace_events(&t),
+            vec![
+                &t.env,
+                (
+                    (symbol_short!("order"), order_id).into_val(&t.env),
+                    CommissionOrderStatus::Refunded.into_val(&t.env),
+                ),
+            ]
+        );
         let refunded = t.marketplace.get_commission_order(&order_id);
         assert_eq!(refunded.status, CommissionOrderStatus::Refunded);
+        assert!(!MockNftClient::new(&t.env, &t.nft_addr).is_commission_locked(&token_a));
         assert_eq!(t.xlm.balance(&buyer), 500_0000000i128);
         assert_eq!(t.xlm.balance(&t.marketplace_addr), 0i128);
 
@@ -1860,7 +3934,6 @@ mod test {
     }
 
     #[test]
-    #[should_panic(expected = "use buy_commission_xlm for commission listings")]
     fn test_regular_buy_rejects_commission_listing() {
         let t = setup();
         let seller = Address::generate(&t.env);
@@ -1876,7 +3949,10 @@ mod test {
         );
 
         t.xlm_sac.mint(&buyer, &1000_0000000i128);
-        t.marketplace.buy_xlm(&buyer, &listing_id);
+        assert_eq!(
+            t.marketplace.try_buy_xlm(&buyer, &listing_id),
+            Err(Ok(MarketplaceError::CommissionListing))
+        );
     }
 
     #[test]
@@ -1939,10 +4015,345 @@ mod test {
         assert!(!t.marketplace.get_swap_bid(&carol_bid).active);
     }
 
+    // ── Offers ────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_owner_accepts_offer_and_escrow_pays_out_like_a_sale() {
+        let t = setup();
+        let treasury = Address::generate(&t.env);
+        let creator = Address::generate(&t.env);
+        let owner = Address::generate(&t.env);
+        let buyer = Address::generate(&t.env);
+        t.marketplace.configure_fees(&FeeConfig {
+            treasury,
+            sale_bps: 250,
+            commission_bps: 500,
+        });
+        let token_id = mint_nft(&t, &owner);
+        let nft = MockNftClient::new(&t.env, &t.nft_addr);
+        nft.set_royalty(&token_id, &creator, &500);
+
+        let amount = 400_0000000i128;
+        t.usdc_sac.mint(&buyer, &amount);
+        let expires_at = t.env.ledger().timestamp() + 3600;
+        let offer_id =
+            t.marketplace
+                .make_offer(&buyer, &token_id, &amount, &Currency::Usdc, &expires_at);
+        assert_eq!(t.usdc.balance(&buyer), 0);
+        assert_eq!(t.usdc.balance(&t.marketplace_addr), amount);
+        assert_eq!(t.marketplace.get_token_offers(&token_id), vec![&t.env, offer_id]);
+        assert_eq!(t.marketplace.get_buyer_offers(&buyer), vec![&t.env, offer_id]);
+
+        assert_eq!(
+            t.marketplace.try_accept_offer(&buyer, &offer_id),
+            Err(Ok(MarketplaceError::NotSeller))
+        );
+        t.marketplace.accept_offer(&owner, &offer_id);
+        let events = marketplace_events(&t);
+        let (topics, data) = events.last().unwrap();
+        let name: Symbol = topics.get(0).unwrap().into_val(&t.env);
+        assert_eq!(name, symbol_short!("offr_acpt"));
+        let (sold_id, paid, currency): (u64, i128, Currency) = data.into_val(&t.env);
+        assert_eq!((sold_id, paid, currency), (token_id, amount, Currency::Usdc));
+
+        assert_eq!(nft.owner_of(&token_id), buyer);
+        let fee = t.marketplace.accrued_fees(&Currency::Usdc);
+        assert_eq!(fee, amount * 250 / 10_000);
+        assert_eq!(t.usdc.balance(&creator), amount * 500 / 10_000);
+        assert_eq!(t.usdc.balance(&owner) + fee + t.usdc.balance(&creator), amount);
+        assert_eq!(t.usdc.balance(&t.marketplace_addr), fee);
+        assert!(!t.marketplace.get_offer(&offer_id).active);
+        assert!(t.marketplace.get_token_offers(&token_id).is_empty());
+        assert!(t.marketplace.get_buyer_offers(&buyer).is_empty());
+        assert_eq!(
+            t.marketplace.try_accept_offer(&owner, &offer_id),
+            Err(Ok(MarketplaceError::OfferNotActive))
+        );
+    }
+
+    #[test]
+    fn test_full_offer_index_of_expired_or_lowball_offers_does_not_block_new_ones() {
+        let t = setup();
+        let owner = Address::generate(&t.env);
+        let spammer = Address::generate(&t.env);
+        let buyer = Address::generate(&t.env);
+        let token_id = mint_nft(&t, &owner);
+        t.xlm_sac.mint(&spammer, &100i128);
+        t.xlm_sac.mint(&buyer, &100_0000000i128);
+
+        let now = t.env.ledger().timestamp();
+        for _ in 0..MAX_OPEN_OFFERS {
+            t.marketplace.make_offer(&spammer, &token_id, &1i128, &Currency::Xlm, &(now + 60));
+        }
+        assert_eq!(t.marketplace.get_token_offers(&token_id).len(), MAX_OPEN_OFFERS);
+
+        // Once they expire, the stale offers make way for a real one.
+        t.env.ledger().set_timestamp(now + 60);
+        let real = t.marketplace.make_offer(
+            &buyer,
+            &token_id,
+            &10_0000000i128,
+            &Currency::Xlm,
+            &(now + 600),
+        );
+        assert_eq!(t.marketplace.get_token_offers(&token_id), vec![&t.env, real]);
+        // Their buyer still finds and reclaims them.
+        let stale = t.marketplace.get_buyer_offers(&spammer);
+        assert_eq!(stale.len(), MAX_OPEN_OFFERS);
+        for offer_id in stale.iter() {
+            t.marketplace.cancel_offer(&spammer, &offer_id);
+        }
+        assert_eq!(t.xlm.balance(&spammer), 100i128);
+
+        // A full index of live offers only takes a higher offer, which pushes the lowest out.
+        let never = now + 365 * 24 * 3600;
+        for _ in 1..MAX_OPEN_OFFERS {
+            t.marketplace.make_offer(&spammer, &token_id, &1i128, &Currency::Xlm, &never);
+        }
+        assert_eq!(
+            t.marketplace.try_make_offer(&buyer, &token_id, &1i128, &Currency::Xlm, &never),
+            Err(Ok(MarketplaceError::LimitReached))
+        );
+        let lowest = t.marketplace.get_token_offers(&token_id).get(1).unwrap();
+        let higher = t.marketplace.make_offer(&buyer, &token_id, &2i128, &Currency::Xlm, &never);
+        assert_eq!(
+            marketplace_events(&t).first().unwrap().0,
+            (symbol_short!("offr_evct"), lowest, spammer.clone()).into_val(&t.env)
+        );
+        let index = t.marketplace.get_token_offers(&token_id);
+        assert_eq!(index.len(), MAX_OPEN_OFFERS);
+        assert!(!index.contains(lowest));
+        assert!(index.contains(higher));
+        assert!(t.marketplace.get_buyer_offers(&spammer).contains(lowest));
+        t.marketplace.cancel_offer(&spammer, &lowest);
+    }
+
+    #[test]
+    fn test_evicted_offer_cannot_be_accepted() {
+        let t = setup();
+        let owner = Address::generate(&t.env);
+        let spammer = Address::generate(&t.env);
+        let buyer = Address::generate(&t.env);
+        let token_id = mint_nft(&t, &owner);
+        t.xlm_sac.mint(&spammer, &100i128);
+        t.xlm_sac.mint(&buyer, &100i128);
+
+        let expires_at = t.env.ledger().timestamp() + 600;
+        let evicted =
+            t.marketplace
+                .make_offer(&spammer, &token_id, &1i128, &Currency::Xlm, &expires_at);
+        for _ in 1..MAX_OPEN_OFFERS {
+            t.marketplace.make_offer(&spammer, &token_id, &2i128, &Currency::Xlm, &expires_at);
+        }
+        t.marketplace.make_offer(&buyer, &token_id, &3i128, &Currency::Xlm, &expires_at);
+        assert!(!t.marketplace.get_token_offers(&token_id).contains(evicted));
+        assert!(t.marketplace.get_offer(&evicted).active);
+
+        assert_eq!(
+            t.marketplace.try_accept_offer(&owner, &evicted),
+            Err(Ok(MarketplaceError::OfferNotActive))
+        );
+        let nft = MockNftClient::new(&t.env, &t.nft_addr);
+        assert_eq!(nft.owner_of(&token_id), owner);
+
+        t.marketplace.cancel_offer(&spammer, &evicted);
+        assert_eq!(t.xlm.balance(&spammer), 100i128 - 2 * (MAX_OPEN_OFFERS as i128 - 1));
+    }
+
+    #[test]
+    fn test_buyer_cancels_offers_before_or_after_expiry() {
+        let t = setup();
+        let owner = Address::generate(&t.env);
+        let buyer = Address::generate(&t.env);
+        let other_buyer = Address::generate(&t.env);
+        let token_id = mint_nft(&t, &owner);
+        t.xlm_sac.mint(&buyer, &300_0000000i128);
+        t.xlm_sac.mint(&other_buyer, &50_0000000i128);
+
+        let now = t.env.ledger().timestamp();
+        assert_eq!(
+            t.marketplace
+                .try_make_offer(&buyer, &token_id, &100_0000000i128, &Currency::Xlm, &now),
+            Err(Ok(MarketplaceError::Expired))
+        );
+        let expires_at = now + 600;
+        let first = t.marketplace.make_offer(
+            &buyer,
+            &token_id,
+            &100_0000000i128,
+            &Currency::Xlm,
+            &expires_at,
+        );
+        let second = t.marketplace.make_offer(
+            &buyer,
+            &token_id,
+            &200_0000000i128,
+            &Currency::Xlm,
+            &expires_at,
+        );
+        let other = t.marketplace.make_offer(
+            &other_buyer,
+            &token_id,
+            &50_0000000i128,
+            &Currency::Xlm,
+            &expires_at,
+        );
+        assert_eq!(t.marketplace.get_token_offers(&token_id), vec![&t.env, first, second, other]);
+        assert_eq!(t.marketplace.get_buyer_offers(&buyer), vec![&t.env, first, second]);
+        assert_eq!(t.marketplace.total_offers(), 3);
+
+        assert_eq!(
+            t.marketplace.try_cancel_offer(&other_buyer, &first),
+            Err(Ok(MarketplaceError::NotBuyer))
+        );
+        // Cancelling stays open while trading is paused.
+        let admin = t.marketplace.admin();
+        t.marketplace.pause(&admin, &PauseFlag::Trading);
+        t.marketplace.cancel_offer(&buyer, &first);
+        assert_eq!(t.xlm.balance(&buyer), 100_0000000i128);
+        assert_eq!(t.marketplace.get_buyer_offers(&buyer), vec![&t.env, second]);
+        t.marketplace.unpause(&admin, &PauseFlag::Trading);
+
+        t.env.ledger().with_mut(|li| li.timestamp = expires_at);
+        assert_eq!(
+            t.marketplace.try_accept_offer(&owner, &second),
+            Err(Ok(MarketplaceError::Expired))
+        );
+        t.marketplace.cancel_offer(&buyer, &second);
+        t.marketplace.cancel_offer(&other_buyer, &other);
+        assert_eq!(t.xlm.balance(&buyer), 300_0000000i128);
+        assert_eq!(t.xlm.balance(&other_buyer), 50_0000000i128);
+        assert_eq!(t.xlm.balance(&t.marketplace_addr), 0);
+        assert!(t.marketplace.get_token_offers(&token_id).is_empty());
+        assert_eq!(
+            t.marketplace.try_cancel_offer(&buyer, &second),
+            Err(Ok(MarketplaceError::OfferNotActive))
+        );
+    }
+
+    #[test]
+    fn test_collection_offer_fills_matching_tokens_and_refunds_the_rest() {
+        let t = setup();
+        let creator = Address::generate(&t.env);
+        let other_creator = Address::generate(&t.env);
+        let holder = Address::generate(&t.env);
+        let buyer = Address::generate(&t.env);
+        let nft = MockNftClient::new(&t.env, &t.nft_addr);
+        let finished = mint_nft(&t, &creator);
+        let egg = mint_nft(&t, &creator);
+        nft.set_token_kind(&egg, &TokenKind::CommissionEgg);
+        let foreign = mint_nft(&t, &other_creator);
+        for token_id in [finished, egg, foreign] {
+            let owner = nft.owner_of(&token_id);
+            nft.transfer(&owner, &holder, &token_id);
+        }
+
+        let price = 50_0000000i128;
+        t.xlm_sac.mint(&buyer, &(price * 3));
+        let scope = CollectionScope::CreatorKind(creator.clone(), TokenKind::Finished);
+        let expires_at = t.env.ledger().timestamp() + 3600;
+        assert_eq!(
+            t.marketplace.try_make_collection_offer(
+                &buyer,
+                &scope,
+                &price,
+                &Currency::Xlm,
+                &0,
+                &expires_at,
+            ),
+            Err(Ok(MarketplaceError::LimitReached))
+        );
+        let offer_id = t.marketplace.make_collection_offer(
+            &buyer,
+            &scope,
+            &price,
+            &Currency::Xlm,
+            &3,
+            &expires_at,
+        );
+        assert_eq!(t.xlm.balance(&t.marketplace_addr), price * 3);
+        assert_eq!(t.marketplace.get_buyer_collection_offers(&buyer), vec![&t.env, offer_id]);
+
+        for token_id in [egg, foreign] {
+            assert_eq!(
+                t.marketplace.try_fill_collection_offer(&holder, &offer_id, &token_id),
+                Err(Ok(MarketplaceError::TokenNotEligible))
+            );
+        }
+        assert_eq!(
+            t.marketplace.try_fill_collection_offer(&creator, &offer_id, &finished),
+            Err(Ok(MarketplaceError::NotSeller))
+        );
+        t.marketplace.fill_collection_offer(&holder, &offer_id, &finished);
+        let events = marketplace_events(&t);
+        let (topics, data) = events.last().unwrap();
+        let name: Symbol = topics.get(0).unwrap().into_val(&t.env);
+        assert_eq!(name, symbol_short!("col_fill"));
+        let (sold_id, paid, _currency, filled): (u64, i128, Currency, u32) =
+            data.into_val(&t.env);
+        assert_eq!((sold_id, paid, filled), (finished, price, 1));
+        assert_eq!(nft.owner_of(&finished), buyer);
+        assert_eq!(t.xlm.balance(&holder), price);
+        assert!(t.marketplace.get_collection_offer(&offer_id).active);
+
+        assert_eq!(t.marketplace.cancel_collection_offer(&buyer, &offer_id), price * 2);
+        assert_eq!(t.xlm.balance(&buyer), price * 2);
+        assert_eq!(t.xlm.balance(&t.marketplace_addr), 0);
+        assert!(t.marketplace.get_buyer_collection_offers(&buyer).is_empty());
+        assert_eq!(
+            t.marketplace.try_cancel_collection_offer(&buyer, &offer_id),
+            Err(Ok(MarketplaceError::OfferNotActive))
+        );
+    }
+
+    #[test]
+    fn test_collection_offer_closes_once_its_quantity_is_filled() {
+        let t = setup();
+        let creator = Address::generate(&t.env);
+        let buyer = Address::generate(&t.env);
+        let tokens = [
+            mint_nft(&t, &creator),
+            mint_nft(&t, &creator),
+            mint_nft(&t, &creator),
+        ];
+
+        let price = 20_0000000i128;
+        t.usdc_sac.mint(&buyer, &(price * 2));
+        let by_creator = CollectionScope::Creator(creator.clone());
+        let expires_at = t.env.ledger().timestamp() + 3600;
+        let offer_id = t.marketplace.make_collection_offer(
+            &buyer,
+            &by_creator,
+            &price,
+            &Currency::Usdc,
+            &2,
+            &expires_at,
+        );
+
+        t.marketplace.fill_collection_offer(&creator, &offer_id, &tokens[0]);
+        t.env.ledger().with_mut(|li| li.timestamp = expires_at);
+        assert_eq!(
+            t.marketplace.try_fill_collection_offer(&creator, &offer_id, &tokens[1]),
+            Err(Ok(MarketplaceError::Expired))
+        );
+        t.env.ledger().with_mut(|li| li.timestamp = expires_at - 1);
+        t.marketplace.fill_collection_offer(&creator, &offer_id, &tokens[1]);
+
+        let offer = t.marketplace.get_collection_offer(&offer_id);
+        assert_eq!(offer.filled, 2);
+        assert!(!offer.active);
+        assert_eq!(t.usdc.balance(&creator), price * 2);
+        assert_eq!(t.usdc.balance(&t.marketplace_addr), 0);
+        assert!(t.marketplace.get_buyer_collection_offers(&buyer).is_empty());
+        assert_eq!(
+            t.marketplace.try_fill_collection_offer(&creator, &offer_id, &tokens[2]),
+            Err(Ok(MarketplaceError::OfferNotActive))
+        );
+    }
+
     // ── Error cases ───────────────────────────────────────────────────────────
 
     #[test]
-    #[should_panic(expected = "listing is not active")]
     fn test_buy_inactive_listing() {
         let t = setup();
         let seller = Address::generate(&t.env);
@@ -1954,31 +4365,149 @@ mod test {
             &token_id,
             &1000_0000000i128,
             &Currency::Xlm,
+            &0,
         );
         t.marketplace.cancel_listing(&seller, &listing_id);
 
         t.xlm_sac.mint(&buyer, &1000_0000000i128);
-        t.marketplace.buy_xlm(&buyer, &listing_id);
+        assert_eq!(
+            t.marketplace.try_buy_xlm(&buyer, &listing_id),
+            Err(Ok(MarketplaceError::ListingNotActive))
+        );
     }
 
     #[test]
-    #[should_panic(expected = "intention cannot be empty")]
     fn test_open_swap_listing_requires_intention() {
         let t = setup();
         let alice = Address::generate(&t.env);
         let alice_token = mint_nft(&t, &alice);
-        t.marketplace
-            .create_swap_listing(&alice, &alice_token, &String::from_str(&t.env, ""));
+        assert_eq!(
+            t.marketplace.try_create_swap_listing(
+                &alice,
+                &alice_token,
+                &String::from_str(&t.env, "")
+            ),
+            Err(Ok(MarketplaceError::EmptyIntention))
+        );
     }
 
     #[test]
-    #[should_panic(expected = "already initialized")]
     fn test_double_initialize() {
         let t = setup();
         let admin = Address::generate(&t.env);
         let nft = Address::generate(&t.env);
         let usdc = Address::generate(&t.env);
         let xlm = Address::generate(&t.env);
-        t.marketplace.initialize(&admin, &nft, &usdc, &xlm);
+        assert_eq!(
+            t.marketplace.try_initialize(&admin, &nft, &usdc, &xlm),
+            Err(Ok(MarketplaceError::AlreadyInitialized))
+        );
+    }
+
+    #[test]
+    fn test_bumped_listing_outlives_default_ttl() {
+        let t = setup();
+        let seller = Address::generate(&t.env);
+        let token_id = mint_nft(&t, &seller);
+        let listing_id =
+            t.marketplace
+                .list_for_sale(&seller, &token_id, &1000_0000000i128, &Currency::Xlm, &0);
+
+        // Far past the network's minimum persistent TTL, inside one bump.
+        t.env.ledger().with_mut(|li| li.sequence_number += 20 * DAY_IN_LEDGERS);
+        t.marketplace.bump_listing(&listing_id);
+        t.env.ledger().with_mut(|li| li.sequence_number += 20 * DAY_IN_LEDGERS);
+
+        let listing = t.marketplace.get_listing(&listing_id);
+        assert_eq!(listing.seller, seller);
+        assert!(listing.active);
+        assert_eq!(
+            t.marketplace.try_bump_listing(&(listing_id + 1)),
+            Err(Ok(MarketplaceError::ListingNotFound))
+        );
+    }
+
+    // ── Upgrades ──────────────────────────────────────────────────────────────
+
+    /// Smallest module the host accepts as contract code: no exports, just the
+    /// `contractenvmetav0` section declaring protocol 22.
+    const EMPTY_CONTRACT_WASM: [u8; 40] = [
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic + version
+        0x00, 0x1e, 0x11, // custom section, 30 bytes, 17-byte name
+        b'c', b'o', b'n', b't', b'r', b'a', b'c', b't', // "contract"
+        b'e', b'n', b'v', b'm', b'e', b't', b'a', b'v', b'0', // "envmetav0"
+        0x00, 0x00, 0x00, 0x00, // ScEnvMetaKind::InterfaceVersion
+        0x00, 0x00, 0x00, 0x16, // protocol 22
+        0x00, 0x00, 0x00, 0x00, // pre-release 0
+    ];
+
+    #[test]
+    fn test_v1_listings_are_read_as_never_expiring() {
+        let t = setup();
+        let seller = Address::generate(&t.env);
+        let legacy = ListingInfoV1 {
+            seller: seller.clone(),
+            token_id: 7,
+            price: 250_0000000i128,
+            currency: Currency::Usdc,
+            commission_eta_days: 0,
+            is_commission_egg: false,
+            active: true,
+        };
+        t.env.as_contract(&t.marketplace_addr, || {
+            t.env.storage().persistent().set(&DataKey::Listing(0), &legacy);
+            t.env.storage().instance().set(&DataKey::NextListingId, &1u64);
+            t.env.storage().instance().set(&DataKey::SchemaVersion, &1u32);
+        });
+
+        // Readable straight after the wasm upgrade, before migrate has run.
+        let listing = t.marketplace.get_listing(&0);
+        assert_eq!(listing.seller, seller);
+        assert_eq!(listing.price, 250_0000000i128);
+        assert_eq!(listing.currency, Currency::Usdc);
+        assert!(listing.active);
+        assert_eq!(listing.expires_at, 0);
+
+        // The next write stores the current layout.
+        t.marketplace.update_listing_price(&seller, &0, &300_0000000i128, &Currency::Usdc);
+        t.env.as_contract(&t.marketplace_addr, || {
+            let stored: ListingInfo =
+                t.env.storage().persistent().get(&DataKey::Listing(0)).unwrap();
+            assert_eq!(stored.price, 300_0000000i128);
+            assert_eq!(stored.expires_at, 0);
+        });
+        assert_eq!(t.marketplace.migrate(), SCHEMA_VERSION);
+        assert_eq!(t.marketplace.migrate(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_upgrade_keeps_active_listings() {
+        let t = setup();
+        let seller = Address::generate(&t.env);
+        let token_id = mint_nft(&t, &seller);
+        let listing_id =
+            t.marketplace
+                .list_for_sale(&seller, &token_id, &1000_0000000i128, &Currency::Xlm, &0);
+        assert_eq!(t.marketplace.schema_version(), SCHEMA_VERSION);
+
+        let wasm = soroban_sdk::Bytes::from_slice(&t.env, &EMPTY_CONTRACT_WASM);
+        let new_hash = t.env.deployer().upload_contract_wasm(wasm);
+        t.marketplace.upgrade(&new_hash);
+
+        // The new code exports nothing, so read storage directly.
+        t.env.as_contract(&t.marketplace_addr, || {
+            let listing: ListingInfo = t
+                .env
+                .storage()
+                .persistent()
+                .get(&DataKey::Listing(listing_id))
+                .unwrap();
+            assert_eq!(listing.seller, seller);
+            assert_eq!(listing.token_id, token_id);
+            assert_eq!(listing.price, 1000_0000000i128);
+            assert!(listing.active);
+            let next_id: u64 = t.env.storage().instance().get(&DataKey::NextListingId).unwrap();
+            assert_eq!(next_id, listing_id + 1);
+        });
     }
 }
@@ -1,6 +1,50 @@
 #![no_std]
 
-use soroban_sdk::{contract, contractimpl, contracttype, Address, Env, String};
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, Address, BytesN, Env,
+    IntoVal, String, TryFromVal, Val, Vec,
+};
+pub use shimeji_common::Role;
+
+/// Upper bound on page size for enumeration queries.
+const MAX_PAGE_LIMIT: u32 = 100;
+/// Creator royalties are capped at 10% of the sale price.
+const MAX_ROYALTY_BPS: u32 = 1_000;
+const BPS_DENOMINATOR: i128 = 10_000;
+/// Storage layout version written by this code. Bump it together with a new
+/// step in `migrate` whenever a release changes how existing records are stored.
+const SCHEMA_VERSION: u32 = 2;
+/// Ledgers close roughly every 5 seconds.
+const DAY_IN_LEDGERS: u32 = 17_280;
+/// Entries touched by a call are topped back up to ~30 days of TTL once they
+/// drop under ~29 days, so anything in active use never nears archival.
+const TTL_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const TTL_THRESHOLD: u32 = TTL_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum NftError {
+    AlreadyInitialized = 1,
+    TokenNotFound = 2,
+    NotOwner = 3,
+    NotCreator = 4,
+    CreatorUpdatesDisabled = 5,
+    EmptyTokenUri = 6,
+    TokenUriTooShort = 7,
+    RoyaltyTooHigh = 8,
+    NotApproved = 9,
+    NotOwnerOrOperator = 10,
+    ApproveToOwner = 11,
+    SelfOperator = 12,
+    IndexOutOfBounds = 13,
+    NotCommissionManager = 14,
+    CommissionLocked = 15,
+    UnsupportedSchemaVersion = 16,
+    NoPendingAdmin = 17,
+    AdminRoleNotGrantable = 18,
+    MissingRole = 19,
+}
 
 #[contracttype]
 #[derive(Clone, Debug, PartialEq)]
@@ -12,6 +56,7 @@ pub enum TokenKind {
 #[contracttype]
 pub enum DataKey {
     Admin,
+    /// Schema v1 single-slot roles; `migrate` folds them into `RoleMember`.
     Minter,
     MetadataUpdater,
     NextTokenId,
@@ -20,18 +65,84 @@ pub enum DataKey {
     TokenCreator(u64),
     TokenKind(u64),
     CreatorCanUpdateMetadata(u64),
+    Approved(u64),
+    OperatorApproval(Address, Address),
+    OwnerBalance(Address),
+    /// Position `index` in an owner's token list.
+    OwnerToken(Address, u64),
+    /// Position of a token inside its current owner's list.
+    OwnerTokenIndex(u64),
+    CirculatingSupply,
+    /// Position `index` in the list of all live (unburned) tokens.
+    AllTokens(u64),
+    AllTokensIndex(u64),
+    /// Contracts allowed to flag eggs as tied to an open commission.
+    CommissionManager(Address),
+    CommissionLocked(u64),
+    TokenRoyaltyBps(u64),
+    SchemaVersion,
+    PendingAdmin,
+    RoleMember(Role, Address),
+    /// Number of addresses holding a role, so "none granted yet" is cheap to check.
+    RoleCount(Role),
 }
 
 #[contract]
 pub struct ShimejiNft;
 
-fn validate_token_uri(token_uri: &String) {
+fn extend_instance(env: &Env) {
+    env.storage()
+        .instance()
+        .extend_ttl(TTL_THRESHOLD, TTL_BUMP_AMOUNT);
+}
+
+/// Reads a persistent entry and, if it exists, tops its TTL back up.
+fn read_entry<V: TryFromVal<Env, Val>>(env: &Env, key: &DataKey) -> Option<V> {
+    let value = env.storage().persistent().get(key);
+    if value.is_some() {
+        env.storage()
+            .persistent()
+            .extend_ttl(key, TTL_THRESHOLD, TTL_BUMP_AMOUNT);
+        extend_instance(env);
+    }
+    value
+}
+
+/// Writes a persistent entry with a full TTL.
+fn write_entry<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+    env.storage().persistent().set(key, value);
+    env.storage()
+        .persistent()
+        .extend_ttl(key, TTL_THRESHOLD, TTL_BUMP_AMOUNT);
+    extend_instance(env);
+}
+
+/// Tops up `key`'s TTL if it exists; returns whether it did.
+fn extend_entry(env: &Env, key: &DataKey) -> bool {
+    if !env.storage().persistent().has(key) {
+        return false;
+    }
+    env.storage()
+        .persistent()
+        .extend_ttl(key, TTL_THRESHOLD, TTL_BUMP_AMOUNT);
+    true
+}
+
+fn validate_token_uri(token_uri: &String) -> Result<(), NftError> {
     if token_uri.is_empty() {
-        panic!("token_uri cannot be empty");
+        return Err(NftError::EmptyTokenUri);
     }
     if token_uri.len() < 7 {
-        panic!("token_uri too short");
+        return Err(NftError::TokenUriTooShort);
     }
+    Ok(())
+}
+
+fn validate_royalty_bps(royalty_bps: u32) -> Result<(), NftError> {
+    if royalty_bps > MAX_ROYALTY_BPS {
+        return Err(NftError::RoyaltyTooHigh);
+    }
+    Ok(())
 }
 
 fn token_exists(env: &Env, token_id: u64) -> bool {
@@ -40,10 +151,130 @@ fn token_exists(env: &Env, token_id: u64) -> bool {
         .has(&DataKey::TokenOwner(token_id))
 }
 
-fn require_existing_token(env: &Env, token_id: u64) {
+fn require_existing_token(env: &Env, token_id: u64) -> Result<(), NftError> {
     if !token_exists(env, token_id) {
-        panic!("token does not exist");
+        return Err(NftError::TokenNotFound);
     }
+    Ok(())
+}
+
+fn balance(env: &Env, owner: &Address) -> u64 {
+    read_entry(env, &DataKey::OwnerBalance(owner.clone())).unwrap_or(0u64)
+}
+
+fn add_token_to_owner(env: &Env, owner: &Address, token_id: u64) {
+    let index = balance(env, owner);
+    write_entry(env, &DataKey::OwnerToken(owner.clone(), index), &token_id);
+    write_entry(env, &DataKey::OwnerTokenIndex(token_id), &index);
+    write_entry(env, &DataKey::OwnerBalance(owner.clone()), &(index + 1));
+}
+
+/// Swap-and-pop removal so an owner's list stays dense.
+fn remove_token_from_owner(env: &Env, owner: &Address, token_id: u64) {
+    let last_index = balance(env, owner) - 1;
+    let index: u64 = read_entry(env, &DataKey::OwnerTokenIndex(token_id)).unwrap();
+
+    if index != last_index {
+        let last_token: u64 = read_entry(env, &DataKey::OwnerToken(owner.clone(), last_index))
+            .unwrap();
+        write_entry(env, &DataKey::OwnerToken(owner.clone(), index), &last_token);
+        write_entry(env, &DataKey::OwnerTokenIndex(last_token), &index);
+    }
+
+    env.storage()
+        .persistent()
+        .remove(&DataKey::OwnerToken(owner.clone(), last_index));
+    env.storage()
+        .persistent()
+        .remove(&DataKey::OwnerTokenIndex(token_id));
+    if last_index == 0 {
+        env.storage()
+            .persistent()
+            .remove(&DataKey::OwnerBalance(owner.clone()));
+    } else {
+        write_entry(env, &DataKey::OwnerBalance(owner.clone()), &last_index);
+    }
+}
+
+fn circulating(env: &Env) -> u64 {
+    read_entry(env, &DataKey::CirculatingSupply).unwrap_or(0u64)
+}
+
+fn add_token_to_all(env: &Env, token_id: u64) {
+    let index = circulating(env);
+    write_entry(env, &DataKey::AllTokens(index), &token_id);
+    write_entry(env, &DataKey::AllTokensIndex(token_id), &index);
+    write_entry(env, &DataKey::CirculatingSupply, &(index + 1));
+}
+
+fn remove_token_from_all(env: &Env, token_id: u64) {
+    let last_index = circulating(env) - 1;
+    let index: u64 = read_entry(env, &DataKey::AllTokensIndex(token_id)).unwrap();
+
+    if index != last_index {
+        let last_token: u64 = read_entry(env, &DataKey::AllTokens(last_index)).unwrap();
+        write_entry(env, &DataKey::AllTokens(index), &last_token);
+        write_entry(env, &DataKey::AllTokensIndex(last_token), &index);
+    }
+
+    env.storage()
+        .persistent()
+        .remove(&DataKey::AllTokens(last_index));
+    env.storage()
+        .persistent()
+        .remove(&DataKey::AllTokensIndex(token_id));
+    write_entry(env, &DataKey::CirculatingSupply, &last_index);
+}
+
+fn stored_admin(env: &Env) -> Address {
+    read_entry(env, &DataKey::Admin).unwrap()
+}
+
+fn role_count(env: &Env, role: Role) -> u32 {
+    read_entry(env, &DataKey::RoleCount(role)).unwrap_or(0u32)
+}
+
+fn holds_role(env: &Env, role: Role, account: &Address) -> bool {
+    match role {
+        Role::Admin => stored_admin(env) == *account,
+        _ => env
+            .storage()
+            .persistent()
+            .has(&DataKey::RoleMember(role, account.clone())),
+    }
+}
+
+/// Until the first address is granted `role`, only the admin may act in it;
+/// afterwards the role holders have it to themselves.
+fn require_role_or_bootstrap_admin(
+    env: &Env,
+    role: Role,
+    account: &Address,
+) -> Result<(), NftError> {
+    account.require_auth();
+    let allowed = if role_count(env, role) > 0 {
+        holds_role(env, role, account)
+    } else {
+        stored_admin(env) == *account
+    };
+    if !allowed {
+        return Err(NftError::MissingRole);
+    }
+    Ok(())
+}
+
+fn add_role_member(env: &Env, role: Role, account: &Address) -> bool {
+    let key = DataKey::RoleMember(role, account.clone());
+    if env.storage().persistent().has(&key) {
+        return false;
+    }
+    write_entry(env, &key, &true);
+    write_entry(env, &DataKey::RoleCount(role), &(role_count(env, role) + 1));
+    true
+}
+
+fn is_operator_for(env: &Env, owner: &Address, operator: &Address) -> bool {
+    read_entry(env, &DataKey::OperatorApproval(owner.clone(), operator.clone())).unwrap_or(false)
 }
 
 #[contractimpl]
@@ -56,266 +287,648 @@ impl ShimejiNft {
         token_kind: TokenKind,
         creator_can_update_metadata: bool,
     ) -> u64 {
-        let token_id: u64 = env.storage().persistent().get(&DataKey::NextTokenId).unwrap();
-        env.storage()
-            .persistent()
-            .set(&DataKey::NextTokenId, &(token_id + 1));
-        env.storage()
-            .persistent()
-            .set(&DataKey::TokenOwner(token_id), &to);
-        env.storage()
-            .persistent()
-            .set(&DataKey::TokenUri(token_id), &token_uri);
-        env.storage()
-            .persistent()
-            .set(&DataKey::TokenCreator(token_id), &creator);
-        env.storage()
-            .persistent()
-            .set(&DataKey::TokenKind(token_id), &token_kind);
-        env.storage().persistent().set(
+        let token_id: u64 = read_entry(env, &DataKey::NextTokenId).unwrap();
+        write_entry(env, &DataKey::NextTokenId, &(token_id + 1));
+        write_entry(env, &DataKey::TokenOwner(token_id), &to);
+        write_entry(env, &DataKey::TokenUri(token_id), &token_uri);
+        write_entry(env, &DataKey::TokenCreator(token_id), &creator);
+        write_entry(env, &DataKey::TokenKind(token_id), &token_kind);
+        write_entry(
+            env,
             &DataKey::CreatorCanUpdateMetadata(token_id),
             &creator_can_update_metadata,
         );
+        add_token_to_owner(env, &to, token_id);
+        add_token_to_all(env, token_id);
 
+        env.events().publish(
+            (symbol_short!("mint"), to),
+            (token_id, creator, token_kind),
+        );
         token_id
     }
 
-    pub fn initialize(env: Env, admin: Address) {
+    pub fn initialize(env: Env, admin: Address) -> Result<(), NftError> {
         if env.storage().persistent().has(&DataKey::Admin) {
-            panic!("already initialized");
+            return Err(NftError::AlreadyInitialized);
         }
-        env.storage().persistent().set(&DataKey::Admin, &admin);
-        env.storage().persistent().set(&DataKey::NextTokenId, &0u64);
+        write_entry(&env, &DataKey::Admin, &admin);
+        write_entry(&env, &DataKey::NextTokenId, &0u64);
+        write_entry(&env, &DataKey::SchemaVersion, &SCHEMA_VERSION);
+        Ok(())
     }
 
-    pub fn mint(env: Env, to: Address, token_uri: String) -> u64 {
-        validate_token_uri(&token_uri);
-        let admin: Address = env.storage().persistent().get(&DataKey::Admin).unwrap();
-
-        // Before a minter is configured, only admin can mint.
-        // After a minter is configured, mint is reserved for the minter (auction contract).
-        if env.storage().persistent().has(&DataKey::Minter) {
-            let minter: Address = env.storage().persistent().get(&DataKey::Minter).unwrap();
-            minter.require_auth();
-        } else {
-            admin.require_auth();
-        }
+    pub fn mint(
+        env: Env,
+        minter: Address,
+        to: Address,
+        token_uri: String,
+    ) -> Result<u64, NftError> {
+        validate_token_uri(&token_uri)?;
+        // Before a minter is granted, only admin can mint.
+        // After that, mint is reserved for Minter role holders (auction contract).
+        require_role_or_bootstrap_admin(&env, Role::Minter, &minter)?;
 
-        Self::mint_internal(
+        Ok(Self::mint_internal(
             &env,
             to.clone(),
             token_uri,
             to,
             TokenKind::Finished,
             false,
-        )
+        ))
     }
 
-    pub fn mint_commission_egg(env: Env, to: Address, creator: Address, token_uri: String) -> u64 {
-        validate_token_uri(&token_uri);
-        let admin: Address = env.storage().persistent().get(&DataKey::Admin).unwrap();
+    /// Mints a copy of a primary-sale edition to `to` on behalf of `creator`, who
+    /// is recorded as the token's creator and earns `royalty_bps` on every resale.
+    /// Restricted like `mint`; the auction contract calls it when a drop settles.
+    pub fn mint_edition(
+        env: Env,
+        minter: Address,
+        to: Address,
+        token_uri: String,
+        creator: Address,
+        royalty_bps: u32,
+    ) -> Result<u64, NftError> {
+        validate_token_uri(&token_uri)?;
+        validate_royalty_bps(royalty_bps)?;
+        require_role_or_bootstrap_admin(&env, Role::Minter, &minter)?;
+
+        let token_id = Self::mint_internal(
+            &env,
+            to,
+            token_uri,
+            creator,
+            TokenKind::Finished,
+            false,
+        );
+        Self::set_royalty(&env, token_id, royalty_bps);
+        Ok(token_id)
+    }
+
+    pub fn mint_commission_egg(
+        env: Env,
+        to: Address,
+        creator: Address,
+        token_uri: String,
+    ) -> Result<u64, NftError> {
+        validate_token_uri(&token_uri)?;
+        let admin: Address = read_entry(&env, &DataKey::Admin).unwrap();
         admin.require_auth();
 
-        Self::mint_internal(
+        Ok(Self::mint_internal(
             &env,
             to,
             token_uri,
             creator,
             TokenKind::CommissionEgg,
             true,
-        )
+        ))
     }
 
     /// Permissionless: any creator can mint their own commission egg (1 at a time, self-service).
-    pub fn create_commission_egg(env: Env, creator: Address, token_uri: String) -> u64 {
-        validate_token_uri(&token_uri);
+    /// `royalty_bps` is the creator's cut of every marketplace resale or auction.
+    pub fn create_commission_egg(
+        env: Env,
+        creator: Address,
+        token_uri: String,
+        royalty_bps: u32,
+    ) -> Result<u64, NftError> {
+        validate_token_uri(&token_uri)?;
+        validate_royalty_bps(royalty_bps)?;
         creator.require_auth();
 
-        Self::mint_internal(
+        let token_id = Self::mint_internal(
             &env,
             creator.clone(),
             token_uri,
             creator,
             TokenKind::CommissionEgg,
             true,
-        )
+        );
+        Self::set_royalty(&env, token_id, royalty_bps);
+        Ok(token_id)
     }
 
     /// Permissionless: any creator can mint their own finished NFT.
-    pub fn create_finished_nft(env: Env, creator: Address, token_uri: String) -> u64 {
-        validate_token_uri(&token_uri);
+    /// `royalty_bps` is the creator's cut of every marketplace resale or auction.
+    pub fn create_finished_nft(
+        env: Env,
+        creator: Address,
+        token_uri: String,
+        royalty_bps: u32,
+    ) -> Result<u64, NftError> {
+        validate_token_uri(&token_uri)?;
+        validate_royalty_bps(royalty_bps)?;
         creator.require_auth();
 
-        Self::mint_internal(
+        let token_id = Self::mint_internal(
             &env,
             creator.clone(),
             token_uri,
             creator,
             TokenKind::Finished,
             true,
-        )
+        );
+        Self::set_royalty(&env, token_id, royalty_bps);
+        Ok(token_id)
     }
 
-    pub fn update_token_uri(env: Env, token_id: u64, new_uri: String) {
-        validate_token_uri(&new_uri);
-        require_existing_token(&env, token_id);
-
-        // Allow admin or designated metadata_updater (e.g. commission contract)
-        if env.storage().persistent().has(&DataKey::MetadataUpdater) {
-            let updater: Address = env.storage().persistent().get(&DataKey::MetadataUpdater).unwrap();
-            updater.require_auth();
-        } else {
-            let admin: Address = env.storage().persistent().get(&DataKey::Admin).unwrap();
-            admin.require_auth();
+    fn set_royalty(env: &Env, token_id: u64, royalty_bps: u32) {
+        if royalty_bps > 0 {
+            write_entry(env, &DataKey::TokenRoyaltyBps(token_id), &royalty_bps);
         }
+    }
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::TokenUri(token_id), &new_uri);
+    pub fn update_token_uri(
+        env: Env,
+        updater: Address,
+        token_id: u64,
+        new_uri: String,
+    ) -> Result<(), NftError> {
+        validate_token_uri(&new_uri)?;
+        require_existing_token(&env, token_id)?;
+
+        // Admin until a MetadataUpdater (e.g. commission contract) is granted.
+        require_role_or_bootstrap_admin(&env, Role::MetadataUpdater, &updater)?;
+
+        write_entry(&env, &DataKey::TokenUri(token_id), &new_uri);
+        env.events()
+            .publish((symbol_short!("uri_upd"), token_id), new_uri);
+        Ok(())
     }
 
-    pub fn update_token_uri_as_creator(env: Env, creator: Address, token_id: u64, new_uri: String) {
-        validate_token_uri(&new_uri);
-        require_existing_token(&env, token_id);
+    pub fn update_token_uri_as_creator(
+        env: Env,
+        creator: Address,
+        token_id: u64,
+        new_uri: String,
+    ) -> Result<(), NftError> {
+        validate_token_uri(&new_uri)?;
+        require_existing_token(&env, token_id)?;
         creator.require_auth();
 
-        let token_creator: Address = env
-            .storage()
-            .persistent()
-            .get(&DataKey::TokenCreator(token_id))
+        let token_creator: Address = read_entry(&env, &DataKey::TokenCreator(token_id))
             .unwrap_or_else(|| {
-                env.storage()
-                    .persistent()
-                    .get(&DataKey::TokenOwner(token_id))
-                    .unwrap()
+                read_entry(&env, &DataKey::TokenOwner(token_id)).unwrap()
             });
         if token_creator != creator {
-            panic!("not token creator");
+            return Err(NftError::NotCreator);
         }
 
-        let can_update: bool = env
-            .storage()
-            .persistent()
-            .get(&DataKey::CreatorCanUpdateMetadata(token_id))
+        let can_update: bool = read_entry(&env, &DataKey::CreatorCanUpdateMetadata(token_id))
             .unwrap_or(false);
         if !can_update {
-            panic!("creator metadata updates disabled");
+            return Err(NftError::CreatorUpdatesDisabled);
         }
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::TokenUri(token_id), &new_uri);
+        write_entry(&env, &DataKey::TokenUri(token_id), &new_uri);
+        env.events()
+            .publish((symbol_short!("uri_upd"), token_id), new_uri);
+        Ok(())
     }
 
-    pub fn freeze_creator_metadata_updates(env: Env, creator: Address, token_id: u64) {
-        require_existing_token(&env, token_id);
+    pub fn freeze_creator_metadata_updates(
+        env: Env,
+        creator: Address,
+        token_id: u64,
+    ) -> Result<(), NftError> {
+        require_existing_token(&env, token_id)?;
         creator.require_auth();
 
-        let token_creator: Address = env
-            .storage()
-            .persistent()
-            .get(&DataKey::TokenCreator(token_id))
+        let token_creator: Address = read_entry(&env, &DataKey::TokenCreator(token_id))
             .unwrap_or_else(|| {
-                env.storage()
-                    .persistent()
-                    .get(&DataKey::TokenOwner(token_id))
-                    .unwrap()
+                read_entry(&env, &DataKey::TokenOwner(token_id)).unwrap()
             });
         if token_creator != creator {
-            panic!("not token creator");
+            return Err(NftError::NotCreator);
         }
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::CreatorCanUpdateMetadata(token_id), &false);
+        write_entry(&env, &DataKey::CreatorCanUpdateMetadata(token_id), &false);
+        env.events()
+            .publish((symbol_short!("uri_frz"), token_id), creator);
+        Ok(())
     }
 
-    pub fn set_minter(env: Env, minter: Address) {
-        let admin: Address = env.storage().persistent().get(&DataKey::Admin).unwrap();
-        admin.require_auth();
-        env.storage().persistent().set(&DataKey::Minter, &minter);
+    /// Destroys `token_id`. Only the current owner may burn, and commission eggs
+    /// cannot be burned while a commission contract still has them locked.
+    pub fn burn(env: Env, owner: Address, token_id: u64) -> Result<(), NftError> {
+        owner.require_auth();
+        let current_owner: Address = read_entry(&env, &DataKey::TokenOwner(token_id))
+            .ok_or(NftError::TokenNotFound)?;
+        if current_owner != owner {
+            return Err(NftError::NotOwner);
+        }
+        if env
+            .storage()
+            .persistent()
+            .has(&DataKey::CommissionLocked(token_id))
+        {
+            return Err(NftError::CommissionLocked);
+        }
+
+        remove_token_from_owner(&env, &owner, token_id);
+        remove_token_from_all(&env, token_id);
+
+        let storage = env.storage().persistent();
+        storage.remove(&DataKey::TokenOwner(token_id));
+        storage.remove(&DataKey::TokenUri(token_id));
+        storage.remove(&DataKey::TokenCreator(token_id));
+        storage.remove(&DataKey::TokenKind(token_id));
+        storage.remove(&DataKey::CreatorCanUpdateMetadata(token_id));
+        storage.remove(&DataKey::Approved(token_id));
+        storage.remove(&DataKey::TokenRoyaltyBps(token_id));
+
+        env.events().publish((symbol_short!("burn"), owner), token_id);
+        Ok(())
     }
 
-    pub fn set_metadata_updater(env: Env, updater: Address) {
-        let admin: Address = env.storage().persistent().get(&DataKey::Admin).unwrap();
+    /// Admin allow-lists a contract (e.g. the marketplace) that opens and closes commissions.
+    pub fn set_commission_manager(env: Env, manager: Address, allowed: bool) {
+        let admin: Address = read_entry(&env, &DataKey::Admin).unwrap();
         admin.require_auth();
-        env.storage().persistent().set(&DataKey::MetadataUpdater, &updater);
+        let key = DataKey::CommissionManager(manager);
+        if allowed {
+            write_entry(&env, &key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
     }
 
-    pub fn transfer(env: Env, from: Address, to: Address, token_id: u64) {
-        from.require_auth();
-        let owner: Address = env
+    /// Called by a commission manager when an order on `token_id` opens (`locked = true`)
+    /// and again when it completes or is refunded. Locked tokens cannot be burned.
+    pub fn set_commission_lock(
+        env: Env,
+        manager: Address,
+        token_id: u64,
+        locked: bool,
+    ) -> Result<(), NftError> {
+        manager.require_auth();
+        require_existing_token(&env, token_id)?;
+        if !env
             .storage()
             .persistent()
-            .get(&DataKey::TokenOwner(token_id))
-            .unwrap_or_else(|| panic!("token does not exist"));
-        if owner != from {
-            panic!("not the owner");
+            .has(&DataKey::CommissionManager(manager))
+        {
+            return Err(NftError::NotCommissionManager);
         }
-        env.storage()
-            .persistent()
-            .set(&DataKey::TokenOwner(token_id), &to);
+        let key = DataKey::CommissionLocked(token_id);
+        if locked {
+            write_entry(&env, &key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+        env.events()
+            .publish((symbol_short!("comm_lock"), token_id), locked);
+        Ok(())
     }
 
-    pub fn owner_of(env: Env, token_id: u64) -> Address {
+    pub fn is_commission_locked(env: Env, token_id: u64) -> bool {
         env.storage()
             .persistent()
-            .get(&DataKey::TokenOwner(token_id))
-            .unwrap_or_else(|| panic!("token does not exist"))
+            .has(&DataKey::CommissionLocked(token_id))
     }
 
-    pub fn token_uri(env: Env, token_id: u64) -> String {
-        env.storage()
-            .persistent()
-            .get(&DataKey::TokenUri(token_id))
-            .unwrap_or_else(|| panic!("token does not exist"))
+    pub fn admin(env: Env) -> Address {
+        stored_admin(&env)
+    }
+
+    /// First half of an admin handover: nothing changes until `new_admin`
+    /// calls `accept_admin`. A later proposal replaces an earlier one.
+    pub fn propose_admin(env: Env, new_admin: Address) {
+        let admin = stored_admin(&env);
+        admin.require_auth();
+        write_entry(&env, &DataKey::PendingAdmin, &new_admin);
+        env.events()
+            .publish((symbol_short!("adm_prop"), admin), new_admin);
+    }
+
+    pub fn accept_admin(env: Env) -> Result<(), NftError> {
+        let new_admin: Address = read_entry(&env, &DataKey::PendingAdmin)
+            .ok_or(NftError::NoPendingAdmin)?;
+        new_admin.require_auth();
+        let previous = stored_admin(&env);
+        write_entry(&env, &DataKey::Admin, &new_admin);
+        env.storage().persistent().remove(&DataKey::PendingAdmin);
+        env.events()
+            .publish((symbol_short!("adm_xfer"), previous), new_admin);
+        Ok(())
+    }
+
+    pub fn pending_admin(env: Env) -> Option<Address> {
+        read_entry(&env, &DataKey::PendingAdmin)
+    }
+
+    pub fn grant_role(env: Env, role: Role, account: Address) -> Result<(), NftError> {
+        stored_admin(&env).require_auth();
+        if role == Role::Admin {
+            return Err(NftError::AdminRoleNotGrantable);
+        }
+        if add_role_member(&env, role, &account) {
+            env.events()
+                .publish((symbol_short!("role_grnt"), account), role);
+        }
+        Ok(())
     }
 
-    pub fn creator_of(env: Env, token_id: u64) -> Address {
-        require_existing_token(&env, token_id);
+    pub fn revoke_role(env: Env, role: Role, account: Address) -> Result<(), NftError> {
+        stored_admin(&env).require_auth();
+        if role == Role::Admin {
+            return Err(NftError::AdminRoleNotGrantable);
+        }
+        let key = DataKey::RoleMember(role, account.clone());
+        if env.storage().persistent().has(&key) {
+            env.storage().persistent().remove(&key);
+            write_entry(&env, &DataKey::RoleCount(role), &(role_count(&env, role) - 1));
+            env.events()
+                .publish((symbol_short!("role_rvk"), account), role);
+        }
+        Ok(())
+    }
+
+    pub fn has_role(env: Env, role: Role, account: Address) -> bool {
+        holds_role(&env, role, &account)
+    }
+
+    /// Swaps this contract's code for an already-uploaded wasm. Storage is left
+    /// untouched; call `migrate` afterwards if the new code bumps the schema.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) {
+        let admin: Address = read_entry(&env, &DataKey::Admin).unwrap();
+        admin.require_auth();
+        env.deployer()
+            .update_current_contract_wasm(new_wasm_hash.clone());
+        env.events()
+            .publish((symbol_short!("upgraded"),), new_wasm_hash);
+    }
+
+    /// Storage layout version. Deployments from before versioning report 0.
+    pub fn schema_version(env: Env) -> u32 {
+        read_entry(&env, &DataKey::SchemaVersion).unwrap_or(0)
+    }
+
+    /// Brings stored data from the recorded schema version up to the one this
+    /// code expects. Safe to call repeatedly; returns the resulting version.
+    pub fn migrate(env: Env) -> Result<u32, NftError> {
+        let admin: Address = read_entry(&env, &DataKey::Admin).unwrap();
+        admin.require_auth();
+        let from = Self::schema_version(env.clone());
+        if from > SCHEMA_VERSION {
+            return Err(NftError::UnsupportedSchemaVersion);
+        }
+        if from == SCHEMA_VERSION {
+            return Ok(from);
+        }
+        // v0 -> v1: layout unchanged, only the version stamp is new.
+        // v1 -> v2: single-slot minter/metadata updater become role grants.
+        if from < 2 {
+            for (legacy_key, role) in [
+                (DataKey::Minter, Role::Minter),
+                (DataKey::MetadataUpdater, Role::MetadataUpdater),
+            ] {
+                let holder: Option<Address> = read_entry(&env, &legacy_key);
+                if let Some(holder) = holder {
+                    add_role_member(&env, role, &holder);
+                    env.storage().persistent().remove(&legacy_key);
+                }
+            }
+        }
+        write_entry(&env, &DataKey::SchemaVersion, &SCHEMA_VERSION);
+        env.events()
+            .publish((symbol_short!("migrated"),), (from, SCHEMA_VERSION));
+        Ok(SCHEMA_VERSION)
+    }
+
+    fn transfer_internal(env: &Env, from: &Address, to: &Address, token_id: u64) {
+        remove_token_from_owner(env, from, token_id);
+        add_token_to_owner(env, to, token_id);
+        write_entry(env, &DataKey::TokenOwner(token_id), to);
+        // A per-token approval never survives a change of ownership.
         env.storage()
             .persistent()
-            .get(&DataKey::TokenCreator(token_id))
-            .unwrap_or_else(|| {
+            .remove(&DataKey::Approved(token_id));
+
+        env.events().publish(
+            (symbol_short!("transfer"), from.clone(), to.clone()),
+            token_id,
+        );
+    }
+
+    pub fn transfer(env: Env, from: Address, to: Address, token_id: u64) -> Result<(), NftError> {
+        from.require_auth();
+        let owner: Address = read_entry(&env, &DataKey::TokenOwner(token_id))
+            .ok_or(NftError::TokenNotFound)?;
+        if owner != from {
+            return Err(NftError::NotOwner);
+        }
+        Self::transfer_internal(&env, &from, &to, token_id);
+        Ok(())
+    }
+
+    /// Moves `token_id` from `from` to `to` on behalf of `spender`, who must be
+    /// the owner, the token's approved address, or an approved operator of the owner.
+    pub fn transfer_from(
+        env: Env,
+        spender: Address,
+        from: Address,
+        to: Address,
+        token_id: u64,
+    ) -> Result<(), NftError> {
+        spender.require_auth();
+        let owner: Address = read_entry(&env, &DataKey::TokenOwner(token_id))
+            .ok_or(NftError::TokenNotFound)?;
+        if owner != from {
+            return Err(NftError::NotOwner);
+        }
+
+        let approved: Option<Address> = read_entry(&env, &DataKey::Approved(token_id));
+        let is_approved = approved.map(|a| a == spender).unwrap_or(false);
+        if spender != owner && !is_approved && !is_operator_for(&env, &owner, &spender) {
+            return Err(NftError::NotApproved);
+        }
+
+        Self::transfer_internal(&env, &from, &to, token_id);
+        Ok(())
+    }
+
+    /// Sets (or clears, with `None`) the single address allowed to transfer
+    /// `token_id`. Callable by the owner or one of the owner's operators.
+    pub fn approve(
+        env: Env,
+        caller: Address,
+        approved: Option<Address>,
+        token_id: u64,
+    ) -> Result<(), NftError> {
+        caller.require_auth();
+        let owner: Address = read_entry(&env, &DataKey::TokenOwner(token_id))
+            .ok_or(NftError::TokenNotFound)?;
+        if caller != owner && !is_operator_for(&env, &owner, &caller) {
+            return Err(NftError::NotOwnerOrOperator);
+        }
+
+        match &approved {
+            Some(approved) => {
+                if *approved == owner {
+                    return Err(NftError::ApproveToOwner);
+                }
+                write_entry(&env, &DataKey::Approved(token_id), approved);
+            }
+            None => {
                 env.storage()
                     .persistent()
-                    .get(&DataKey::TokenOwner(token_id))
-                    .unwrap()
-            })
+                    .remove(&DataKey::Approved(token_id));
+            }
+        }
+        env.events()
+            .publish((symbol_short!("approve"), owner, token_id), approved);
+        Ok(())
     }
 
-    pub fn token_kind(env: Env, token_id: u64) -> TokenKind {
-        require_existing_token(&env, token_id);
-        env.storage()
-            .persistent()
-            .get(&DataKey::TokenKind(token_id))
-            .unwrap_or(TokenKind::Finished)
+    pub fn get_approved(env: Env, token_id: u64) -> Result<Option<Address>, NftError> {
+        require_existing_token(&env, token_id)?;
+        Ok(read_entry(&env, &DataKey::Approved(token_id)))
     }
 
-    pub fn is_commission_egg(env: Env, token_id: u64) -> bool {
-        matches!(Self::token_kind(env, token_id), TokenKind::CommissionEgg)
+    /// Grants or revokes `operator` the right to transfer and approve every token held by `owner`.
+    pub fn set_approval_for_all(
+        env: Env,
+        owner: Address,
+        operator: Address,
+        approved: bool,
+    ) -> Result<(), NftError> {
+        owner.require_auth();
+        if owner == operator {
+            return Err(NftError::SelfOperator);
+        }
+        let key = DataKey::OperatorApproval(owner.clone(), operator.clone());
+        if approved {
+            write_entry(&env, &key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+        env.events()
+            .publish((symbol_short!("appr_all"), owner, operator), approved);
+        Ok(())
     }
 
-    pub fn creator_can_update_metadata(env: Env, token_id: u64) -> bool {
-        require_existing_token(&env, token_id);
-        env.storage()
-            .persistent()
-            .get(&DataKey::CreatorCanUpdateMetadata(token_id))
-            .unwrap_or(false)
+    pub fn is_approved_for_all(env: Env, owner: Address, operator: Address) -> bool {
+        is_operator_for(&env, &owner, &operator)
+    }
+
+    pub fn owner_of(env: Env, token_id: u64) -> Result<Address, NftError> {
+        read_entry(&env, &DataKey::TokenOwner(token_id)).ok_or(NftError::TokenNotFound)
+    }
+
+    pub fn token_uri(env: Env, token_id: u64) -> Result<String, NftError> {
+        read_entry(&env, &DataKey::TokenUri(token_id)).ok_or(NftError::TokenNotFound)
+    }
+
+    pub fn creator_of(env: Env, token_id: u64) -> Result<Address, NftError> {
+        require_existing_token(&env, token_id)?;
+        Ok(read_entry(&env, &DataKey::TokenCreator(token_id)).unwrap_or_else(|| {
+                read_entry(&env, &DataKey::TokenOwner(token_id)).unwrap()
+            }))
+    }
+
+    pub fn token_kind(env: Env, token_id: u64) -> Result<TokenKind, NftError> {
+        require_existing_token(&env, token_id)?;
+        Ok(read_entry(&env, &DataKey::TokenKind(token_id)).unwrap_or(TokenKind::Finished))
+    }
+
+    /// Returns the royalty receiver (the token creator) and the amount owed on `sale_price`.
+    pub fn royalty_info(
+        env: Env,
+        token_id: u64,
+        sale_price: i128,
+    ) -> Result<(Address, i128), NftError> {
+        let creator = Self::creator_of(env.clone(), token_id)?;
+        let royalty_bps: u32 = read_entry(&env, &DataKey::TokenRoyaltyBps(token_id))
+            .unwrap_or(0u32);
+        Ok((creator, sale_price * royalty_bps as i128 / BPS_DENOMINATOR))
     }
 
+    pub fn is_commission_egg(env: Env, token_id: u64) -> Result<bool, NftError> {
+        Ok(matches!(Self::token_kind(env, token_id)?, TokenKind::CommissionEgg))
+    }
+
+    pub fn creator_can_update_metadata(env: Env, token_id: u64) -> Result<bool, NftError> {
+        require_existing_token(&env, token_id)?;
+        Ok(read_entry(&env, &DataKey::CreatorCanUpdateMetadata(token_id)).unwrap_or(false))
+    }
+
+    /// Permissionless keep-alive for every entry describing `token_id` (owner,
+    /// metadata, royalty, approval, enumeration slots) plus the contract-wide
+    /// counters. Anyone may call it; it only extends TTLs.
+    pub fn bump_token(env: Env, token_id: u64) -> Result<(), NftError> {
+        let owner: Address =
+            read_entry(&env, &DataKey::TokenOwner(token_id)).ok_or(NftError::TokenNotFound)?;
+        for key in [
+            DataKey::TokenUri(token_id),
+            DataKey::TokenCreator(token_id),
+            DataKey::TokenKind(token_id),
+            DataKey::CreatorCanUpdateMetadata(token_id),
+            DataKey::TokenRoyaltyBps(token_id),
+            DataKey::Approved(token_id),
+            DataKey::CommissionLocked(token_id),
+            DataKey::OwnerBalance(owner.clone()),
+            DataKey::Admin,
+            DataKey::NextTokenId,
+            DataKey::CirculatingSupply,
+        ] {
+            extend_entry(&env, &key);
+        }
+        let owner_index: u64 = read_entry(&env, &DataKey::OwnerTokenIndex(token_id)).unwrap();
+        extend_entry(&env, &DataKey::OwnerToken(owner, owner_index));
+        let all_index: u64 = read_entry(&env, &DataKey::AllTokensIndex(token_id)).unwrap();
+        extend_entry(&env, &DataKey::AllTokens(all_index));
+        extend_instance(&env);
+        Ok(())
+    }
+
+    /// Number of token IDs ever issued (burned IDs included). Kept for clients
+    /// that iterate `0..total_supply()`; see `circulating_supply` for live tokens.
     pub fn total_supply(env: Env) -> u64 {
-        env.storage()
-            .persistent()
-            .get(&DataKey::NextTokenId)
-            .unwrap_or(0u64)
+        Self::total_minted(env)
+    }
+
+    pub fn total_minted(env: Env) -> u64 {
+        read_entry(&env, &DataKey::NextTokenId).unwrap_or(0u64)
+    }
+
+    pub fn circulating_supply(env: Env) -> u64 {
+        circulating(&env)
+    }
+
+    pub fn balance_of(env: Env, owner: Address) -> u64 {
+        balance(&env, &owner)
+    }
+
+    /// Returns up to `limit` token IDs held by `owner`, starting at position `start`
+    /// of the owner's list. Ordering is stable until the owner sends a token away.
+    pub fn tokens_of_owner(env: Env, owner: Address, start: u64, limit: u32) -> Vec<u64> {
+        let limit = limit.min(MAX_PAGE_LIMIT) as u64;
+        let end = balance(&env, &owner).min(start.saturating_add(limit));
+        let mut tokens = Vec::new(&env);
+        for index in start..end {
+            let token_id: u64 = read_entry(&env, &DataKey::OwnerToken(owner.clone(), index))
+                .unwrap();
+            tokens.push_back(token_id);
+        }
+        tokens
+    }
+
+    /// Returns the live token at `index` of the global list (`index < circulating_supply()`).
+    pub fn token_by_index(env: Env, index: u64) -> Result<u64, NftError> {
+        read_entry(&env, &DataKey::AllTokens(index)).ok_or(NftError::IndexOutOfBounds)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use soroban_sdk::{testutils::Address as _, Env, String};
+    use soroban_sdk::{
+        testutils::{Address as _, Events, Ledger as _},
+        vec, Env, IntoVal, String,
+    };
 
     fn setup() -> (Env, ShimejiNftClient<'static>, Address) {
         let env = Env::default();
@@ -335,16 +948,16 @@ mod test {
 
     #[test]
     fn test_mint() {
-        let (env, client, _admin) = setup();
+        let (env, client, admin) = setup();
         let user = Address::generate(&env);
         let uri = String::from_str(&env, "ipfs://Qm.../metadata.json");
-        let token_id = client.mint(&user, &uri);
+        let token_id = client.mint(&admin, &user, &uri);
         assert_eq!(token_id, 0);
         assert_eq!(client.owner_of(&0), user);
         assert_eq!(client.token_uri(&0), uri);
-        assert_eq!(client.is_commission_egg(&0), false);
+        assert!(!client.is_commission_egg(&0));
         assert_eq!(client.creator_of(&0), user);
-        assert_eq!(client.creator_can_update_metadata(&0), false);
+        assert!(!client.creator_can_update_metadata(&0));
         assert_eq!(client.total_supply(), 1);
     }
 
@@ -356,9 +969,9 @@ mod test {
 
         let token_id = client.mint_commission_egg(&creator, &creator, &placeholder);
         assert_eq!(token_id, 0);
-        assert_eq!(client.is_commission_egg(&0), true);
+        assert!(client.is_commission_egg(&0));
         assert_eq!(client.creator_of(&0), creator);
-        assert_eq!(client.creator_can_update_metadata(&0), true);
+        assert!(client.creator_can_update_metadata(&0));
 
         let final_uri = String::from_str(&env, "ipfs://final-art");
         client.update_token_uri_as_creator(&creator, &0, &final_uri);
@@ -366,19 +979,20 @@ mod test {
     }
 
     #[test]
-    #[should_panic(expected = "creator metadata updates disabled")]
     fn test_creator_cannot_update_finished_token() {
-        let (env, client, _admin) = setup();
+        let (env, client, admin) = setup();
         let user = Address::generate(&env);
         let uri = String::from_str(&env, "ipfs://finished");
-        client.mint(&user, &uri);
+        client.mint(&admin, &user, &uri);
 
         let new_uri = String::from_str(&env, "ipfs://new-finished");
-        client.update_token_uri_as_creator(&user, &0, &new_uri);
+        assert_eq!(
+            client.try_update_token_uri_as_creator(&user, &0, &new_uri),
+            Err(Ok(NftError::CreatorUpdatesDisabled))
+        );
     }
 
     #[test]
-    #[should_panic(expected = "creator metadata updates disabled")]
     fn test_freeze_creator_metadata_updates() {
         let (env, client, _admin) = setup();
         let creator = Address::generate(&env);
@@ -386,10 +1000,13 @@ mod test {
         client.mint_commission_egg(&creator, &creator, &placeholder);
 
         client.freeze_creator_metadata_updates(&creator, &0);
-        assert_eq!(client.creator_can_update_metadata(&0), false);
+        assert!(!client.creator_can_update_metadata(&0));
 
         let final_uri = String::from_str(&env, "ipfs://final-art");
-        client.update_token_uri_as_creator(&creator, &0, &final_uri);
+        assert_eq!(
+            client.try_update_token_uri_as_creator(&creator, &0, &final_uri),
+            Err(Ok(NftError::CreatorUpdatesDisabled))
+        );
     }
 
     #[test]
@@ -404,18 +1021,18 @@ mod test {
 
         let user = Address::generate(&env);
         let uri = String::from_str(&env, "ipfs://Qm.../metadata.json");
-        client.mint(&user, &uri);
+        client.mint(&admin, &user, &uri);
     }
 
     #[test]
     fn test_update_uri() {
-        let (env, client, _admin) = setup();
+        let (env, client, admin) = setup();
         let user = Address::generate(&env);
         let uri = String::from_str(&env, "ipfs://old");
-        client.mint(&user, &uri);
+        client.mint(&admin, &user, &uri);
 
         let new_uri = String::from_str(&env, "ipfs://new");
-        client.update_token_uri(&0, &new_uri);
+        client.update_token_uri(&admin, &0, &new_uri);
         assert_eq!(client.token_uri(&0), new_uri);
     }
 
@@ -431,7 +1048,7 @@ mod test {
         env.mock_all_auths();
         let user = Address::generate(&env);
         let uri = String::from_str(&env, "ipfs://old");
-        client.mint(&user, &uri);
+        client.mint(&admin, &user, &uri);
 
         // Remove mock to test unauthorized
         let env2 = Env::default();
@@ -442,27 +1059,27 @@ mod test {
 
         // Simplify: test that update on non-existent token panics
         let new_uri = String::from_str(&env, "ipfs://new");
-        client.update_token_uri(&999, &new_uri);
+        client.update_token_uri(&admin, &999, &new_uri);
     }
 
     #[test]
     fn test_owner_of() {
-        let (env, client, _admin) = setup();
+        let (env, client, admin) = setup();
         let user = Address::generate(&env);
         let uri = String::from_str(&env, "ipfs://test");
-        client.mint(&user, &uri);
+        client.mint(&admin, &user, &uri);
         assert_eq!(client.owner_of(&0), user);
     }
 
     #[test]
     fn test_total_supply() {
-        let (env, client, _admin) = setup();
+        let (env, client, admin) = setup();
         assert_eq!(client.total_supply(), 0);
         let user = Address::generate(&env);
         let uri = String::from_str(&env, "ipfs://test");
-        client.mint(&user, &uri);
+        client.mint(&admin, &user, &uri);
         assert_eq!(client.total_supply(), 1);
-        client.mint(&user, &uri);
+        client.mint(&admin, &user, &uri);
         assert_eq!(client.total_supply(), 2);
     }
 
@@ -472,27 +1089,56 @@ mod test {
         let creator = Address::generate(&env);
         let uri = String::from_str(&env, "ipfs://egg-self-service");
 
-        let token_id = client.create_commission_egg(&creator, &uri);
+        let token_id = client.create_commission_egg(&creator, &uri, &0);
         assert_eq!(token_id, 0);
         assert_eq!(client.owner_of(&0), creator);
         assert_eq!(client.creator_of(&0), creator);
         assert_eq!(client.token_uri(&0), uri);
-        assert_eq!(client.is_commission_egg(&0), true);
-        assert_eq!(client.creator_can_update_metadata(&0), true);
+        assert!(client.is_commission_egg(&0));
+        assert!(client.creator_can_update_metadata(&0));
         assert_eq!(client.total_supply(), 1);
     }
 
     #[test]
-    fn test_set_minter() {
-        let (env, client, _admin) = setup();
+    fn test_granted_minter_takes_over_minting() {
+        let (env, client, admin) = setup();
         let minter = Address::generate(&env);
-        client.set_minter(&minter);
+        client.grant_role(&Role::Minter, &minter);
+        assert!(client.has_role(&Role::Minter, &minter));
 
         let user = Address::generate(&env);
         let uri = String::from_str(&env, "ipfs://minted-by-minter");
-        let token_id = client.mint(&user, &uri);
+        let token_id = client.mint(&minter, &user, &uri);
         assert_eq!(token_id, 0);
         assert_eq!(client.owner_of(&0), user);
+        assert_eq!(client.try_mint(&admin, &user, &uri), Err(Ok(NftError::MissingRole)));
+
+        // With no minter left, minting falls back to the admin.
+        client.revoke_role(&Role::Minter, &minter);
+        assert!(!client.has_role(&Role::Minter, &minter));
+        assert_eq!(client.mint(&admin, &user, &uri), 1);
+    }
+
+    #[test]
+    fn test_two_step_admin_transfer() {
+        let (env, client, admin) = setup();
+        let new_admin = Address::generate(&env);
+        assert_eq!(client.try_accept_admin(), Err(Ok(NftError::NoPendingAdmin)));
+
+        client.propose_admin(&new_admin);
+        assert_eq!(client.pending_admin(), Some(new_admin.clone()));
+        assert_eq!(client.admin(), admin);
+
+        client.accept_admin();
+        assert_eq!(env.auths()[0].0, new_admin);
+        assert_eq!(client.admin(), new_admin);
+        assert_eq!(client.pending_admin(), None);
+        assert!(client.has_role(&Role::Admin, &new_admin));
+        assert!(!client.has_role(&Role::Admin, &admin));
+        assert_eq!(
+            client.try_grant_role(&Role::Admin, &admin),
+            Err(Ok(NftError::AdminRoleNotGrantable))
+        );
     }
 
     #[test]
@@ -501,13 +1147,386 @@ mod test {
         let creator = Address::generate(&env);
         let uri = String::from_str(&env, "ipfs://finished-self-service");
 
-        let token_id = client.create_finished_nft(&creator, &uri);
+        let token_id = client.create_finished_nft(&creator, &uri, &0);
         assert_eq!(token_id, 0);
         assert_eq!(client.owner_of(&0), creator);
         assert_eq!(client.creator_of(&0), creator);
         assert_eq!(client.token_uri(&0), uri);
-        assert_eq!(client.is_commission_egg(&0), false);
-        assert_eq!(client.creator_can_update_metadata(&0), true);
+        assert!(!client.is_commission_egg(&0));
+        assert!(client.creator_can_update_metadata(&0));
         assert_eq!(client.total_supply(), 1);
     }
+
+    #[test]
+    fn test_approve_and_transfer_from() {
+        let (env, client, admin) = setup();
+        let owner = Address::generate(&env);
+        let spender = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        client.mint(&admin, &owner, &String::from_str(&env, "ipfs://approve"));
+
+        assert_eq!(client.get_approved(&0), None);
+        client.approve(&owner, &Some(spender.clone()), &0);
+        assert_eq!(client.get_approved(&0), Some(spender.clone()));
+
+        client.transfer_from(&spender, &owner, &recipient, &0);
+        assert_eq!(client.owner_of(&0), recipient);
+        // Approval is cleared once the token changes hands.
+        assert_eq!(client.get_approved(&0), None);
+    }
+
+    #[test]
+    fn test_operator_can_transfer_and_approve() {
+        let (env, client, admin) = setup();
+        let owner = Address::generate(&env);
+        let operator = Address::generate(&env);
+        let delegate = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let uri = String::from_str(&env, "ipfs://operator");
+        client.mint(&admin, &owner, &uri);
+        client.mint(&admin, &owner, &uri);
+
+        client.set_approval_for_all(&owner, &operator, &true);
+        assert!(client.is_approved_for_all(&owner, &operator));
+
+        client.transfer_from(&operator, &owner, &recipient, &0);
+        assert_eq!(client.owner_of(&0), recipient);
+
+        client.approve(&operator, &Some(delegate.clone()), &1);
+        client.transfer_from(&delegate, &owner, &recipient, &1);
+        assert_eq!(client.owner_of(&1), recipient);
+
+        client.set_approval_for_all(&owner, &operator, &false);
+        assert!(!client.is_approved_for_all(&owner, &operator));
+    }
+
+    #[test]
+    fn test_transfer_from_requires_approval() {
+        let (env, client, admin) = setup();
+        let owner = Address::generate(&env);
+        let stranger = Address::generate(&env);
+        client.mint(&admin, &owner, &String::from_str(&env, "ipfs://unapproved"));
+
+        assert_eq!(
+            client.try_transfer_from(&stranger, &owner, &stranger, &0),
+            Err(Ok(NftError::NotApproved))
+        );
+    }
+
+    #[test]
+    fn test_revoked_approval_cannot_transfer() {
+        let (env, client, admin) = setup();
+        let owner = Address::generate(&env);
+        let spender = Address::generate(&env);
+        client.mint(&admin, &owner, &String::from_str(&env, "ipfs://revoked"));
+
+        client.approve(&owner, &Some(spender.clone()), &0);
+        client.approve(&owner, &None, &0);
+        assert_eq!(
+            client.try_transfer_from(&spender, &owner, &spender, &0),
+            Err(Ok(NftError::NotApproved))
+        );
+    }
+
+    #[test]
+    fn test_balance_and_tokens_of_owner_follow_transfers() {
+        let (env, client, admin) = setup();
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        let uri = String::from_str(&env, "ipfs://enumerate");
+        client.mint(&admin, &alice, &uri);
+        client.mint(&admin, &alice, &uri);
+        client.mint(&admin, &alice, &uri);
+
+        assert_eq!(client.balance_of(&alice), 3);
+        assert_eq!(client.tokens_of_owner(&alice, &0, &10), vec![&env, 0u64, 1, 2]);
+
+        client.transfer(&alice, &bob, &0);
+        assert_eq!(client.balance_of(&alice), 2);
+        assert_eq!(client.balance_of(&bob), 1);
+        // The last token fills the slot that was vacated.
+        assert_eq!(client.tokens_of_owner(&alice, &0, &10), vec![&env, 2u64, 1]);
+        assert_eq!(client.tokens_of_owner(&bob, &0, &10), vec![&env, 0u64]);
+
+        client.transfer(&bob, &alice, &0);
+        assert_eq!(client.balance_of(&bob), 0);
+        assert_eq!(client.tokens_of_owner(&bob, &0, &10).len(), 0);
+    }
+
+    #[test]
+    fn test_tokens_of_owner_pagination() {
+        let (env, client, admin) = setup();
+        let owner = Address::generate(&env);
+        let uri = String::from_str(&env, "ipfs://paginate");
+        for _ in 0..5 {
+            client.mint(&admin, &owner, &uri);
+        }
+
+        assert_eq!(client.tokens_of_owner(&owner, &0, &2), vec![&env, 0u64, 1]);
+        assert_eq!(client.tokens_of_owner(&owner, &2, &2), vec![&env, 2u64, 3]);
+        assert_eq!(client.tokens_of_owner(&owner, &4, &2), vec![&env, 4u64]);
+        assert_eq!(client.tokens_of_owner(&owner, &9, &2).len(), 0);
+        assert_eq!(client.token_by_index(&4), 4);
+    }
+
+    #[test]
+    fn test_token_by_index_out_of_bounds() {
+        let (env, client, admin) = setup();
+        let owner = Address::generate(&env);
+        client.mint(&admin, &owner, &String::from_str(&env, "ipfs://bounds"));
+        assert_eq!(
+            client.try_token_by_index(&1),
+            Err(Ok(NftError::IndexOutOfBounds))
+        );
+    }
+
+    #[test]
+    fn test_burn_clears_token_and_updates_supplies() {
+        let (env, client, admin) = setup();
+        let owner = Address::generate(&env);
+        let uri = String::from_str(&env, "ipfs://burnable");
+        client.mint(&admin, &owner, &uri);
+        client.create_commission_egg(&owner, &uri, &0);
+        client.mint(&admin, &owner, &uri);
+
+        client.burn(&owner, &0);
+
+        assert_eq!(client.total_minted(), 3);
+        assert_eq!(client.total_supply(), 3);
+        assert_eq!(client.circulating_supply(), 2);
+        assert_eq!(client.balance_of(&owner), 2);
+        assert_eq!(client.token_by_index(&0), 2);
+        assert_eq!(client.token_by_index(&1), 1);
+        assert!(client.try_owner_of(&0).is_err());
+        assert!(client.try_token_uri(&0).is_err());
+        assert!(client.try_creator_of(&0).is_err());
+
+        // IDs are never reused after a burn.
+        assert_eq!(client.mint(&admin, &owner, &uri), 3);
+    }
+
+    #[test]
+    fn test_burn_requires_owner() {
+        let (env, client, _admin) = setup();
+        let creator = Address::generate(&env);
+        let buyer = Address::generate(&env);
+        client.create_finished_nft(&creator, &String::from_str(&env, "ipfs://sold"), &0);
+        client.transfer(&creator, &buyer, &0);
+
+        assert_eq!(
+            client.try_burn(&creator, &0),
+            Err(Ok(NftError::NotOwner))
+        );
+    }
+
+    #[test]
+    fn test_cannot_burn_egg_locked_by_commission() {
+        let (env, client, _admin) = setup();
+        let creator = Address::generate(&env);
+        let marketplace = Address::generate(&env);
+        client.create_commission_egg(&creator, &String::from_str(&env, "ipfs://egg-locked"), &0);
+
+        client.set_commission_manager(&marketplace, &true);
+        client.set_commission_lock(&marketplace, &0, &true);
+        assert!(client.is_commission_locked(&0));
+
+        assert_eq!(
+            client.try_burn(&creator, &0),
+            Err(Ok(NftError::CommissionLocked))
+        );
+    }
+
+    #[test]
+    fn test_egg_can_be_burned_after_commission_unlock() {
+        let (env, client, _admin) = setup();
+        let creator = Address::generate(&env);
+        let marketplace = Address::generate(&env);
+        client.create_commission_egg(&creator, &String::from_str(&env, "ipfs://egg-unlocked"), &0);
+
+        client.set_commission_manager(&marketplace, &true);
+        client.set_commission_lock(&marketplace, &0, &true);
+        client.set_commission_lock(&marketplace, &0, &false);
+        client.burn(&creator, &0);
+        assert_eq!(client.circulating_supply(), 0);
+    }
+
+    #[test]
+    fn test_commission_lock_requires_manager() {
+        let (env, client, _admin) = setup();
+        let creator = Address::generate(&env);
+        client.create_commission_egg(&creator, &String::from_str(&env, "ipfs://egg-griefed"), &0);
+
+        assert_eq!(
+            client.try_set_commission_lock(&creator, &0, &true),
+            Err(Ok(NftError::NotCommissionManager))
+        );
+    }
+
+    #[test]
+    fn test_royalty_info_pays_creator_after_resale() {
+        let (env, client, admin) = setup();
+        let creator = Address::generate(&env);
+        let collector = Address::generate(&env);
+        let uri = String::from_str(&env, "ipfs://royalty");
+        client.create_finished_nft(&creator, &uri, &500);
+        client.transfer(&creator, &collector, &0);
+
+        assert_eq!(client.royalty_info(&0, &1000_0000000), (creator.clone(), 50_0000000));
+
+        // Admin mints carry no royalty.
+        client.mint(&admin, &collector, &uri);
+        assert_eq!(client.royalty_info(&1, &1000_0000000), (collector, 0));
+    }
+
+    #[test]
+    fn test_minted_edition_credits_its_creator() {
+        let (env, client, admin) = setup();
+        let creator = Address::generate(&env);
+        let winner = Address::generate(&env);
+        let uri = String::from_str(&env, "ipfs://drop-edition");
+        client.mint_edition(&admin, &winner, &uri, &creator, &750);
+
+        assert_eq!(client.owner_of(&0), winner);
+        assert_eq!(client.creator_of(&0), creator);
+        assert!(!client.is_commission_egg(&0));
+        assert!(!client.creator_can_update_metadata(&0));
+        assert_eq!(client.royalty_info(&0, &1000_0000000), (creator.clone(), 75_0000000));
+        assert_eq!(
+            client.try_mint_edition(&admin, &winner, &uri, &creator, &1_001),
+            Err(Ok(NftError::RoyaltyTooHigh))
+        );
+    }
+
+    #[test]
+    fn test_royalty_bps_is_capped() {
+        let (env, client, _admin) = setup();
+        let creator = Address::generate(&env);
+        assert_eq!(
+            client.try_create_commission_egg(
+                &creator,
+                &String::from_str(&env, "ipfs://greedy"),
+                &1_001
+            ),
+            Err(Ok(NftError::RoyaltyTooHigh))
+        );
+    }
+
+    #[test]
+    fn test_mint_and_transfer_emit_events() {
+        let (env, client, _admin) = setup();
+        let creator = Address::generate(&env);
+        let collector = Address::generate(&env);
+        let uri = String::from_str(&env, "ipfs://evented");
+
+        client.create_finished_nft(&creator, &uri, &0);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    (symbol_short!("mint"), creator.clone()).into_val(&env),
+                    (0u64, creator.clone(), TokenKind::Finished).into_val(&env),
+                ),
+            ]
+        );
+
+        client.transfer(&creator, &collector, &0);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    (symbol_short!("transfer"), creator.clone(), collector.clone()).into_val(&env),
+                    0u64.into_val(&env),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_metadata_update_emits_event() {
+        let (env, client, _admin) = setup();
+        let creator = Address::generate(&env);
+        client.create_commission_egg(&creator, &String::from_str(&env, "ipfs://egg"), &0);
+
+        let final_uri = String::from_str(&env, "ipfs://final-art");
+        client.update_token_uri_as_creator(&creator, &0, &final_uri);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                (
+                    client.address.clone(),
+                    (symbol_short!("uri_upd"), 0u64).into_val(&env),
+                    final_uri.into_val(&env),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_migrate_stamps_schema_version() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let contract_id = env.register(ShimejiNft, ());
+        let client = ShimejiNftClient::new(&env, &contract_id);
+        let admin = Address::generate(&env);
+        client.initialize(&admin);
+        assert_eq!(client.schema_version(), SCHEMA_VERSION);
+
+        // Deployments from before versioning have no stamp at all.
+        env.as_contract(&contract_id, || {
+            env.storage().persistent().remove(&DataKey::SchemaVersion);
+        });
+        assert_eq!(client.schema_version(), 0);
+        assert_eq!(client.migrate(), SCHEMA_VERSION);
+        assert_eq!(client.migrate(), SCHEMA_VERSION);
+        assert_eq!(client.schema_version(), SCHEMA_VERSION);
+
+        env.as_contract(&contract_id, || {
+            env.storage()
+                .persistent()
+                .set(&DataKey::SchemaVersion, &(SCHEMA_VERSION + 1));
+        });
+        assert_eq!(client.try_migrate(), Err(Ok(NftError::UnsupportedSchemaVersion)));
+    }
+
+    #[test]
+    fn test_migrate_folds_legacy_roles_into_grants() {
+        let (env, client, admin) = setup();
+        let minter = Address::generate(&env);
+        env.as_contract(&client.address, || {
+            env.storage().persistent().set(&DataKey::Minter, &minter);
+            env.storage().persistent().set(&DataKey::SchemaVersion, &1u32);
+        });
+
+        assert_eq!(client.migrate(), SCHEMA_VERSION);
+        assert!(client.has_role(&Role::Minter, &minter));
+        assert!(!client.has_role(&Role::MetadataUpdater, &minter));
+
+        let uri = String::from_str(&env, "ipfs://post-migration");
+        assert_eq!(client.mint(&minter, &admin, &uri), 0);
+        assert_eq!(client.try_mint(&admin, &admin, &uri), Err(Ok(NftError::MissingRole)));
+    }
+
+    #[test]
+    fn test_bumped_token_outlives_default_ttl() {
+        let (env, client, admin) = setup();
+        let owner = Address::generate(&env);
+        let uri = String::from_str(&env, "ipfs://long-lived");
+        client.mint(&admin, &owner, &uri);
+
+        // Far past the network's minimum persistent TTL, inside one bump.
+        env.ledger().with_mut(|li| li.sequence_number += 20 * DAY_IN_LEDGERS);
+        client.bump_token(&0);
+        env.ledger().with_mut(|li| li.sequence_number += 20 * DAY_IN_LEDGERS);
+
+        assert_eq!(client.owner_of(&0), owner);
+        assert_eq!(client.token_uri(&0), uri);
+        assert_eq!(client.tokens_of_owner(&owner, &0, &10), vec![&env, 0u64]);
+        assert_eq!(client.token_by_index(&0), 0);
+        assert_eq!(client.try_bump_token(&7), Err(Ok(NftError::TokenNotFound)));
+    }
 }
@@ -652,7 +652,7 @@ mint_initial_item_auction_token_pre_minter_if_enabled() {
       --source "$IDENTITY" \
       --rpc-url "$RPC_URL" \
       --network-passphrase "$PASSPHRASE" \
-      -- mint --to "$ADMIN" --token_uri "$INITIAL_AUCTION_TOKEN_URI")"; then
+      -- mint --minter "$ADMIN" --to "$ADMIN" --token_uri "$INITIAL_AUCTION_TOKEN_URI")"; then
     INITIAL_AUCTION_STATUS="failed-mint-item-token"
     return
   fi
@@ -1987,7 +1987,7 @@ mint_marketplace_showcase_tokens_pre_minter_if_enabled() {
       --source "$IDENTITY" \
       --rpc-url "$RPC_URL" \
       --network-passphrase "$PASSPHRASE" \
-      -- mint --to "$ADMIN" --token_uri "$SHOWCASE_SALE_TOKEN_URI")"; then
+      -- mint --minter "$ADMIN" --to "$ADMIN" --token_uri "$SHOWCASE_SALE_TOKEN_URI")"; then
     MARKETPLACE_SHOWCASE_STATUS="failed-mint-sale"
     return
   fi
@@ -1999,7 +1999,7 @@ mint_marketplace_showcase_tokens_pre_minter_if_enabled() {
       --source "$IDENTITY" \
       --rpc-url "$RPC_URL" \
       --network-passphrase "$PASSPHRASE" \
-      -- mint --to "$ADMIN" --token_uri "$SHOWCASE_SALE_TOKEN_URI")"; then
+      -- mint --minter "$ADMIN" --to "$ADMIN" --token_uri "$SHOWCASE_SALE_TOKEN_URI")"; then
     MARKETPLACE_SHOWCASE_STATUS="failed-mint-sale-copy"
     return
   fi
@@ -2011,7 +2011,7 @@ mint_marketplace_showcase_tokens_pre_minter_if_enabled() {
       --source "$IDENTITY" \
       --rpc-url "$RPC_URL" \
       --network-passphrase "$PASSPHRASE" \
-      -- mint --to "$ADMIN" --token_uri "$SHOWCASE_SWAP_TOKEN_URI")"; then
+      -- mint --minter "$ADMIN" --to "$ADMIN" --token_uri "$SHOWCASE_SWAP_TOKEN_URI")"; then
     MARKETPLACE_SHOWCASE_STATUS="failed-mint-swap"
     return
   fi
@@ -2023,7 +2023,7 @@ mint_marketplace_showcase_tokens_pre_minter_if_enabled() {
       --source "$IDENTITY" \
       --rpc-url "$RPC_URL" \
       --network-passphrase "$PASSPHRASE" \
-      -- mint --to "$ADMIN" --token_uri "$SHOWCASE_SWAP_TARGET_TOKEN_URI")"; then
+      -- mint --minter "$ADMIN" --to "$ADMIN" --token_uri "$SHOWCASE_SWAP_TARGET_TOKEN_URI")"; then
     MARKETPLACE_SHOWCASE_STATUS="failed-mint-swap-target"
     return
   fi
@@ -2090,7 +2090,8 @@ seed_marketplace_showcase_examples_if_enabled() {
       --seller "$ADMIN" \
       --token_id "$SHOWCASE_SALE_TOKEN_ID" \
       --price "$list_price_xlm_stroops" \
-      --currency '"Xlm"')"; then
+      --currency '"Xlm"' \
+      --expires_at 0)"; then
     MARKETPLACE_SHOWCASE_STATUS="failed-list-sale"
     return
   fi
@@ -2248,8 +2249,8 @@ deploy_contracts() {
     --source "$IDENTITY" \
     --rpc-url "$RPC_URL" \
     --network-passphrase "$PASSPHRASE" \
-    -- set_minter --minter "$AUCTION_ID"
-  wait_for_network_propagation_step "ShimejiNFT set_minter"
+    -- grant_role --role Minter --account "$AUCTION_ID"
+  wait_for_network_propagation_step "ShimejiNFT grant_role Minter"
 
   MARKETPLACE_ID="$(deploy_from_hash "ShimejiMarketplace" "$marketplace_hash")"
   wait_for_network_propagation_step "ShimejiMarketplace deploy"
@@ -2267,6 +2268,16 @@ deploy_contracts() {
     --xlm_token "$XLM_TOKEN"
   wait_for_network_propagation_step "ShimejiMarketplace initialize"
 
+  echo "==> Allowing marketplace to lock commission eggs on the NFT contract..."
+  run_with_deploy_retries "Set NFT commission manager" \
+    stellar contract invoke \
+    --id "$NFT_ID" \
+    --source "$IDENTITY" \
+    --rpc-url "$RPC_URL" \
+    --network-passphrase "$PASSPHRASE" \
+    -- set_commission_manager --manager "$MARKETPLACE_ID" --allowed true
+  wait_for_network_propagation_step "ShimejiNFT set_commission_manager"
+
   if [ "$ENABLE_TRUSTLESS_ESCROW" = "1" ]; then
     if [ -n "${ESCROW_VAULT_ID:-}" ]; then
       echo "==> Granting marketplace contract escrow-vault operator access (commission escrow payouts/refunds)..."
@@ -2276,7 +2287,7 @@ deploy_contracts() {
         --source "$IDENTITY" \
         --rpc-url "$RPC_URL" \
         --network-passphrase "$PASSPHRASE" \
-        -- set_operator --operator "$MARKETPLACE_ID"
+        -- grant_role --role Operator --account "$MARKETPLACE_ID"
       wait_for_network_propagation_step "Escrow vault operator configuration"
 
       echo "==> Configuring marketplace commission escrow to use Trustless escrow destinations..."
@@ -2420,7 +2431,8 @@ create_initial_auction_if_enabled() {
     --token_id "$INITIAL_AUCTION_TOKEN_ID" \
     --starting_price "$INITIAL_AUCTION_STARTING_PRICE" \
     --currency "$currency_enum" \
-    --duration_seconds "$duration_seconds" 2>&1)" || {
+    --duration_seconds "$duration_seconds" \
+    --options '{"reserve_price":null,"reserve_hidden":false,"buy_now_price":null}' 2>&1)" || {
       INITIAL_AUCTION_STATUS="failed-create"
       echo "==> Failed to create initial item auction:"
       echo "$create_output"
@@ -2798,7 +2810,8 @@ print_success_summary() {
     echo "     --token_id <token_id_you_own> \\"
     echo "     --starting_price 5000000000 \\"
     echo "     --currency '\"Xlm\"' \\"
-    echo "     --duration_seconds 86400"
+    echo "     --duration_seconds 86400 \\"
+    echo "     --options '{\"reserve_price\":null,\"reserve_hidden\":false,\"buy_now_price\":null}'"
     echo ""
   fi
   echo "2) Confirm the auction contract has at least one auction:"